@@ -1,8 +1,11 @@
 // src-tauri/src/app_state.rs
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 use anyhow::{Result, anyhow};
 use uuid::Uuid;
@@ -17,6 +20,253 @@ pub struct FavoriteItem {
     pub file_size: Option<String>,
     pub file_list: Vec<String>,
     pub created_at: String, // ISO 8601 格式
+    #[serde(default)]
+    pub user_tags: Vec<String>, // 用户自定义标签
+    #[serde(default)]
+    pub notes: Option<String>, // 用户备注
+    #[serde(default)]
+    pub folder_id: Option<String>, // 所在收藏文件夹，None 表示根目录
+    #[serde(default)]
+    pub original_magnet_link: Option<String>, // 规范化前的磁力链接，与 magnet_link 相同时为 None
+    #[serde(default)]
+    pub downloaded: bool, // 是否已下载
+    #[serde(default)]
+    pub downloaded_at: Option<String>, // 标记为已下载的时间，ISO 8601 格式
+    #[serde(default)]
+    pub link_dead: bool, // 磁力链接是否已被标记为失效
+}
+
+/// 收藏文件夹，通过 parent_id 组成树形结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteFolder {
+    pub id: String,
+    pub name: String,
+    /// 上级文件夹，None 表示位于根目录
+    #[serde(default)]
+    pub parent_id: Option<String>,
+}
+
+/// 删除文件夹时如何处理其中的收藏和子文件夹
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderDeleteMode {
+    /// 将收藏和子文件夹移到被删除文件夹的上级
+    Reparent,
+    /// 连同子文件夹及其中的收藏一起删除
+    DeleteContents,
+}
+
+/// 批量删除收藏时的筛选条件，所有已设置的条件需同时满足
+///
+/// 没有设置任何条件时不匹配任何收藏，避免误删整个收藏夹。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FavoriteFilter {
+    /// 包含该用户标签（不区分大小写）
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// 收藏时间不早于该时间
+    #[serde(default)]
+    pub added_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// 收藏时间早于该时间
+    #[serde(default)]
+    pub added_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// 资源类型，由标题和文件列表推断
+    #[serde(default)]
+    pub content_type: Option<crate::filter::ContentType>,
+    /// 链接是否已标记为失效
+    #[serde(default)]
+    pub link_dead: Option<bool>,
+}
+
+impl FavoriteFilter {
+    fn is_empty(&self) -> bool {
+        self.tag.is_none() && self.added_after.is_none() && self.added_before.is_none()
+            && self.content_type.is_none()
+            && self.link_dead.is_none()
+    }
+
+    /// 收藏时间无法解析的条目不匹配任何时间条件
+    fn matches(&self, item: &FavoriteItem) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if let Some(tag) = &self.tag {
+            if !item.user_tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())) {
+                return false;
+            }
+        }
+        if self.added_after.is_some() || self.added_before.is_some() {
+            let Ok(created_at) = chrono::DateTime::parse_from_rfc3339(&item.created_at) else {
+                return false;
+            };
+            if self.added_after.is_some_and(|after| created_at < after) {
+                return false;
+            }
+            if self.added_before.is_some_and(|before| created_at >= before) {
+                return false;
+            }
+        }
+        if self.content_type.is_some_and(|content_type| {
+            crate::filter::classify_content_type(&item.title, &item.file_list) != content_type
+        }) {
+            return false;
+        }
+        self.link_dead.is_none_or(|link_dead| item.link_dead == link_dead)
+    }
+}
+
+/// 合并重复收藏时保留哪一条作为主记录
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeStrategy {
+    /// 保留最早添加的收藏
+    Oldest,
+    /// 保留最近添加的收藏
+    Newest,
+    /// 保留标题信息最完整（最长）的收藏
+    LongestTitle,
+}
+
+/// 可单独恢复默认值的设置分区
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsSection {
+    /// LLM 配置
+    Llm,
+    /// 搜索设置
+    Search,
+    /// 搜索引擎列表
+    Engines,
+}
+
+/// 自动标签规则的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoTagMatch {
+    /// 标题包含指定文本（不区分大小写）
+    #[default]
+    Substring,
+    /// 标题匹配正则表达式
+    Regex,
+}
+
+/// 添加收藏时按标题自动打标签的规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTagRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub match_type: AutoTagMatch,
+    pub tags: Vec<String>,
+}
+
+impl AutoTagRule {
+    /// 判断标题是否命中规则；无效的正则视为不命中
+    pub fn matches(&self, title: &str) -> bool {
+        match self.match_type {
+            AutoTagMatch::Substring => {
+                !self.pattern.is_empty() && title.to_lowercase().contains(&self.pattern.to_lowercase())
+            }
+            AutoTagMatch::Regex => regex::Regex::new(&self.pattern)
+                .map(|re| re.is_match(title))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// 按顺序应用所有命中的规则，返回去重后的标签
+pub fn apply_auto_tag_rules(rules: &[AutoTagRule], title: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.matches(title)) {
+        for tag in &rule.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+    tags
+}
+
+/// 搜索引擎的长期统计，按引擎名称记录，跨越多次搜索累计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineStatistics {
+    /// 参与过的搜索次数
+    pub total_searches: u64,
+    /// 至少有一个页面请求成功的搜索次数
+    pub successful_searches: u64,
+    /// 平均每次搜索返回的结果数（失败的搜索按 0 计）
+    pub avg_results: f64,
+    /// 最近若干次搜索的平均请求耗时（毫秒），最旧的在前
+    #[serde(default)]
+    pub recent_latencies_ms: Vec<u64>,
+    /// 返回过结果的搜索次数
+    #[serde(default)]
+    pub productive_searches: u64,
+    /// 请求成功、页面接近正常结果页大小却没有解析出任何结果的搜索次数
+    #[serde(default)]
+    pub empty_parse_searches: u64,
+    /// 自上次返回结果以来，连续从正常大小的页面中解析不出结果的搜索次数
+    #[serde(default)]
+    pub consecutive_empty_parses: u64,
+    /// 返回结果的页面的平均响应体字节数
+    #[serde(default)]
+    pub typical_body_bytes: u64,
+    /// 曾经有结果的引擎连续多次从正常大小的页面中解析不出结果，页面结构可能已经变化；再次返回结果时清除
+    #[serde(default)]
+    pub parse_warning: bool,
+}
+
+/// 每个引擎保留的最近耗时记录数
+const LATENCY_HISTORY_LEN: usize = 20;
+
+/// 连续多少次搜索解析为空后才发出解析警告，单次没有结果多半只是关键词冷门
+const PARSE_WARNING_STREAK: u64 = 3;
+
+impl EngineStatistics {
+    /// 记录一次搜索的结果与平均请求耗时
+    fn record(&mut self, succeeded: bool, results: usize, latency_ms: u64) {
+        self.total_searches += 1;
+        if succeeded {
+            self.successful_searches += 1;
+        }
+        self.avg_results += (results as f64 - self.avg_results) / self.total_searches as f64;
+
+        self.recent_latencies_ms.push(latency_ms);
+        if self.recent_latencies_ms.len() > LATENCY_HISTORY_LEN {
+            self.recent_latencies_ms.remove(0);
+        }
+    }
+
+    /// 记录一次搜索的解析情况，返回是否新出现解析警告
+    ///
+    /// 解析为空的页面明显小于该站点正常结果页时（通常是站点自己的“无结果”页面）不计入。
+    fn record_parse_outcome(&mut self, metric: &crate::searcher::ProviderMetrics) -> bool {
+        if metric.results_count > 0 {
+            self.productive_searches += 1;
+            if metric.productive_pages > 0 {
+                let page_bytes = metric.productive_body_bytes / u64::from(metric.productive_pages);
+                let searches = self.productive_searches;
+                self.typical_body_bytes = (self.typical_body_bytes * (searches - 1) + page_bytes) / searches;
+            }
+            self.consecutive_empty_parses = 0;
+            self.parse_warning = false;
+            return false;
+        }
+        if metric.empty_parses == 0 || (metric.empty_parse_bytes as u64) * 2 < self.typical_body_bytes {
+            return false;
+        }
+        self.empty_parse_searches += 1;
+        self.consecutive_empty_parses += 1;
+        let raised = self.productive_searches > 0 && self.consecutive_empty_parses >= PARSE_WARNING_STREAK && !self.parse_warning;
+        self.parse_warning |= raised;
+        raised
+    }
+
+    /// 最近耗时的中位数，没有记录时为 None
+    pub fn median_latency_ms(&self) -> Option<u64> {
+        let mut latencies = self.recent_latencies_ms.clone();
+        latencies.sort_unstable();
+        latencies.get(latencies.len() / 2).copied()
+    }
 }
 
 /// 搜索引擎配置
@@ -27,6 +277,43 @@ pub struct SearchEngine {
     pub url_template: String, // 包含 {keyword} 和 {page} 占位符
     pub is_enabled: bool,
     pub is_deletable: bool, // 默认引擎不可删除
+    /// URL 中的分类代码（目前仅 clmclm.com 使用）
+    #[serde(default)]
+    pub category_code: Option<String>,
+    /// URL 中的排序代码（目前仅 clmclm.com 使用）
+    #[serde(default)]
+    pub sort_code: Option<String>,
+    /// 结果页解析选择器（目前仅 clmclm.com 使用），为 None 时使用默认值
+    #[serde(default)]
+    pub selectors: Option<crate::searcher::ClmclmSelectors>,
+    /// 是否使用 AI 从结果页提取（仅自定义引擎），关闭时只使用基础解析
+    #[serde(default = "default_use_ai")]
+    pub use_ai: bool,
+    /// 自定义引擎结果页的 CSS 选择器，配置后优先于 AI 和启发式解析
+    #[serde(default)]
+    pub generic_selectors: Option<crate::searcher::GenericSelectors>,
+    /// 该引擎附加的请求头，覆盖同名的全局请求头
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 连续失败的搜索次数，任意一次成功后清零
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// 自定义引擎为 JSON 接口时的字段映射，配置后不再解析 HTML
+    #[serde(default)]
+    pub json_api: Option<crate::searcher::JsonApiMapping>,
+    /// 自定义引擎为 Torznab 接口（URL 模板为接口地址）时的配置
+    #[serde(default)]
+    pub torznab: Option<crate::searcher::TorznabConfig>,
+    /// 自定义引擎解析结果页的策略顺序，依次尝试直到得到结果
+    #[serde(default = "crate::searcher::default_parse_order")]
+    pub parse_order: Vec<crate::searcher::ParseStrategy>,
+    /// 需要登录的自定义引擎的登录表单与成功判断方式
+    #[serde(default)]
+    pub auth: Option<crate::searcher::EngineAuth>,
+}
+
+fn default_use_ai() -> bool {
+    true
 }
 
 /// 优先关键词
@@ -45,12 +332,69 @@ pub struct SingleLlmConfig {
     pub model: String,
     #[serde(default = "default_batch_size")]
     pub batch_size: u32,
+    /// 单独分析一个项目的时限（秒），为 0 时不限制
+    #[serde(default = "crate::llm_service::default_single_item_timeout_secs")]
+    pub single_item_timeout_secs: u64,
+    /// 该阶段同时进行的 LLM 请求上限，提取与分析各自独立；为 None 时提取不限制，分析逐批进行
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// 第一阶段提取时发送给模型的 HTML 最大字符数，只用于提取配置，为 None 时使用默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_html_chars: Option<usize>,
+    #[serde(default)]
+    pub batch_retry_strategy: BatchRetryStrategy,
+    #[serde(default)]
+    pub failure: AnalysisFailureConfig,
 }
 
 fn default_batch_size() -> u32 {
     5
 }
 
+/// 每批发送给 LLM 的条目数允许范围，过大时容易超出模型的 token 上限
+pub const MIN_BATCH_SIZE: u32 = 1;
+pub const MAX_BATCH_SIZE: u32 = 50;
+
+/// 将批量大小限制在允许范围内
+pub fn clamp_batch_size(batch_size: u32) -> u32 {
+    batch_size.clamp(MIN_BATCH_SIZE, MAX_BATCH_SIZE)
+}
+
+/// 批量分析失败后的重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchRetryStrategy {
+    /// 逐个分析失败批次中的每一项
+    #[default]
+    PerItem,
+    /// 将失败批次二分后重试，直到定位到出错的单个项目
+    BinarySplit,
+}
+
+/// 分析失败的项目写入结果时使用的分数、标签与回退标题
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisFailureConfig {
+    /// 失败项目的分数；为 None 时不给出分数，便于界面单独展示
+    #[serde(default)]
+    pub score: Option<u8>,
+    /// 自定义失败标签；为 None 时使用当前语言的默认文案
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// AI 没有给出精简标题时，由原始标题生成回退标题依次执行的清理步骤
+    #[serde(default = "crate::filter::default_title_cleaning_steps")]
+    pub fallback_title_steps: Vec<crate::filter::TitleCleaningStep>,
+}
+
+impl Default for AnalysisFailureConfig {
+    fn default() -> Self {
+        Self {
+            score: None,
+            tag: None,
+            fallback_title_steps: crate::filter::default_title_cleaning_steps(),
+        }
+    }
+}
+
 impl Default for SingleLlmConfig {
     fn default() -> Self {
         Self {
@@ -59,6 +403,11 @@ impl Default for SingleLlmConfig {
             api_base: "https://generativelanguage.googleapis.com".to_string(),
             model: "gemini-2.5-flash".to_string(),
             batch_size: default_batch_size(),
+            single_item_timeout_secs: crate::llm_service::default_single_item_timeout_secs(),
+            max_concurrency: None,
+            max_html_chars: None,
+            batch_retry_strategy: BatchRetryStrategy::default(),
+            failure: AnalysisFailureConfig::default(),
         }
     }
 }
@@ -68,25 +417,21 @@ impl Default for SingleLlmConfig {
 pub struct LlmConfig {
     pub extraction_config: SingleLlmConfig,  // 第一次API调用：从HTML提取基础信息
     pub analysis_config: SingleLlmConfig,    // 第二次API调用：分析分数和标签
+    /// 覆盖内置模型价格，用于估算分析成本
+    #[serde(default)]
+    pub price_table: Vec<crate::llm_service::ModelPrice>,
 }
 
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
-            extraction_config: SingleLlmConfig {
-                provider: "gemini".to_string(),
-                api_key: "".to_string(),
-                api_base: "https://generativelanguage.googleapis.com".to_string(),
-                model: "gemini-2.5-flash".to_string(),
-                batch_size: default_batch_size(),
-            },
+            extraction_config: SingleLlmConfig::default(),
+            // 分析阶段的请求多而小，默认使用更便宜的模型
             analysis_config: SingleLlmConfig {
-                provider: "gemini".to_string(),
-                api_key: "".to_string(),
-                api_base: "https://generativelanguage.googleapis.com".to_string(),
                 model: "gemini-2.5-flash-lite".to_string(),
-                batch_size: default_batch_size(),
+                ..SingleLlmConfig::default()
             },
+            price_table: Vec::new(),
         }
     }
 }
@@ -101,6 +446,126 @@ pub struct SearchSettings {
     /// 是否显示调试区域（设置页顶部）
     #[serde(default)]
     pub show_debug_area: bool,
+    /// 是否访问详情页补全真实的文件列表和大小
+    #[serde(default)]
+    pub deep_enrich: bool,
+    /// 详情页补全的最大并发请求数
+    #[serde(default = "default_enrich_concurrency")]
+    pub enrich_concurrency: usize,
+    /// 单条结果详情页补全的时限（秒），超时的结果保留原有文件列表；为 None 时不限制
+    #[serde(default = "default_enrich_timeout_secs")]
+    pub enrich_timeout_secs: Option<u64>,
+    /// 是否丢弃没有详情页链接（source_url）的结果
+    #[serde(default)]
+    pub require_source_url: bool,
+    /// 整个搜索的总时限（秒），到期后返回已收集的结果；为 None 时不限制
+    #[serde(default = "default_search_deadline_secs")]
+    pub search_deadline_secs: Option<u64>,
+    /// 域名解析时优先使用 IPv6 地址
+    #[serde(default)]
+    pub prefer_ipv6: bool,
+    /// DNS-over-HTTPS 解析地址，为 None 时使用系统 DNS
+    #[serde(default)]
+    pub doh_resolver: Option<String>,
+    /// 每次请求搜索引擎前的最大随机等待时间（毫秒），为 0 时不等待
+    #[serde(default)]
+    pub request_jitter_ms: u64,
+    /// 单个页面响应体的最大字节数，超出后放弃该页面
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+    /// AI 提取到空标题时从磁力链接推导标题还是丢弃该结果
+    #[serde(default)]
+    pub empty_title_policy: crate::searcher::EmptyTitlePolicy,
+    /// 每个搜索引擎单页最多解析的结果数，为 None 时不限制
+    #[serde(default)]
+    pub max_results_per_page: Option<usize>,
+    /// 质量分中做种数、大小和纯净度的权重
+    #[serde(default)]
+    pub quality_weights: crate::filter::QualityWeights,
+    /// 按标题相似度合并重复资源的阈值（0.5-1.0），为 None 时不启用
+    #[serde(default)]
+    pub fuzzy_dedupe_threshold: Option<f64>,
+    /// 单次搜索最多请求的页数，超出的请求会被截断
+    #[serde(default = "default_max_pages_limit")]
+    pub max_pages_limit: u32,
+    /// 所有搜索请求附带的请求头，默认只发送 DNT 与精简的 Accept-Language
+    #[serde(default = "crate::net::privacy_default_headers")]
+    pub default_headers: BTreeMap<String, String>,
+    /// 引擎连续失败达到该次数后自动禁用，为 None 时不自动禁用
+    #[serde(default)]
+    pub auto_disable_after_failures: Option<u32>,
+    /// AI 提取结果的最低可信度（0.0-1.0），为 None 时不过滤
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    /// 最低做种数，为 None 时不过滤
+    #[serde(default)]
+    pub min_seeders: Option<u32>,
+    /// 启用最低做种数时是否保留做种数未知的结果
+    #[serde(default = "default_keep_unknown_seeders")]
+    pub keep_unknown_seeders: bool,
+    /// 禁止搜索的关键词（大小写不敏感），命中时搜索命令直接拒绝
+    #[serde(default)]
+    pub forbidden_keywords: Vec<String>,
+    /// 禁止关键词按完整单词匹配，关闭时按子串匹配
+    #[serde(default)]
+    pub forbidden_keywords_whole_word: bool,
+    /// 结果标题的最大字符数，超出部分截断为省略号（完整标题保留在原始标题中）
+    #[serde(default = "default_max_title_length")]
+    pub max_title_length: usize,
+    /// 是否按统一格式重新显示结果大小（关闭时保留各站点原有的写法）
+    #[serde(default)]
+    pub normalize_file_sizes: bool,
+    /// 统一显示大小时保留的小数位数
+    #[serde(default = "default_size_precision")]
+    pub size_precision: usize,
+    /// 启用的可疑资源检查项（夹带可执行文件、大小与画质不符、加密压缩包）
+    #[serde(default = "crate::filter::default_suspicion_checks")]
+    pub suspicion_checks: Vec<crate::filter::SuspicionCheck>,
+    /// `search_multi_page`、`search_clmclm_first`、`search_other_engines` 一次返回给前端的结果数，
+    /// 其余结果通过 `get_next_result_page` 分页取回；为 None 时一次返回全部
+    #[serde(default)]
+    pub results_page_size: Option<usize>,
+    /// 严格校验磁力链接：infohash 不是40位十六进制或32位 base32 的结果直接丢弃
+    #[serde(default)]
+    pub strict_magnet_validation: bool,
+    /// 一次搜索合并、排序后最多返回的结果数，超出的部分直接丢弃；为 None 时不限制
+    #[serde(default)]
+    pub max_total_results: Option<usize>,
+    /// 只保留标题文字属于这些语言的结果（见 `filter::filter_by_language`）；为空时不过滤
+    #[serde(default)]
+    pub allowed_languages: Vec<crate::filter::Lang>,
+}
+
+fn default_search_deadline_secs() -> Option<u64> {
+    Some(60)
+}
+
+fn default_enrich_concurrency() -> usize {
+    crate::searcher::DEFAULT_ENRICH_CONCURRENCY
+}
+
+fn default_enrich_timeout_secs() -> Option<u64> {
+    Some(crate::searcher::DEFAULT_ENRICH_TIMEOUT.as_secs())
+}
+
+fn default_keep_unknown_seeders() -> bool {
+    true
+}
+
+fn default_size_precision() -> usize {
+    crate::filter::DEFAULT_SIZE_PRECISION
+}
+
+fn default_max_title_length() -> usize {
+    crate::searcher::DEFAULT_MAX_TITLE_LENGTH
+}
+
+fn default_max_pages_limit() -> u32 {
+    20
+}
+
+fn default_max_response_bytes() -> u64 {
+    crate::net::DEFAULT_MAX_RESPONSE_BYTES
 }
 
 impl Default for SearchSettings {
@@ -111,6 +576,35 @@ impl Default for SearchSettings {
             sort_by: "score".to_string(),
             title_must_contain_keyword: true,
             show_debug_area: false,
+            deep_enrich: false,
+            enrich_concurrency: default_enrich_concurrency(),
+            enrich_timeout_secs: default_enrich_timeout_secs(),
+            require_source_url: false,
+            search_deadline_secs: default_search_deadline_secs(),
+            prefer_ipv6: false,
+            doh_resolver: None,
+            request_jitter_ms: 0,
+            max_response_bytes: default_max_response_bytes(),
+            empty_title_policy: crate::searcher::EmptyTitlePolicy::default(),
+            max_results_per_page: None,
+            quality_weights: crate::filter::QualityWeights::default(),
+            fuzzy_dedupe_threshold: None,
+            max_pages_limit: default_max_pages_limit(),
+            default_headers: crate::net::privacy_default_headers(),
+            auto_disable_after_failures: None,
+            min_confidence: None,
+            min_seeders: None,
+            keep_unknown_seeders: default_keep_unknown_seeders(),
+            forbidden_keywords: Vec::new(),
+            forbidden_keywords_whole_word: false,
+            max_title_length: default_max_title_length(),
+            normalize_file_sizes: false,
+            size_precision: default_size_precision(),
+            suspicion_checks: crate::filter::default_suspicion_checks(),
+            results_page_size: None,
+            strict_magnet_validation: false,
+            max_total_results: None,
+            allowed_languages: Vec::new(),
         }
     }
 }
@@ -121,6 +615,12 @@ pub struct DownloadConfig {
     pub custom_app_path: Option<String>, // 自定义应用程序路径
     pub enable_quick_download: bool, // 是否启用快速下载按钮
     pub auto_close_page: bool, // 是否自动关闭下载页面
+    /// 导出和打开磁力链接时追加的 tracker，已存在的不会重复追加
+    #[serde(default)]
+    pub extra_trackers: Vec<String>,
+    /// 收藏时也把追加 tracker 后的磁力链接保存下来（默认只在导出和打开时追加）
+    #[serde(default)]
+    pub store_extra_trackers: bool,
 }
 
 impl Default for DownloadConfig {
@@ -129,6 +629,8 @@ impl Default for DownloadConfig {
             custom_app_path: None,
             enable_quick_download: true,
             auto_close_page: true,
+            extra_trackers: Vec::new(),
+            store_extra_trackers: false,
         }
     }
 }
@@ -137,19 +639,56 @@ impl Default for DownloadConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppData {
     pub favorites: Vec<FavoriteItem>,
+    #[serde(default)]
+    pub favorite_folders: Vec<FavoriteFolder>,
+    #[serde(default)]
+    pub auto_tag_rules: Vec<AutoTagRule>,
+    /// 从收藏中学到的发布组和标签偏好，用于结果重排
+    #[serde(default)]
+    pub preference_weights: crate::filter::PreferenceWeights,
     pub search_engines: Vec<SearchEngine>,
+    /// 各引擎的长期搜索统计，键为引擎名称
+    #[serde(default)]
+    pub engine_statistics: BTreeMap<String, EngineStatistics>,
     pub priority_keywords: Vec<PriorityKeyword>,
     pub llm_config: LlmConfig,
     pub search_settings: SearchSettings,
     pub download_config: DownloadConfig,
     pub current_locale: String, // 当前语言设置
     pub version: String, // 用于数据迁移
+    /// 数据结构版本，旧文件没有该字段时为 0，加载时按版本依次执行迁移
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+/// 当前的数据结构版本
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// 将旧版本的数据迁移到当前结构，返回是否有改动（有改动时需要写回文件）
+pub fn migrate_app_data(data: &mut AppData) -> bool {
+    if data.schema_version >= CURRENT_SCHEMA_VERSION {
+        return false;
+    }
+
+    // v0 → v1：旧版本可能保存了 infohash 相同的重复收藏，合并一次并保留最早的记录
+    if data.schema_version < 1 {
+        let merged = dedupe_favorite_items(&mut data.favorites, DedupeStrategy::Oldest, false);
+        if merged > 0 {
+            println!("🧹 Merged {merged} duplicate favorites while migrating app data");
+        }
+    }
+
+    data.schema_version = CURRENT_SCHEMA_VERSION;
+    true
 }
 
 impl Default for AppData {
     fn default() -> Self {
         Self {
             favorites: Vec::new(),
+            favorite_folders: Vec::new(),
+            auto_tag_rules: Vec::new(),
+            preference_weights: crate::filter::PreferenceWeights::default(),
             search_engines: vec![
                 // 默认搜索引擎
                 SearchEngine {
@@ -158,14 +697,27 @@ impl Default for AppData {
                     url_template: "http://clmclm.com/search-{keyword}-1-1-{page}.html".to_string(),
                     is_enabled: true,
                     is_deletable: false,
+                    category_code: None,
+                    sort_code: None,
+                    selectors: None,
+                    use_ai: true,
+                    generic_selectors: None,
+                    headers: BTreeMap::new(),
+                    consecutive_failures: 0,
+                    json_api: None,
+                    torznab: None,
+                    parse_order: crate::searcher::default_parse_order(),
+                    auth: None,
                 }
             ],
+            engine_statistics: BTreeMap::new(),
             priority_keywords: Vec::new(),
             llm_config: LlmConfig::default(),
             search_settings: SearchSettings::default(),
             download_config: DownloadConfig::default(),
             current_locale: "en".to_string(), // 默认英文
             version: "1.2.0".to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION,
         }
     }
 }
@@ -181,9 +733,14 @@ impl AppStateManager {
             .path()
             .app_data_dir()
             .map_err(|e| anyhow!("Failed to get app data directory: {}", e))?;
-        
+
+        Self::with_data_dir(&app_data_dir)
+    }
+
+    /// 使用指定目录存放数据文件（无 Tauri 环境时使用）
+    pub fn with_data_dir(app_data_dir: &Path) -> Result<Self> {
         // 确保目录存在
-        fs::create_dir_all(&app_data_dir)
+        fs::create_dir_all(app_data_dir)
             .map_err(|e| anyhow!("Failed to create app data directory: {}", e))?;
         
         let data_file_path = app_data_dir.join("app_data.json");
@@ -203,8 +760,13 @@ impl AppStateManager {
         let content = fs::read_to_string(&self.data_file_path)
             .map_err(|e| anyhow!("Failed to read app data file: {}", e))?;
         
-        let data: AppData = match serde_json::from_str(&content) {
-            Ok(data) => data,
+        let data: AppData = match serde_json::from_str::<AppData>(&content) {
+            Ok(mut data) => {
+                if migrate_app_data(&mut data) {
+                    self.save_data(&data)?;
+                }
+                data
+            }
             Err(e) => {
                 eprintln!("Failed to parse app data, using default: {e}");
                 // 如果解析失败，备份损坏的文件并使用默认数据
@@ -235,7 +797,7 @@ impl AppStateManager {
 /// Tauri 状态管理
 pub type AppState = std::sync::Mutex<AppData>;
 
-/// 初始化应用状态
+/// 初始化应用状态，旧版本的数据在加载时迁移到当前结构（见 `migrate_app_data`）
 pub fn init_app_state(app_handle: &AppHandle) -> Result<AppState> {
     let manager = AppStateManager::new(app_handle)?;
     let data = manager.load_data()?;
@@ -243,12 +805,174 @@ pub fn init_app_state(app_handle: &AppHandle) -> Result<AppState> {
 }
 
 /// 保存当前状态到文件
+///
+/// 已注册 `DebouncedSaver` 时只登记一次保存请求，由后台在空闲后统一写入，写入失败通过
+/// `state-save-failed` 事件通知前端；否则立即写入文件。
 pub fn save_app_state(app_handle: &AppHandle, state: &AppState) -> Result<()> {
+    if let Some(saver) = app_handle.try_state::<DebouncedSaver>() {
+        saver.schedule();
+        return Ok(());
+    }
+    write_app_state(app_handle, state)
+}
+
+/// 立即写入所有尚未落盘的状态，用于退出前显式持久化
+pub fn flush_app_state(app_handle: &AppHandle, state: &AppState) -> Result<()> {
+    match app_handle.try_state::<DebouncedSaver>() {
+        Some(saver) => saver.flush(),
+        None => write_app_state(app_handle, state),
+    }
+}
+
+/// 立即把当前状态写入文件
+pub fn write_app_state(app_handle: &AppHandle, state: &AppState) -> Result<()> {
     let manager = AppStateManager::new(app_handle)?;
     let data = state.lock().unwrap().clone();
     manager.save_data(&data)
 }
 
+/// 默认的保存防抖间隔
+pub const DEFAULT_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+type SaveWriter = Arc<dyn Fn() -> Result<()> + Send + Sync>;
+type SaveErrorHandler = Arc<dyn Fn(&anyhow::Error) + Send + Sync>;
+
+#[derive(Default)]
+struct SaveSchedule {
+    pending: bool,
+    last_request: Option<tokio::time::Instant>,
+    closed: bool,
+}
+
+/// 合并短时间内的多次保存请求，最后一次请求后空闲 `delay` 才真正写入一次
+///
+/// 后台写入由 `background_task` 返回的任务完成，失败时调用 `on_error`，不会影响之后的保存请求；
+/// 失败的写入会在下一次 `flush` 时重试。
+pub struct DebouncedSaver {
+    schedule: Arc<(Mutex<SaveSchedule>, tokio::sync::Notify)>,
+    // 后台写入与 flush 互斥，避免同时写同一个文件
+    write_lock: Arc<Mutex<()>>,
+    writer: SaveWriter,
+    on_error: SaveErrorHandler,
+    delay: Duration,
+    // 最近一次后台写入是否失败，之后写入成功时清除
+    failed: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl DebouncedSaver {
+    /// 创建防抖保存器，`writer` 在写入时读取最新状态并落盘，`on_error` 接收后台写入的错误
+    ///
+    /// 需要另外运行 `background_task` 返回的任务才会在后台写入。
+    pub fn new<F, E>(delay: Duration, writer: F, on_error: E) -> Self
+    where
+        F: Fn() -> Result<()> + Send + Sync + 'static,
+        E: Fn(&anyhow::Error) + Send + Sync + 'static,
+    {
+        Self {
+            schedule: Arc::new((Mutex::new(SaveSchedule::default()), tokio::sync::Notify::new())),
+            write_lock: Arc::new(Mutex::new(())),
+            writer: Arc::new(writer),
+            on_error: Arc::new(on_error),
+            delay,
+            failed: Arc::default(),
+        }
+    }
+
+    /// 为 Tauri 应用创建保存器并启动后台写入，写入时读取已注册的 `AppState`，失败时发送 `state-save-failed` 事件
+    pub fn for_app(app_handle: AppHandle, delay: Duration) -> Self {
+        let events = app_handle.clone();
+        let saver = Self::new(
+            delay,
+            move || {
+                let state = app_handle.state::<AppState>();
+                write_app_state(&app_handle, &state)
+            },
+            move |e| {
+                use tauri::Emitter;
+                if let Err(emit_error) = events.emit("state-save-failed", e.to_string()) {
+                    eprintln!("⚠️ Failed to emit state-save-failed: {emit_error}");
+                }
+            },
+        );
+        tauri::async_runtime::spawn(saver.background_task());
+        saver
+    }
+
+    /// 登记一次保存请求
+    pub fn schedule(&self) {
+        let (lock, notify) = &*self.schedule;
+        let mut schedule = lock.lock().unwrap();
+        schedule.pending = true;
+        schedule.last_request = Some(tokio::time::Instant::now());
+        notify.notify_one();
+    }
+
+    /// 如有未写入的请求或上次后台写入失败则立即写入，并等待进行中的后台写入完成
+    pub fn flush(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let pending = std::mem::take(&mut self.schedule.0.lock().unwrap().pending);
+        // 上一次后台写入失败时也重新写入
+        if pending || self.failed.load(std::sync::atomic::Ordering::SeqCst) {
+            (self.writer)()?;
+            self.failed.store(false, std::sync::atomic::Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// 后台写入任务：最后一次保存请求后空闲 `delay` 时写入，保存器被释放后结束
+    pub fn background_task(&self) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let schedule = self.schedule.clone();
+        let write_lock = self.write_lock.clone();
+        let writer = self.writer.clone();
+        let on_error = self.on_error.clone();
+        let failed = self.failed.clone();
+        let delay = self.delay;
+
+        async move {
+            let (lock, notify) = &*schedule;
+            loop {
+                let due = {
+                    let state = lock.lock().unwrap();
+                    if state.closed {
+                        return;
+                    }
+                    state.last_request.filter(|_| state.pending).map(|last_request| last_request + delay)
+                };
+                match due {
+                    None => notify.notified().await,
+                    Some(due) if tokio::time::Instant::now() < due => {
+                        // 期间有新的请求时重新计算空闲时间
+                        tokio::select! {
+                            _ = tokio::time::sleep_until(due) => {}
+                            _ = notify.notified() => {}
+                        }
+                    }
+                    Some(_) => {
+                        let _guard = write_lock.lock().unwrap();
+                        // flush 可能已在等待写锁期间完成写入
+                        if std::mem::take(&mut lock.lock().unwrap().pending) {
+                            let result = writer();
+                            failed.store(result.is_err(), std::sync::atomic::Ordering::SeqCst);
+                            if let Err(e) = result {
+                                eprintln!("❌ Failed to save app state: {e}");
+                                on_error(&e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DebouncedSaver {
+    fn drop(&mut self) {
+        let (lock, notify) = &*self.schedule;
+        lock.lock().unwrap().closed = true;
+        notify.notify_one();
+    }
+}
+
 // ============ 收藏夹相关函数 ============
 
 /// 添加到收藏夹
@@ -260,23 +984,72 @@ pub fn add_to_favorites(
     file_list: Vec<String>,
 ) -> Result<FavoriteItem> {
     let mut data = state.lock().unwrap();
-    
-    // 检查是否已经收藏
-    if data.favorites.iter().any(|item| item.magnet_link == magnet_link) {
+
+    // 检查是否已经收藏，与批量收藏使用相同的 infohash 去重键
+    if favorite_dedupe_keys(&data).contains(&crate::filter::dedupe_key(&magnet_link)) {
         return Err(anyhow!(translate_error(&ErrorCode::FavoritesDuplicate)));
     }
     
+    Ok(push_favorite(&mut data, title, magnet_link, file_size, file_list))
+}
+
+/// 创建收藏条目并加入收藏夹，同时应用自动标签规则并记录偏好
+fn push_favorite(
+    data: &mut AppData,
+    title: String,
+    magnet_link: String,
+    file_size: Option<String>,
+    file_list: Vec<String>,
+) -> FavoriteItem {
+    let mut stored_magnet = crate::searcher::canonicalize_magnet(&magnet_link);
+    if data.download_config.store_extra_trackers {
+        stored_magnet = crate::searcher::append_trackers(&stored_magnet, &data.download_config.extra_trackers);
+    }
+    let user_tags = apply_auto_tag_rules(&data.auto_tag_rules, &title);
     let favorite_item = FavoriteItem {
         id: Uuid::new_v4().to_string(),
         title,
-        magnet_link,
+        original_magnet_link: (magnet_link != stored_magnet).then_some(magnet_link),
+        magnet_link: stored_magnet,
         file_size,
         file_list,
         created_at: chrono::Utc::now().to_rfc3339(),
+        user_tags,
+        notes: None,
+        folder_id: None,
+        downloaded: false,
+        downloaded_at: None,
+        link_dead: false,
     };
     
+    data.preference_weights.record_favorite(&favorite_item.title, &favorite_item.user_tags);
     data.favorites.push(favorite_item.clone());
-    Ok(favorite_item)
+    favorite_item
+}
+
+/// 批量收藏的结果统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchFavoriteResult {
+    pub added: usize,
+    /// 已在收藏夹中或在本批次内重复的条目数
+    pub skipped: usize,
+}
+
+/// 批量收藏搜索结果，按 infohash 与已有收藏及本批次内的条目去重
+pub fn add_many_to_favorites(state: &AppState, results: Vec<crate::searcher::SearchResult>) -> BatchFavoriteResult {
+    let mut data = state.lock().unwrap();
+    let mut seen = favorite_dedupe_keys(&data);
+
+    let mut summary = BatchFavoriteResult::default();
+    for result in results {
+        if !seen.insert(crate::filter::dedupe_key(&result.magnet_link)) {
+            summary.skipped += 1;
+            continue;
+        }
+        push_favorite(&mut data, result.title, result.magnet_link, result.file_size, result.file_list);
+        summary.added += 1;
+    }
+    summary
 }
 
 /// 获取所有收藏
@@ -298,50 +1071,377 @@ pub fn remove_from_favorites(state: &AppState, id: String) -> Result<()> {
     Ok(())
 }
 
+/// 所有收藏的去重键（infohash，无法解析时为规范化的磁力链接），包括收藏时的原始链接
+pub fn get_favorited_infohashes(state: &AppState) -> std::collections::HashSet<String> {
+    favorite_dedupe_keys(&state.lock().unwrap())
+}
+
+fn favorite_dedupe_keys(data: &AppData) -> std::collections::HashSet<String> {
+    data.favorites
+        .iter()
+        .flat_map(|item| std::iter::once(&item.magnet_link).chain(item.original_magnet_link.as_ref()))
+        .map(|magnet| crate::filter::dedupe_key(magnet))
+        .collect()
+}
+
 /// 在收藏中搜索
 pub fn search_favorites(state: &AppState, query: String) -> Vec<FavoriteItem> {
     let data = state.lock().unwrap();
-    let query_lower = query.to_lowercase();
+    let query = crate::filter::normalize_for_match(&query);
     
     data.favorites
         .iter()
-        .filter(|item| item.title.to_lowercase().contains(&query_lower))
+        .filter(|item| crate::filter::normalize_for_match(&item.title).contains(&query))
         .cloned()
         .collect()
 }
 
-// ============ 搜索引擎相关函数 ============
-
-/// 添加搜索引擎
-pub fn add_search_engine(
-    state: &AppState,
-    name: String,
-    url_template: String,
-) -> Result<SearchEngine> {
+/// 按 infohash 合并重复的收藏，返回被合并掉的条目数量
+///
+/// 每组重复项按 `strategy` 选出主记录，其余条目的标签与备注并入主记录。
+/// `dry_run` 为 true 时只统计数量，不修改状态。
+pub fn dedupe_favorites(state: &AppState, strategy: DedupeStrategy, dry_run: bool) -> usize {
     let mut data = state.lock().unwrap();
+    dedupe_favorite_items(&mut data.favorites, strategy, dry_run)
+}
 
-    let engine = SearchEngine {
-        id: Uuid::new_v4().to_string(),
-        name,
-        url_template,
-        is_enabled: true,
-        is_deletable: true,
-    };
+/// `dedupe_favorites` 的实现，直接作用于收藏列表，加载数据时的迁移也使用它
+fn dedupe_favorite_items(favorites: &mut Vec<FavoriteItem>, strategy: DedupeStrategy, dry_run: bool) -> usize {
+    // 按 infohash 分组，保持首次出现的顺序；无法解析 infohash 时退回使用完整磁力链接
+    let mut groups: Vec<(String, Vec<FavoriteItem>)> = Vec::new();
+    for item in favorites.iter() {
+        let key = crate::filter::dedupe_key(&item.magnet_link);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(item.clone()),
+            None => groups.push((key, vec![item.clone()])),
+        }
+    }
 
-    data.search_engines.push(engine.clone());
-    Ok(engine)
+    let merged_count = favorites.len() - groups.len();
+    if dry_run || merged_count == 0 {
+        return merged_count;
+    }
+
+    *favorites = groups
+        .into_iter()
+        .map(|(_, members)| merge_favorite_group(members, strategy))
+        .collect();
+
+    merged_count
 }
 
-/// 更新搜索引擎
-pub fn update_search_engine(
-    state: &AppState,
-    id: String,
-    name: String,
-    url_template: String,
-) -> Result<()> {
+/// 删除所有匹配 `criteria` 的收藏，返回删除（或 `dry_run` 时将会删除）的条目数量
+pub fn remove_favorites_matching(state: &AppState, criteria: &FavoriteFilter, dry_run: bool) -> usize {
     let mut data = state.lock().unwrap();
 
-    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+    let matched = data.favorites.iter().filter(|item| criteria.matches(item)).count();
+    if !dry_run && matched > 0 {
+        data.favorites.retain(|item| !criteria.matches(item));
+    }
+
+    matched
+}
+
+/// 将同一 infohash 的多条收藏合并为一条
+fn merge_favorite_group(mut members: Vec<FavoriteItem>, strategy: DedupeStrategy) -> FavoriteItem {
+    if members.len() == 1 {
+        return members.remove(0);
+    }
+
+    let primary_index = match strategy {
+        DedupeStrategy::Oldest => members
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.created_at.cmp(&b.created_at))
+            .map(|(i, _)| i),
+        DedupeStrategy::Newest => members
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.created_at.cmp(&b.created_at))
+            .map(|(i, _)| i),
+        DedupeStrategy::LongestTitle => members
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, item)| item.title.trim().chars().count())
+            .map(|(i, _)| i),
+    }
+    .unwrap_or(0);
+
+    let mut primary = members.remove(primary_index);
+    for other in members {
+        for tag in other.user_tags {
+            if !primary.user_tags.contains(&tag) {
+                primary.user_tags.push(tag);
+            }
+        }
+
+        if let Some(note) = other.notes.filter(|n| !n.trim().is_empty()) {
+            primary.notes = match primary.notes.take().filter(|n| !n.trim().is_empty()) {
+                Some(existing) if existing.contains(note.as_str()) => Some(existing),
+                Some(existing) => Some(format!("{existing}\n{note}")),
+                None => Some(note),
+            };
+        }
+
+        if primary.file_size.is_none() {
+            primary.file_size = other.file_size;
+        }
+        if primary.file_list.is_empty() {
+            primary.file_list = other.file_list;
+        }
+        if primary.folder_id.is_none() {
+            primary.folder_id = other.folder_id;
+        }
+        if !primary.downloaded && other.downloaded {
+            primary.downloaded = true;
+            primary.downloaded_at = other.downloaded_at;
+        }
+    }
+
+    primary
+}
+
+/// 获取自动标签规则
+pub fn get_auto_tag_rules(state: &AppState) -> Vec<AutoTagRule> {
+    let data = state.lock().unwrap();
+    data.auto_tag_rules.clone()
+}
+
+/// 更新自动标签规则，拒绝包含无效正则的规则
+pub fn update_auto_tag_rules(state: &AppState, rules: Vec<AutoTagRule>) -> Result<()> {
+    for rule in rules.iter().filter(|rule| rule.match_type == AutoTagMatch::Regex) {
+        regex::Regex::new(&rule.pattern)
+            .map_err(|e| anyhow!("Invalid auto-tag pattern '{}': {}", rule.pattern, e))?;
+    }
+
+    let mut data = state.lock().unwrap();
+    data.auto_tag_rules = rules;
+    Ok(())
+}
+
+// ============ 偏好权重相关函数 ============
+
+/// 获取从收藏中学到的偏好权重
+pub fn get_preference_weights(state: &AppState) -> crate::filter::PreferenceWeights {
+    let data = state.lock().unwrap();
+    data.preference_weights.clone()
+}
+
+/// 清空学到的偏好权重
+pub fn reset_preference_weights(state: &AppState) {
+    let mut data = state.lock().unwrap();
+    data.preference_weights = crate::filter::PreferenceWeights::default();
+}
+
+// ============ 收藏文件夹相关函数 ============
+
+/// 获取所有收藏文件夹
+pub fn get_all_favorite_folders(state: &AppState) -> Vec<FavoriteFolder> {
+    let data = state.lock().unwrap();
+    data.favorite_folders.clone()
+}
+
+/// 创建收藏文件夹，`parent_id` 为 None 时创建在根目录
+pub fn create_favorite_folder(state: &AppState, name: String, parent_id: Option<String>) -> Result<FavoriteFolder> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(parent_id) = &parent_id {
+        if !data.favorite_folders.iter().any(|f| &f.id == parent_id) {
+            return Err(anyhow!(translate_error(&ErrorCode::FolderNotFound)));
+        }
+    }
+
+    let folder = FavoriteFolder {
+        id: Uuid::new_v4().to_string(),
+        name,
+        parent_id,
+    };
+
+    data.favorite_folders.push(folder.clone());
+    Ok(folder)
+}
+
+/// 重命名收藏文件夹
+pub fn rename_favorite_folder(state: &AppState, id: String, name: String) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    let folder = data.favorite_folders
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or_else(|| anyhow!(translate_error(&ErrorCode::FolderNotFound)))?;
+
+    folder.name = name;
+    Ok(())
+}
+
+/// 移动收藏文件夹到新的上级文件夹下，不允许移动到自身或其子文件夹中
+pub fn move_favorite_folder(state: &AppState, id: String, parent_id: Option<String>) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if !data.favorite_folders.iter().any(|f| f.id == id) {
+        return Err(anyhow!(translate_error(&ErrorCode::FolderNotFound)));
+    }
+
+    if let Some(parent_id) = &parent_id {
+        if !data.favorite_folders.iter().any(|f| &f.id == parent_id) {
+            return Err(anyhow!(translate_error(&ErrorCode::FolderNotFound)));
+        }
+        if folder_subtree_ids(&data.favorite_folders, &id).contains(parent_id) {
+            return Err(anyhow!(translate_error(&ErrorCode::FolderInvalidParent)));
+        }
+    }
+
+    if let Some(folder) = data.favorite_folders.iter_mut().find(|f| f.id == id) {
+        folder.parent_id = parent_id;
+    }
+    Ok(())
+}
+
+/// 删除收藏文件夹，按 `mode` 处理其中的收藏与子文件夹
+pub fn delete_favorite_folder(state: &AppState, id: String, mode: FolderDeleteMode) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    let parent_id = data.favorite_folders
+        .iter()
+        .find(|f| f.id == id)
+        .map(|f| f.parent_id.clone())
+        .ok_or_else(|| anyhow!(translate_error(&ErrorCode::FolderNotFound)))?;
+
+    match mode {
+        FolderDeleteMode::Reparent => {
+            data.favorite_folders.retain(|f| f.id != id);
+            for folder in data.favorite_folders.iter_mut().filter(|f| f.parent_id.as_deref() == Some(id.as_str())) {
+                folder.parent_id = parent_id.clone();
+            }
+            for item in data.favorites.iter_mut().filter(|item| item.folder_id.as_deref() == Some(id.as_str())) {
+                item.folder_id = parent_id.clone();
+            }
+        }
+        FolderDeleteMode::DeleteContents => {
+            let removed = folder_subtree_ids(&data.favorite_folders, &id);
+            data.favorite_folders.retain(|f| !removed.contains(&f.id));
+            data.favorites.retain(|item| !item.folder_id.as_ref().is_some_and(|folder_id| removed.contains(folder_id)));
+        }
+    }
+
+    Ok(())
+}
+
+/// 将收藏移动到指定文件夹，`folder_id` 为 None 时移回根目录
+pub fn move_favorite_to_folder(state: &AppState, favorite_id: String, folder_id: Option<String>) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(folder_id) = &folder_id {
+        if !data.favorite_folders.iter().any(|f| &f.id == folder_id) {
+            return Err(anyhow!(translate_error(&ErrorCode::FolderNotFound)));
+        }
+    }
+
+    let item = data.favorites
+        .iter_mut()
+        .find(|item| item.id == favorite_id)
+        .ok_or_else(|| anyhow!(translate_error(&ErrorCode::FavoritesNotFound)))?;
+
+    item.folder_id = folder_id;
+    Ok(())
+}
+
+/// 标记收藏是否已下载，标记为已下载时记录当前时间
+pub fn mark_favorite_downloaded(state: &AppState, id: String, downloaded: bool) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    let item = data.favorites
+        .iter_mut()
+        .find(|item| item.id == id)
+        .ok_or_else(|| anyhow!(translate_error(&ErrorCode::FavoritesNotFound)))?;
+
+    item.downloaded = downloaded;
+    item.downloaded_at = downloaded.then(|| chrono::Utc::now().to_rfc3339());
+    Ok(())
+}
+
+/// 标记收藏的磁力链接是否已失效，供按失效状态批量清理收藏
+pub fn mark_favorite_link_dead(state: &AppState, id: String, link_dead: bool) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    let item = data.favorites
+        .iter_mut()
+        .find(|item| item.id == id)
+        .ok_or_else(|| anyhow!(translate_error(&ErrorCode::FavoritesNotFound)))?;
+
+    item.link_dead = link_dead;
+    Ok(())
+}
+
+/// 按下载状态筛选收藏，`downloaded` 为 false 时只返回尚未下载的条目
+pub fn get_favorites_by_downloaded(state: &AppState, downloaded: bool) -> Vec<FavoriteItem> {
+    let data = state.lock().unwrap();
+    data.favorites
+        .iter()
+        .filter(|item| item.downloaded == downloaded)
+        .cloned()
+        .collect()
+}
+
+/// 返回文件夹自身及其所有子孙文件夹的 id
+fn folder_subtree_ids(folders: &[FavoriteFolder], root_id: &str) -> Vec<String> {
+    let mut ids = vec![root_id.to_string()];
+    let mut index = 0;
+    while index < ids.len() {
+        let current = ids[index].clone();
+        for folder in folders.iter().filter(|f| f.parent_id.as_deref() == Some(current.as_str())) {
+            if !ids.contains(&folder.id) {
+                ids.push(folder.id.clone());
+            }
+        }
+        index += 1;
+    }
+    ids
+}
+
+// ============ 搜索引擎相关函数 ============
+
+/// 添加搜索引擎
+pub fn add_search_engine(
+    state: &AppState,
+    name: String,
+    url_template: String,
+) -> Result<SearchEngine> {
+    let mut data = state.lock().unwrap();
+
+    let engine = SearchEngine {
+        id: Uuid::new_v4().to_string(),
+        name,
+        url_template,
+        is_enabled: true,
+        is_deletable: true,
+        category_code: None,
+        sort_code: None,
+        selectors: None,
+        use_ai: true,
+        generic_selectors: None,
+        headers: BTreeMap::new(),
+        consecutive_failures: 0,
+        json_api: None,
+        torznab: None,
+        parse_order: crate::searcher::default_parse_order(),
+        auth: None,
+    };
+
+    data.search_engines.push(engine.clone());
+    Ok(engine)
+}
+
+/// 更新搜索引擎
+pub fn update_search_engine(
+    state: &AppState,
+    id: String,
+    name: String,
+    url_template: String,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
         engine.name = name;
         engine.url_template = url_template;
         Ok(())
@@ -350,6 +1450,144 @@ pub fn update_search_engine(
     }
 }
 
+/// 更新搜索引擎URL中的分类与排序代码（空字符串表示恢复默认）
+pub fn update_engine_url_codes(
+    state: &AppState,
+    id: String,
+    category_code: Option<String>,
+    sort_code: Option<String>,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.category_code = category_code.filter(|c| !c.trim().is_empty());
+        engine.sort_code = sort_code.filter(|c| !c.trim().is_empty());
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新搜索引擎的结果解析选择器，无效的选择器回退为默认值
+pub fn update_engine_selectors(
+    state: &AppState,
+    id: String,
+    selectors: Option<crate::searcher::ClmclmSelectors>,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.selectors = selectors.map(crate::searcher::ClmclmSelectors::validated);
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 设置自定义引擎是否使用 AI 提取
+pub fn update_engine_use_ai(state: &AppState, id: String, use_ai: bool) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.use_ai = use_ai;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新自定义引擎的 CSS 选择器，None 表示不使用选择器
+pub fn update_engine_generic_selectors(
+    state: &AppState,
+    id: String,
+    selectors: Option<crate::searcher::GenericSelectors>,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.generic_selectors = selectors;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新自定义引擎的 JSON 接口字段映射，为 None 时恢复为 HTML 解析。
+/// 已配置 Torznab 的引擎不能再配置 JSON 接口
+pub fn update_engine_json_api(
+    state: &AppState,
+    id: String,
+    mapping: Option<crate::searcher::JsonApiMapping>,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        if mapping.is_some() && engine.torznab.is_some() {
+            return Err(anyhow!(translate_error(&ErrorCode::EngineInvalid)));
+        }
+        engine.json_api = mapping;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新自定义引擎的 Torznab 配置，为 None 时恢复为 HTML 解析。
+/// 已配置 JSON 接口的引擎不能再配置 Torznab
+pub fn update_engine_torznab(
+    state: &AppState,
+    id: String,
+    config: Option<crate::searcher::TorznabConfig>,
+) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        if config.is_some() && engine.json_api.is_some() {
+            return Err(anyhow!(translate_error(&ErrorCode::EngineInvalid)));
+        }
+        engine.torznab = config;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新自定义引擎的登录方式，为 None 时不再登录
+pub fn update_engine_auth(state: &AppState, id: String, auth: Option<crate::searcher::EngineAuth>) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.auth = auth;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新自定义引擎解析结果页的策略顺序，重复的策略会被去掉，空列表恢复默认顺序
+pub fn update_engine_parse_order(state: &AppState, id: String, order: Vec<crate::searcher::ParseStrategy>) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.parse_order = crate::searcher::normalize_parse_order(order);
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 更新搜索引擎附加的请求头
+pub fn update_engine_headers(state: &AppState, id: String, headers: BTreeMap<String, String>) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.headers = headers;
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
 /// 获取所有搜索引擎
 pub fn get_all_engines(state: &AppState) -> Vec<SearchEngine> {
     let data = state.lock().unwrap();
@@ -362,6 +1600,94 @@ pub fn update_engine_status(state: &AppState, id: String, is_enabled: bool) -> R
     
     if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
         engine.is_enabled = is_enabled;
+        // 手动启用时与 reenable_engine 一样清零连续失败次数
+        if is_enabled {
+            engine.consecutive_failures = 0;
+        }
+        Ok(())
+    } else {
+        Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
+    }
+}
+
+/// 按一次搜索的请求统计更新各引擎的连续失败次数
+///
+/// 有成功请求的引擎清零；只有失败的引擎加一，达到 `threshold` 时自动禁用。
+/// 返回本次被自动禁用的引擎名称。
+pub fn record_engine_outcomes(
+    state: &AppState,
+    metrics: &[crate::searcher::ProviderMetrics],
+    threshold: Option<u32>,
+) -> Vec<String> {
+    let mut data = state.lock().unwrap();
+    let mut disabled = Vec::new();
+
+    for metric in metrics {
+        let Some(engine) = data.search_engines.iter_mut().find(|e| e.name == metric.name) else {
+            continue;
+        };
+        if metric.successes > 0 {
+            engine.consecutive_failures = 0;
+        } else if metric.failures > 0 {
+            engine.consecutive_failures += 1;
+            if engine.is_enabled && threshold.is_some_and(|threshold| engine.consecutive_failures >= threshold.max(1)) {
+                engine.is_enabled = false;
+                disabled.push(engine.name.clone());
+            }
+        }
+    }
+
+    disabled
+}
+
+/// 按一次搜索的请求统计累计各引擎的长期统计，没有发出请求的引擎不计入
+///
+/// 返回本次新出现解析警告（以前有结果，最近连续多次从正常大小的页面中解析不出结果）的引擎名称。
+pub fn record_engine_statistics(state: &AppState, metrics: &[crate::searcher::ProviderMetrics]) -> Vec<String> {
+    let mut data = state.lock().unwrap();
+    let mut warned = Vec::new();
+    for metric in metrics.iter().filter(|metric| metric.requests > 0) {
+        let statistics = data.engine_statistics.entry(metric.name.clone()).or_default();
+        statistics.record(
+            metric.successes > 0,
+            metric.results_count,
+            metric.total_latency_ms / u64::from(metric.requests),
+        );
+        if statistics.record_parse_outcome(metric) {
+            warned.push(metric.name.clone());
+        }
+    }
+    warned
+}
+
+/// 各引擎最近请求耗时的中位数，用于按响应速度排列搜索顺序
+pub fn engine_median_latencies(state: &AppState) -> BTreeMap<String, u64> {
+    let data = state.lock().unwrap();
+    data.engine_statistics
+        .iter()
+        .filter_map(|(name, statistics)| Some((name.clone(), statistics.median_latency_ms()?)))
+        .collect()
+}
+
+/// 获取各引擎的长期搜索统计
+pub fn get_engine_statistics(state: &AppState) -> BTreeMap<String, EngineStatistics> {
+    let data = state.lock().unwrap();
+    data.engine_statistics.clone()
+}
+
+/// 清空所有引擎的长期搜索统计
+pub fn reset_engine_statistics(state: &AppState) {
+    let mut data = state.lock().unwrap();
+    data.engine_statistics.clear();
+}
+
+/// 重新启用搜索引擎并清零连续失败次数
+pub fn reenable_engine(state: &AppState, id: String) -> Result<()> {
+    let mut data = state.lock().unwrap();
+
+    if let Some(engine) = data.search_engines.iter_mut().find(|e| e.id == id) {
+        engine.is_enabled = true;
+        engine.consecutive_failures = 0;
         Ok(())
     } else {
         Err(anyhow!(translate_error(&ErrorCode::EngineNotFound)))
@@ -437,10 +1763,38 @@ pub fn get_llm_config(state: &AppState) -> LlmConfig {
 }
 
 /// 更新 LLM 配置
-pub fn update_llm_config(state: &AppState, config: LlmConfig) -> Result<()> {
+///
+/// 超出范围的 `batch_size` 与无效的 `max_html_chars` 会被修正，返回值为每次修正的提示信息。
+pub fn update_llm_config(state: &AppState, mut config: LlmConfig) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+    for (name, single) in [
+        ("extraction", &mut config.extraction_config),
+        ("analysis", &mut config.analysis_config),
+    ] {
+        let clamped = clamp_batch_size(single.batch_size);
+        if clamped != single.batch_size {
+            warnings.push(format!(
+                "{name} batch_size {} is outside {MIN_BATCH_SIZE}-{MAX_BATCH_SIZE}, using {clamped}",
+                single.batch_size
+            ));
+            single.batch_size = clamped;
+        }
+    }
+    if config.extraction_config.max_html_chars == Some(0) {
+        warnings.push(format!(
+            "extraction max_html_chars must be positive, using the default {}",
+            crate::llm_service::DEFAULT_MAX_HTML_CHARS
+        ));
+        config.extraction_config.max_html_chars = None;
+    }
+    // 分析阶段不发送 HTML，不保存该字段
+    if config.analysis_config.max_html_chars.take().is_some() {
+        warnings.push("analysis max_html_chars is ignored, only extraction truncates HTML".to_string());
+    }
+
     let mut data = state.lock().unwrap();
     data.llm_config = config;
-    Ok(())
+    Ok(warnings)
 }
 
 
@@ -453,13 +1807,146 @@ pub fn get_search_settings(state: &AppState) -> SearchSettings {
     data.search_settings.clone()
 }
 
+/// 模糊去重阈值的允许范围，过低时标题差别很大的不同资源也会被合并
+pub const MIN_FUZZY_DEDUPE_THRESHOLD: f64 = 0.5;
+pub const MAX_FUZZY_DEDUPE_THRESHOLD: f64 = 1.0;
+
 /// 更新搜索设置
-pub fn update_search_settings(state: &AppState, settings: SearchSettings) -> Result<()> {
+///
+/// 超出范围的 `fuzzy_dedupe_threshold` 会被修正到允许范围内，无效值（NaN）视为不启用。
+pub fn update_search_settings(state: &AppState, mut settings: SearchSettings) -> Result<()> {
+    if let Some(threshold) = settings.fuzzy_dedupe_threshold {
+        let clamped = (!threshold.is_nan()).then(|| threshold.clamp(MIN_FUZZY_DEDUPE_THRESHOLD, MAX_FUZZY_DEDUPE_THRESHOLD));
+        if clamped != Some(threshold) {
+            println!(
+                "⚠️ fuzzy_dedupe_threshold {threshold} is outside {MIN_FUZZY_DEDUPE_THRESHOLD}-{MAX_FUZZY_DEDUPE_THRESHOLD}, using {clamped:?}"
+            );
+        }
+        settings.fuzzy_dedupe_threshold = clamped;
+    }
+
     let mut data = state.lock().unwrap();
     data.search_settings = settings;
     Ok(())
 }
 
+// ============ 生效配置相关函数 ============
+
+/// 当前实际生效的配置，默认值与覆盖已合并，密钥已打码，用于排查问题
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub search_settings: SearchSettings,
+    pub engines: Vec<EffectiveEngineConfig>,
+    pub llm_config: LlmConfig,
+}
+
+/// 单个引擎实际使用的请求参数
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveEngineConfig {
+    pub name: String,
+    pub is_enabled: bool,
+    /// 引擎类型："clmclm"、"html"、"json_api" 或 "torznab"
+    pub kind: String,
+    pub timeout_secs: u64,
+    /// 为 None 时使用 reqwest 的默认 User-Agent
+    pub user_agent: Option<String>,
+    /// 全局请求头与引擎请求头合并后实际发送的请求头（名称为小写），同名时引擎优先
+    pub headers: BTreeMap<String, String>,
+    pub use_ai: bool,
+    pub parse_order: Vec<crate::searcher::ParseStrategy>,
+    pub torznab: Option<crate::searcher::TorznabConfig>,
+    pub auth: Option<crate::searcher::EngineAuth>,
+}
+
+/// 携带凭据的请求头，输出生效配置时打码
+const SENSITIVE_HEADERS: [&str; 5] = ["authorization", "cookie", "proxy-authorization", "x-api-key", "api-key"];
+
+/// 打码密钥，只保留末尾 4 个字符便于核对；较短或为空的密钥不保留任何字符
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    match chars.len() {
+        0 => String::new(),
+        len if len <= 8 => "****".to_string(),
+        len => format!("****{}", chars[len - 4..].iter().collect::<String>()),
+    }
+}
+
+/// 凭据类请求头的值打码，其他请求头原样返回
+fn mask_header_value(name: &str, value: &str) -> String {
+    if SENSITIVE_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()) {
+        mask_secret(value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn effective_engine_config(engine: &SearchEngine, default_headers: &BTreeMap<String, String>) -> EffectiveEngineConfig {
+    let (kind, user_agent) = if engine.name == "clmclm.com" {
+        ("clmclm", Some(crate::searcher::CLMCLM_USER_AGENT))
+    } else if engine.torznab.is_some() {
+        ("torznab", None)
+    } else if engine.json_api.is_some() {
+        ("json_api", None)
+    } else {
+        ("html", Some(crate::searcher::GENERIC_USER_AGENT))
+    };
+
+    // 与发送请求时使用同一合并逻辑：名称不区分大小写，无效的请求头被忽略
+    let applied = crate::net::merge_headers(default_headers, &engine.headers);
+    // 配置的 User-Agent 请求头覆盖客户端默认值
+    let user_agent = applied
+        .get(reqwest::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .or(user_agent);
+    let headers = applied
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), mask_header_value(name.as_str(), value.to_str().ok()?))))
+        .collect();
+
+    let torznab = engine.torznab.clone().map(|config| crate::searcher::TorznabConfig {
+        api_key: mask_secret(&config.api_key),
+        ..config
+    });
+    // 登录表单中可能包含密码，字段值一律打码
+    let auth = engine.auth.clone().map(|auth| crate::searcher::EngineAuth {
+        fields: auth.fields.iter().map(|(name, value)| (name.clone(), mask_secret(value))).collect(),
+        ..auth
+    });
+
+    EffectiveEngineConfig {
+        name: engine.name.clone(),
+        is_enabled: engine.is_enabled,
+        kind: kind.to_string(),
+        timeout_secs: crate::searcher::PROVIDER_REQUEST_TIMEOUT.as_secs(),
+        user_agent: user_agent.map(str::to_string),
+        headers,
+        use_ai: engine.use_ai && kind == "html",
+        parse_order: engine.parse_order.clone(),
+        torznab,
+        auth,
+    }
+}
+
+/// 获取当前实际生效的配置，API 密钥、凭据类请求头和登录字段均已打码
+pub fn get_effective_config(state: &AppState) -> EffectiveConfig {
+    let data = state.lock().unwrap();
+    let mut search_settings = data.search_settings.clone();
+    let engines = data
+        .search_engines
+        .iter()
+        .map(|engine| effective_engine_config(engine, &search_settings.default_headers))
+        .collect();
+    for (name, value) in search_settings.default_headers.iter_mut() {
+        *value = mask_header_value(name, value);
+    }
+
+    let mut llm_config = data.llm_config.clone();
+    llm_config.extraction_config.api_key = mask_secret(&llm_config.extraction_config.api_key);
+    llm_config.analysis_config.api_key = mask_secret(&llm_config.analysis_config.api_key);
+
+    EffectiveConfig { search_settings, engines, llm_config }
+}
+
 // ============ 下载配置相关函数 ============
 
 /// 获取下载配置
@@ -489,3 +1976,700 @@ pub fn set_current_locale(state: &AppState, locale: String) -> Result<()> {
     data.current_locale = locale;
     Ok(())
 }
+
+// ============ 设置重置相关函数 ============
+
+/// 将指定设置分区恢复为首次启动时的默认值，其他数据保持不变
+pub fn reset_settings_section(state: &AppState, section: SettingsSection) {
+    let mut data = state.lock().unwrap();
+    let defaults = AppData::default();
+
+    match section {
+        SettingsSection::Llm => data.llm_config = defaults.llm_config,
+        SettingsSection::Search => data.search_settings = defaults.search_settings,
+        SettingsSection::Engines => data.search_engines = defaults.search_engines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(id: &str, title: &str, magnet_link: &str, created_at: &str) -> FavoriteItem {
+        FavoriteItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            magnet_link: magnet_link.to_string(),
+            file_size: None,
+            file_list: Vec::new(),
+            created_at: created_at.to_string(),
+            user_tags: Vec::new(),
+            notes: None,
+            folder_id: None,
+            original_magnet_link: None,
+            downloaded: false,
+            downloaded_at: None,
+            link_dead: false,
+        }
+    }
+
+    #[test]
+    fn test_dedupe_favorites_merges_tags_and_notes() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut first = favorite("1", "Movie", &format!("magnet:?xt=urn:btih:{hash}"), "2024-01-01T00:00:00Z");
+        first.user_tags = vec!["4K".to_string()];
+        first.notes = Some("from site A".to_string());
+        let mut second = favorite(
+            "2",
+            "Movie 2023 2160p",
+            &format!("magnet:?xt=urn:btih:{}&dn=Movie", hash.to_uppercase()),
+            "2024-02-01T00:00:00Z",
+        );
+        second.user_tags = vec!["HDR".to_string(), "4K".to_string()];
+        second.notes = Some("from site B".to_string());
+
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![first, second],
+            ..AppData::default()
+        });
+
+        // dry_run 只报告，不修改
+        assert_eq!(dedupe_favorites(&state, DedupeStrategy::LongestTitle, true), 1);
+        assert_eq!(get_all_favorites(&state).len(), 2);
+
+        assert_eq!(dedupe_favorites(&state, DedupeStrategy::LongestTitle, false), 1);
+        let favorites = get_all_favorites(&state);
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].id, "2");
+        assert_eq!(favorites[0].title, "Movie 2023 2160p");
+        assert_eq!(favorites[0].user_tags, vec!["HDR".to_string(), "4K".to_string()]);
+        assert_eq!(favorites[0].notes.as_deref(), Some("from site B\nfrom site A"));
+    }
+
+    #[test]
+    fn test_nested_folders_and_moving_favorites() {
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![favorite("f1", "Movie 2160p", "magnet:?xt=urn:btih:1", "2024-01-01T00:00:00Z")],
+            ..AppData::default()
+        });
+
+        let movies = create_favorite_folder(&state, "Movies".to_string(), None).unwrap();
+        let uhd = create_favorite_folder(&state, "4K".to_string(), Some(movies.id.clone())).unwrap();
+        assert_eq!(uhd.parent_id.as_deref(), Some(movies.id.as_str()));
+        assert!(create_favorite_folder(&state, "Orphan".to_string(), Some("missing".to_string())).is_err());
+
+        // 不能把文件夹移动到自己的子文件夹下
+        assert!(move_favorite_folder(&state, movies.id.clone(), Some(uhd.id.clone())).is_err());
+
+        move_favorite_to_folder(&state, "f1".to_string(), Some(uhd.id.clone())).unwrap();
+        assert_eq!(get_all_favorites(&state)[0].folder_id.as_deref(), Some(uhd.id.as_str()));
+        move_favorite_to_folder(&state, "f1".to_string(), Some(movies.id.clone())).unwrap();
+        assert_eq!(get_all_favorites(&state)[0].folder_id.as_deref(), Some(movies.id.as_str()));
+
+        // 删除上级文件夹并保留内容：子文件夹和收藏都移到根目录
+        delete_favorite_folder(&state, movies.id.clone(), FolderDeleteMode::Reparent).unwrap();
+        let folders = get_all_favorite_folders(&state);
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].parent_id, None);
+        assert_eq!(get_all_favorites(&state)[0].folder_id, None);
+
+        move_favorite_to_folder(&state, "f1".to_string(), Some(uhd.id.clone())).unwrap();
+        delete_favorite_folder(&state, uhd.id.clone(), FolderDeleteMode::DeleteContents).unwrap();
+        assert!(get_all_favorite_folders(&state).is_empty());
+        assert!(get_all_favorites(&state).is_empty());
+    }
+
+    #[test]
+    fn test_load_merges_duplicate_favorites_from_legacy_file() {
+        let dir = std::env::temp_dir().join(format!("ai-magnet-migrate-{}", Uuid::new_v4()));
+        let manager = AppStateManager::with_data_dir(&dir).unwrap();
+
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let mut first = favorite("1", "Movie", &format!("magnet:?xt=urn:btih:{hash}"), "2024-01-01T00:00:00Z");
+        first.user_tags = vec!["4K".to_string()];
+        let mut second = favorite("2", "Movie copy", &format!("magnet:?xt=urn:btih:{}&dn=Movie", hash.to_uppercase()), "2024-02-01T00:00:00Z");
+        second.notes = Some("from site B".to_string());
+        let other = favorite("3", "Other", "magnet:?xt=urn:btih:ffffffffffffffffffffffffffffffffffffffff", "2024-03-01T00:00:00Z");
+        let mut legacy = serde_json::to_value(AppData { favorites: vec![first, second, other], ..AppData::default() }).unwrap();
+        // 旧版本的数据文件没有 schema_version 字段
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        fs::write(dir.join("app_data.json"), legacy.to_string()).unwrap();
+
+        let data = manager.load_data().unwrap();
+        assert_eq!(data.schema_version, CURRENT_SCHEMA_VERSION);
+        let ids: Vec<_> = data.favorites.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "3"]);
+        assert_eq!(data.favorites[0].user_tags, vec!["4K"]);
+        assert_eq!(data.favorites[0].notes.as_deref(), Some("from site B"));
+
+        // 迁移结果已写回文件，再次加载不会重复迁移
+        let reloaded: AppData = serde_json::from_str(&fs::read_to_string(dir.join("app_data.json")).unwrap()).unwrap();
+        assert_eq!(reloaded.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(reloaded.favorites.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mark_favorite_downloaded_and_filter() {
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![
+                favorite("f1", "Movie A", "magnet:?xt=urn:btih:1", "2024-01-01T00:00:00Z"),
+                favorite("f2", "Movie B", "magnet:?xt=urn:btih:2", "2024-01-02T00:00:00Z"),
+            ],
+            ..AppData::default()
+        });
+
+        mark_favorite_downloaded(&state, "f1".to_string(), true).unwrap();
+        let downloaded = get_favorites_by_downloaded(&state, true);
+        assert_eq!(downloaded.len(), 1);
+        assert_eq!(downloaded[0].id, "f1");
+        assert!(downloaded[0].downloaded_at.is_some());
+        let pending: Vec<_> = get_favorites_by_downloaded(&state, false).into_iter().map(|item| item.id).collect();
+        assert_eq!(pending, vec!["f2"]);
+
+        // 取消标记时清除下载时间
+        mark_favorite_downloaded(&state, "f1".to_string(), false).unwrap();
+        assert_eq!(get_favorites_by_downloaded(&state, false).len(), 2);
+        assert!(get_all_favorites(&state)[0].downloaded_at.is_none());
+        assert!(mark_favorite_downloaded(&state, "missing".to_string(), true).is_err());
+
+        // 旧版本保存的收藏没有这两个字段，默认为未下载
+        let legacy: FavoriteItem = serde_json::from_str(
+            r#"{"id":"old","title":"Old","magnet_link":"magnet:?xt=urn:btih:3","file_size":null,"file_list":[],"created_at":"2023-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert!(!legacy.downloaded);
+        assert!(legacy.downloaded_at.is_none());
+    }
+
+    #[test]
+    fn test_remove_favorites_matching_tag_and_date_range() {
+        let tagged = |id: &str, created_at: &str, tags: &[&str]| FavoriteItem {
+            user_tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..favorite(id, id, &format!("magnet:?xt=urn:btih:{id}"), created_at)
+        };
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![
+                tagged("old-cam", "2023-05-01T00:00:00Z", &["CAM"]),
+                tagged("new-cam", "2024-06-01T00:00:00Z", &["cam", "4K"]),
+                tagged("old-4k", "2023-06-01T00:00:00Z", &["4K"]),
+                tagged("broken-date", "yesterday", &["4K"]),
+            ],
+            ..AppData::default()
+        });
+        let ids = |state: &AppState| get_all_favorites(state).into_iter().map(|item| item.id).collect::<Vec<_>>();
+
+        let by_tag = FavoriteFilter { tag: Some("cam".to_string()), ..FavoriteFilter::default() };
+        assert_eq!(remove_favorites_matching(&state, &by_tag, true), 2);
+        assert_eq!(ids(&state).len(), 4);
+        assert_eq!(remove_favorites_matching(&state, &by_tag, false), 2);
+        assert_eq!(ids(&state), vec!["old-4k", "broken-date"]);
+
+        // 空条件不删除任何收藏
+        assert_eq!(remove_favorites_matching(&state, &FavoriteFilter::default(), false), 0);
+
+        let in_2023 = FavoriteFilter {
+            added_after: Some("2023-01-01T00:00:00Z".parse().unwrap()),
+            added_before: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            ..FavoriteFilter::default()
+        };
+        assert_eq!(remove_favorites_matching(&state, &in_2023, false), 1);
+        assert_eq!(ids(&state), vec!["broken-date"]);
+    }
+
+    #[test]
+    fn test_remove_favorites_matching_content_type_and_dead_link() {
+        let with_files = |id: &str, files: &[&str]| FavoriteItem {
+            file_list: files.iter().map(|f| f.to_string()).collect(),
+            ..favorite(id, id, &format!("magnet:?xt=urn:btih:{id}"), "2024-01-01T00:00:00Z")
+        };
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![
+                with_files("movie", &["Movie.mkv (8 GB)"]),
+                with_files("album", &["01.flac", "02.flac"]),
+                with_files("dead-album", &["01.mp3"]),
+            ],
+            ..AppData::default()
+        });
+        mark_favorite_link_dead(&state, "dead-album".to_string(), true).unwrap();
+        let ids = |state: &AppState| get_all_favorites(state).into_iter().map(|item| item.id).collect::<Vec<_>>();
+
+        let dead_audio = FavoriteFilter {
+            content_type: Some(crate::filter::ContentType::Audio),
+            link_dead: Some(true),
+            ..FavoriteFilter::default()
+        };
+        assert_eq!(remove_favorites_matching(&state, &dead_audio, false), 1);
+        assert_eq!(ids(&state), vec!["movie", "album"]);
+
+        let audio = FavoriteFilter { content_type: Some(crate::filter::ContentType::Audio), ..FavoriteFilter::default() };
+        assert_eq!(remove_favorites_matching(&state, &audio, false), 1);
+        assert_eq!(ids(&state), vec!["movie"]);
+    }
+
+    #[test]
+    fn test_add_to_favorites_applies_all_matching_auto_tag_rules() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        update_auto_tag_rules(&state, vec![
+            AutoTagRule { pattern: "4k".to_string(), match_type: AutoTagMatch::Substring, tags: vec!["4K".to_string()] },
+            AutoTagRule { pattern: "Anime".to_string(), match_type: AutoTagMatch::Substring, tags: vec!["Anime".to_string()] },
+            AutoTagRule { pattern: r"(?i)\b(hdr|dv)\b".to_string(), match_type: AutoTagMatch::Regex, tags: vec!["HDR".to_string(), "4K".to_string()] },
+        ]).unwrap();
+
+        let item = add_to_favorites(&state, "Movie 2023 4K HDR".to_string(), "magnet:?xt=urn:btih:1".to_string(), None, Vec::new()).unwrap();
+        assert_eq!(item.user_tags, vec!["4K".to_string(), "HDR".to_string()]);
+
+        let invalid = AutoTagRule { pattern: "(".to_string(), match_type: AutoTagMatch::Regex, tags: vec!["x".to_string()] };
+        assert!(update_auto_tag_rules(&state, vec![invalid]).is_err());
+    }
+
+    #[test]
+    fn test_reset_settings_section_only_touches_target() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        add_search_engine(&state, "custom".to_string(), "https://example.com/?q={keyword}".to_string()).unwrap();
+        add_to_favorites(&state, "Movie".to_string(), "magnet:?xt=urn:btih:abc".to_string(), None, Vec::new()).unwrap();
+        {
+            let mut data = state.lock().unwrap();
+            data.llm_config.extraction_config.model = "custom-model".to_string();
+            data.search_settings.max_results_per_page = Some(5);
+        }
+
+        reset_settings_section(&state, SettingsSection::Search);
+        let data = state.lock().unwrap().clone();
+        assert_eq!(data.search_settings.max_results_per_page, None);
+        assert_eq!(data.llm_config.extraction_config.model, "custom-model");
+        assert_eq!(data.search_engines.len(), 2);
+        assert_eq!(data.favorites.len(), 1);
+
+        reset_settings_section(&state, SettingsSection::Engines);
+        let data = state.lock().unwrap().clone();
+        assert_eq!(data.search_engines.len(), 1);
+        assert_eq!(data.search_engines[0].id, "default_clmclm");
+        assert_eq!(data.llm_config.extraction_config.model, "custom-model");
+
+        reset_settings_section(&state, SettingsSection::Llm);
+        let data = state.lock().unwrap().clone();
+        assert_eq!(data.llm_config.extraction_config.model, LlmConfig::default().extraction_config.model);
+        assert_eq!(data.favorites.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounced_saver_coalesces_rapid_saves() {
+        let writes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = writes.clone();
+        let saver = DebouncedSaver::new(
+            Duration::from_millis(50),
+            move || {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            },
+            |_| {},
+        );
+        tokio::spawn(saver.background_task());
+        let writes = || writes.load(std::sync::atomic::Ordering::SeqCst);
+
+        for _ in 0..100 {
+            saver.schedule();
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(writes(), 1);
+
+        // flush 立即写入未落盘的请求，之后后台不再重复写
+        saver.schedule();
+        saver.flush().unwrap();
+        assert_eq!(writes(), 2);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(writes(), 2);
+
+        // 没有待写入的请求时 flush 不写文件
+        saver.flush().unwrap();
+        assert_eq!(writes(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounced_saver_reports_background_write_failure() {
+        let fail = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let (failing, reported) = (fail.clone(), errors.clone());
+        let saver = DebouncedSaver::new(
+            Duration::from_millis(20),
+            move || match failing.load(std::sync::atomic::Ordering::SeqCst) {
+                true => Err(anyhow!("disk full")),
+                false => Ok(()),
+            },
+            move |e| reported.lock().unwrap().push(e.to_string()),
+        );
+        tokio::spawn(saver.background_task());
+
+        saver.schedule();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*errors.lock().unwrap(), vec!["disk full".to_string()]);
+
+        // 失败的写入在 flush 时重试，即使之后没有新的保存请求
+        assert!(saver.flush().is_err());
+        fail.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert!(saver.flush().is_ok());
+        assert_eq!(errors.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_favorites_matches_width_variants() {
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![
+                favorite("1", "ＡＢＣ Movie １０８０ｐ", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "2024-01-01T00:00:00Z"),
+                favorite("2", "Other", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "2024-01-01T00:00:00Z"),
+            ],
+            ..AppData::default()
+        });
+
+        let found = search_favorites(&state, "abc movie 1080P".to_string());
+        assert_eq!(found.len(), 1);
+        // 展示的标题保持原样
+        assert_eq!(found[0].title, "ＡＢＣ Movie １０８０ｐ");
+        assert_eq!(search_favorites(&state, "ＯＴＨＥＲ".to_string()).len(), 1);
+    }
+
+    #[test]
+    fn test_add_to_favorites_stores_canonical_magnet() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let original = "magnet:?dn=Movie&xt=urn:btih:0123456789ABCDEF0123456789ABCDEF01234567&tr=udp://b&tr=udp://a";
+
+        let item = add_to_favorites(&state, "Movie".to_string(), original.to_string(), None, Vec::new()).unwrap();
+        assert_eq!(item.magnet_link, crate::searcher::canonicalize_magnet(original));
+        assert_eq!(item.original_magnet_link.as_deref(), Some(original));
+
+        // 同一资源的另一种写法视为重复
+        let reordered = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&tr=udp://a&dn=Movie&tr=udp://b";
+        assert!(add_to_favorites(&state, "Movie".to_string(), reordered.to_string(), None, Vec::new()).is_err());
+
+        // 只要 infohash 相同，名称和 tracker 不同也视为重复，与批量收藏一致
+        let renamed = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Other&tr=udp://c";
+        assert!(add_to_favorites(&state, "Other".to_string(), renamed.to_string(), None, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_add_many_to_favorites_skips_duplicates() {
+        let state: AppState = std::sync::Mutex::new(AppData {
+            favorites: vec![favorite(
+                "existing",
+                "Old",
+                "magnet:?xt=urn:btih:1111111111111111111111111111111111111111",
+                "2024-01-01T00:00:00Z",
+            )],
+            ..AppData::default()
+        });
+        let result = |title: &str, magnet_link: &str| crate::searcher::SearchResult {
+            title: title.to_string(),
+            original_title: title.to_string(),
+            magnet_link: magnet_link.to_string(),
+            file_size: Some("1.00 GB".to_string()),
+            provider_name: "test".to_string(),
+            ..Default::default()
+        };
+
+        let summary = add_many_to_favorites(
+            &state,
+            vec![
+                result("Already favorited", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111&dn=Old"),
+                result("New A", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222"),
+                result("New A again", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222&tr=udp://x"),
+                result("New B", "magnet:?xt=urn:btih:3333333333333333333333333333333333333333"),
+            ],
+        );
+
+        assert_eq!(summary, BatchFavoriteResult { added: 2, skipped: 2 });
+        let titles: Vec<String> = get_all_favorites(&state).into_iter().map(|item| item.title).collect();
+        assert_eq!(titles, vec!["Old", "New A", "New B"]);
+    }
+
+    #[test]
+    fn test_effective_config_reflects_overrides_and_masks_secrets() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let mut llm_config = LlmConfig::default();
+        llm_config.extraction_config.api_key = "sk-extraction-1234567890abcd".to_string();
+        llm_config.analysis_config.api_key = "short".to_string();
+        update_llm_config(&state, llm_config).unwrap();
+
+        let mut settings = get_search_settings(&state);
+        settings.max_pages_limit = 7;
+        settings.default_headers.insert("Accept-Language".to_string(), "zh-CN".to_string());
+        update_search_settings(&state, settings).unwrap();
+
+        let engine = add_search_engine(&state, "private".to_string(), "https://private.test/s?q={keyword}".to_string()).unwrap();
+        update_engine_headers(
+            &state,
+            engine.id.clone(),
+            BTreeMap::from([
+                ("accept-language".to_string(), "en-GB".to_string()),
+                ("Authorization".to_string(), "Bearer secret-token-9876".to_string()),
+                ("Bad Header".to_string(), "ignored".to_string()),
+                ("User-Agent".to_string(), "PrivateBot/1.0".to_string()),
+            ]),
+        )
+        .unwrap();
+        update_engine_auth(
+            &state,
+            engine.id,
+            Some(crate::searcher::EngineAuth {
+                login_url: "https://private.test/login".to_string(),
+                fields: BTreeMap::from([("password".to_string(), "hunter2".to_string())]),
+                success_text: None,
+                success_cookie: None,
+            }),
+        )
+        .unwrap();
+
+        let effective = get_effective_config(&state);
+        assert_eq!(effective.search_settings.max_pages_limit, 7);
+        assert_eq!(effective.llm_config.extraction_config.api_key, "****abcd");
+        assert_eq!(effective.llm_config.analysis_config.api_key, "****");
+
+        let private = effective.engines.iter().find(|e| e.name == "private").unwrap();
+        assert_eq!(private.kind, "html");
+        assert_eq!(private.timeout_secs, crate::searcher::PROVIDER_REQUEST_TIMEOUT.as_secs());
+        // 引擎请求头不区分大小写地覆盖全局请求头，凭据被打码，无效的请求头不会出现
+        assert_eq!(private.headers["accept-language"], "en-GB");
+        assert_eq!(private.headers["authorization"], "****9876");
+        assert!(!private.headers.contains_key("bad header"));
+        assert_eq!(private.user_agent.as_deref(), Some("PrivateBot/1.0"));
+        assert_eq!(private.auth.as_ref().unwrap().fields["password"], "****");
+
+        let serialized = serde_json::to_string(&effective).unwrap();
+        for secret in ["sk-extraction", "secret-token", "hunter2"] {
+            assert!(!serialized.contains(secret), "{secret} leaked");
+        }
+        // 打码不影响保存的配置
+        assert_eq!(get_llm_config(&state).analysis_config.api_key, "short");
+    }
+
+    #[test]
+    fn test_extra_trackers_stored_only_when_enabled() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let magnet = "magnet:?xt=urn:btih:1111111111111111111111111111111111111111";
+        let tracker = "udp://tracker.example.org:1337".to_string();
+        update_download_config(&state, DownloadConfig { extra_trackers: vec![tracker.clone()], ..DownloadConfig::default() }).unwrap();
+
+        let plain = add_to_favorites(&state, "Plain".to_string(), magnet.to_string(), None, Vec::new()).unwrap();
+        assert_eq!(plain.magnet_link, magnet);
+
+        remove_from_favorites(&state, plain.id).unwrap();
+        update_download_config(
+            &state,
+            DownloadConfig { extra_trackers: vec![tracker], store_extra_trackers: true, ..DownloadConfig::default() },
+        )
+        .unwrap();
+        let augmented = add_to_favorites(&state, "Augmented".to_string(), magnet.to_string(), None, Vec::new()).unwrap();
+        assert_eq!(augmented.magnet_link, format!("{magnet}&tr=udp%3A%2F%2Ftracker.example.org%3A1337"));
+        assert_eq!(augmented.original_magnet_link.as_deref(), Some(magnet));
+    }
+
+    #[test]
+    fn test_update_llm_config_clamps_batch_size() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let mut config = LlmConfig::default();
+        config.extraction_config.batch_size = 0;
+        config.analysis_config.batch_size = 500;
+        config.extraction_config.max_html_chars = Some(0);
+        config.analysis_config.max_html_chars = Some(1000);
+
+        let warnings = update_llm_config(&state, config).unwrap();
+        assert_eq!(warnings.len(), 4);
+        let saved = get_llm_config(&state);
+        assert_eq!(saved.extraction_config.batch_size, MIN_BATCH_SIZE);
+        assert_eq!(saved.analysis_config.batch_size, MAX_BATCH_SIZE);
+        assert_eq!(saved.extraction_config.max_html_chars, None);
+        assert_eq!(saved.analysis_config.max_html_chars, None);
+
+        assert!(update_llm_config(&state, LlmConfig::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_search_settings_clamps_fuzzy_dedupe_threshold() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let threshold = |value: Option<f64>| {
+            update_search_settings(&state, SearchSettings { fuzzy_dedupe_threshold: value, ..SearchSettings::default() }).unwrap();
+            get_search_settings(&state).fuzzy_dedupe_threshold
+        };
+
+        assert_eq!(threshold(Some(0.0)), Some(MIN_FUZZY_DEDUPE_THRESHOLD));
+        assert_eq!(threshold(Some(1.5)), Some(MAX_FUZZY_DEDUPE_THRESHOLD));
+        assert_eq!(threshold(Some(0.85)), Some(0.85));
+        assert_eq!(threshold(Some(f64::NAN)), None);
+        assert_eq!(threshold(None), None);
+    }
+
+    #[test]
+    fn test_favoriting_records_release_group_preference() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        add_to_favorites(
+            &state,
+            "Movie.2023.1080p.BluRay.x264-SPARKS".to_string(),
+            "magnet:?xt=urn:btih:1111111111111111111111111111111111111111".to_string(),
+            None,
+            Vec::new(),
+        )
+        .unwrap();
+
+        let preferences = get_preference_weights(&state);
+        assert_eq!(preferences.release_group_weights.get("sparks"), Some(&1.0));
+
+        reset_preference_weights(&state);
+        assert!(get_preference_weights(&state).is_empty());
+    }
+
+    #[test]
+    fn test_engine_auto_disabled_after_consecutive_failures() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let engine = add_search_engine(&state, "flaky".to_string(), "https://flaky.test/s?q={keyword}".to_string()).unwrap();
+        let outcome = |successes, failures| crate::searcher::ProviderMetrics {
+            name: "flaky".to_string(),
+            requests: successes + failures,
+            successes,
+            failures,
+            ..Default::default()
+        };
+
+        // 成功一次后计数清零
+        record_engine_outcomes(&state, &[outcome(0, 2)], Some(3));
+        record_engine_outcomes(&state, &[outcome(0, 1)], Some(3));
+        record_engine_outcomes(&state, &[outcome(1, 1)], Some(3));
+        let flaky = |state: &AppState| get_all_engines(state).into_iter().find(|e| e.id == engine.id).unwrap();
+        assert_eq!(flaky(&state).consecutive_failures, 0);
+
+        assert!(record_engine_outcomes(&state, &[outcome(0, 1)], Some(3)).is_empty());
+        assert!(record_engine_outcomes(&state, &[outcome(0, 1)], Some(3)).is_empty());
+        assert_eq!(record_engine_outcomes(&state, &[outcome(0, 1)], Some(3)), vec!["flaky".to_string()]);
+        assert!(!flaky(&state).is_enabled);
+
+        reenable_engine(&state, engine.id.clone()).unwrap();
+        let reenabled = flaky(&state);
+        assert!(reenabled.is_enabled);
+        assert_eq!(reenabled.consecutive_failures, 0);
+
+        // 通过普通的启用开关重新启用时同样清零
+        record_engine_outcomes(&state, &[outcome(0, 1)], Some(3));
+        update_engine_status(&state, engine.id.clone(), false).unwrap();
+        update_engine_status(&state, engine.id.clone(), true).unwrap();
+        assert_eq!(flaky(&state).consecutive_failures, 0);
+
+        // 未设置阈值时只计数，不禁用
+        for _ in 0..5 {
+            assert!(record_engine_outcomes(&state, &[outcome(0, 1)], None).is_empty());
+        }
+        assert!(flaky(&state).is_enabled);
+    }
+
+    #[tokio::test]
+    async fn test_parse_warning_after_repeated_empty_parses_of_normal_sized_pages() {
+        use httpmock::prelude::*;
+
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let server = MockServer::start();
+        let search = |server: &MockServer| {
+            let engine = crate::searcher::CustomEngineConfig::new("changed".to_string(), format!("{}/s/{{page}}?q={{keyword}}", server.base_url()))
+                .with_use_ai(false);
+            crate::searcher::create_ai_enhanced_search_core(None, None, Vec::new(), vec![engine], None, Default::default())
+        };
+        let serve = |body: String| {
+            server.mock(|when, then| {
+                when.method(GET).path("/s/1");
+                then.status(200).body(body);
+            })
+        };
+
+        // 正常的结果页约 19 KiB
+        let mut page = serve(format!(
+            r#"<html><body>{}<a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">Movie 2023</a></body></html>"#,
+            "<div class=\"sidebar\">Links</div>".repeat(600)
+        ));
+        let core = search(&server);
+        assert_eq!(core.search_multi_page("movie", 1).await.unwrap().len(), 1);
+        assert!(record_engine_statistics(&state, &core.metrics()).is_empty());
+        page.delete();
+
+        // 站点自己的“无结果”页面超过 4 KiB，但明显小于正常结果页，不计入
+        let mut page = serve(format!("<html><body>{}</body></html>", "<p>Nothing here</p>".repeat(300)));
+        let core = search(&server);
+        assert!(core.search_multi_page("rare-title", 1).await.unwrap().is_empty());
+        assert_eq!(core.metrics()[0].empty_parses, 1);
+        assert!(record_engine_statistics(&state, &core.metrics()).is_empty());
+        assert_eq!(get_engine_statistics(&state)["changed"].empty_parse_searches, 0);
+        page.delete();
+
+        // 站点改版后，不同关键词连续返回正常大小却没有磁力链接的页面
+        serve(format!("<html><body>{}</body></html>", "<div class=\"new-layout\">Movie 2023</div>".repeat(500)));
+        for query in ["movie", "sintel"] {
+            let core = search(&server);
+            assert!(core.search_multi_page(query, 1).await.unwrap().is_empty());
+            assert!(record_engine_statistics(&state, &core.metrics()).is_empty());
+        }
+        let core = search(&server);
+        assert!(core.search_multi_page("ubuntu", 1).await.unwrap().is_empty());
+        assert_eq!(record_engine_statistics(&state, &core.metrics()), vec!["changed".to_string()]);
+
+        let statistics = &get_engine_statistics(&state)["changed"];
+        assert!(statistics.parse_warning);
+        assert_eq!((statistics.productive_searches, statistics.empty_parse_searches), (1, 3));
+        // 警告持续期间不重复通知
+        assert!(record_engine_statistics(&state, &core.metrics()).is_empty());
+    }
+
+    #[test]
+    fn test_engine_statistics_accumulate_across_searches() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let metric = |name: &str, successes, failures, results_count| crate::searcher::ProviderMetrics {
+            name: name.to_string(),
+            requests: successes + failures,
+            successes,
+            failures,
+            results_count,
+            total_latency_ms: u64::from(successes + failures) * 100 * (results_count as u64 + 1),
+            ..Default::default()
+        };
+
+        record_engine_statistics(&state, &[metric("a", 1, 0, 10), metric("b", 0, 1, 0)]);
+        record_engine_statistics(&state, &[metric("a", 2, 1, 20), metric("b", 1, 0, 4)]);
+        record_engine_statistics(&state, &[metric("a", 0, 1, 0), metric("idle", 0, 0, 0)]);
+
+        let statistics = get_engine_statistics(&state);
+        let a = &statistics["a"];
+        assert_eq!((a.total_searches, a.successful_searches), (3, 2));
+        assert!((a.avg_results - 10.0).abs() < 1e-9);
+        let b = &statistics["b"];
+        assert_eq!((b.total_searches, b.successful_searches), (2, 1));
+        assert!((b.avg_results - 2.0).abs() < 1e-9);
+        assert!(!statistics.contains_key("idle"));
+        assert_eq!(a.recent_latencies_ms, vec![1100, 2100, 100]);
+        assert_eq!(engine_median_latencies(&state).get("a"), Some(&1100));
+
+        reset_engine_statistics(&state);
+        assert!(get_engine_statistics(&state).is_empty());
+    }
+
+    #[test]
+    fn test_engine_rejects_both_torznab_and_json_api() {
+        let state: AppState = std::sync::Mutex::new(AppData::default());
+        let engine = add_search_engine(&state, "indexer".to_string(), "https://indexer.test/api".to_string()).unwrap();
+        let torznab = crate::searcher::TorznabConfig { api_key: "key".to_string(), categories: Vec::new() };
+        let mapping = crate::searcher::JsonApiMapping {
+            items: "data".to_string(),
+            title: "name".to_string(),
+            magnet: Some("magnet".to_string()),
+            infohash: None,
+            size: None,
+            date: None,
+        };
+
+        update_engine_torznab(&state, engine.id.clone(), Some(torznab.clone())).unwrap();
+        assert!(update_engine_json_api(&state, engine.id.clone(), Some(mapping.clone())).is_err());
+
+        // 清除 Torznab 后可以改用 JSON 接口，反过来同样互斥
+        update_engine_torznab(&state, engine.id.clone(), None).unwrap();
+        update_engine_json_api(&state, engine.id.clone(), Some(mapping)).unwrap();
+        assert!(update_engine_torznab(&state, engine.id.clone(), Some(torznab)).is_err());
+
+        let stored = get_all_engines(&state).into_iter().find(|e| e.id == engine.id).unwrap();
+        assert!(stored.json_api.is_some());
+        assert!(stored.torznab.is_none());
+    }
+}
@@ -0,0 +1,225 @@
+// src-tauri/src/bin/cli.rs
+// 命令行搜索工具：不启动界面，直接运行多页搜索并输出到 stdout
+//
+// 用法: cli <keyword> --engines <engines.json> [--llm <llm.json>] [--pages N] [--format json|magnets] [--no-ai]
+
+use ai_magnet_assistant_lib::llm_service::LlmConfig;
+use ai_magnet_assistant_lib::searcher::{self, ClmclmConfig, CustomEngineConfig, EmptyTitlePolicy, SearchResult};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: cli <keyword> --engines <engines.json> [--llm <llm.json>] [--pages N] [--format json|magnets] [--no-ai]";
+
+/// 引擎配置文件中的单个引擎，名称为 "clmclm.com" 时使用内置的 clmclm 提供商
+#[derive(Debug, Clone, Deserialize)]
+struct EngineEntry {
+    name: String,
+    #[serde(default)]
+    url_template: String,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    /// 为 false 时该引擎不使用 AI 提取
+    #[serde(default = "default_enabled")]
+    use_ai: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    /// 完整结果的 JSON 数组
+    Json,
+    /// 每行一个磁力链接
+    Magnets,
+}
+
+#[derive(Debug)]
+struct CliOptions {
+    keyword: String,
+    engines_path: PathBuf,
+    llm_path: Option<PathBuf>,
+    pages: u32,
+    format: OutputFormat,
+    no_ai: bool,
+}
+
+/// 解析命令行参数（不含程序名）
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliOptions> {
+    let mut keyword = None;
+    let mut engines_path = None;
+    let mut llm_path = None;
+    let mut pages = 3;
+    let mut format = OutputFormat::Json;
+    let mut no_ai = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| anyhow!("Missing value for {flag}"));
+        match arg.as_str() {
+            "--engines" => engines_path = Some(PathBuf::from(value("--engines")?)),
+            "--llm" => llm_path = Some(PathBuf::from(value("--llm")?)),
+            "--pages" => {
+                pages = value("--pages")?
+                    .parse()
+                    .map_err(|_| anyhow!("--pages must be a positive number"))?;
+            }
+            "--format" => {
+                format = match value("--format")?.as_str() {
+                    "json" => OutputFormat::Json,
+                    "magnets" => OutputFormat::Magnets,
+                    other => return Err(anyhow!("Unknown format '{other}', expected json or magnets")),
+                };
+            }
+            "--no-ai" => no_ai = true,
+            flag if flag.starts_with("--") => return Err(anyhow!("Unknown option {flag}")),
+            _ if keyword.is_none() => keyword = Some(arg),
+            _ => return Err(anyhow!("Unexpected argument '{arg}'")),
+        }
+    }
+
+    if pages == 0 {
+        return Err(anyhow!("--pages must be a positive number"));
+    }
+
+    Ok(CliOptions {
+        keyword: keyword.ok_or_else(|| anyhow!("Missing search keyword"))?,
+        engines_path: engines_path.ok_or_else(|| anyhow!("Missing --engines <file>"))?,
+        llm_path,
+        pages,
+        format,
+        no_ai,
+    })
+}
+
+/// 按输出格式渲染搜索结果
+fn format_results(results: &[SearchResult], format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(results)?),
+        OutputFormat::Magnets => Ok(results
+            .iter()
+            .map(|r| r.magnet_link.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
+/// 读取配置、执行搜索并返回要输出的文本
+async fn run(options: &CliOptions) -> Result<String> {
+    let engines_json = std::fs::read_to_string(&options.engines_path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", options.engines_path.display(), e))?;
+    let engines: Vec<EngineEntry> = serde_json::from_str(&engines_json)
+        .map_err(|e| anyhow!("Invalid engine config: {}", e))?;
+
+    let llm_config = match (&options.llm_path, options.no_ai) {
+        (Some(path), false) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+            Some(serde_json::from_str::<LlmConfig>(&content).map_err(|e| anyhow!("Invalid LLM config: {}", e))?)
+        }
+        _ => None,
+    };
+
+    let enabled: Vec<_> = engines.into_iter().filter(|e| e.enabled).collect();
+    if enabled.is_empty() {
+        return Err(anyhow!("No enabled engines in {}", options.engines_path.display()));
+    }
+
+    let clmclm_config = enabled
+        .iter()
+        .any(|e| e.name == "clmclm.com")
+        .then(ClmclmConfig::default);
+    let custom_engines = enabled
+        .into_iter()
+        .filter(|e| e.name != "clmclm.com")
+        .map(|e| CustomEngineConfig::new(e.name, e.url_template).with_use_ai(e.use_ai))
+        .collect();
+
+    let core = searcher::create_ai_enhanced_search_core(
+        llm_config,
+        None,
+        Vec::new(),
+        custom_engines,
+        clmclm_config,
+        EmptyTitlePolicy::default(),
+    );
+    let results = core.search_multi_page(&options.keyword, options.pages).await?;
+    format_results(&results, options.format)
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    match run(&options).await {
+        Ok(output) => println!("{output}"),
+        Err(e) => {
+            eprintln!("❌ {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_flags() {
+        let options = parse_args(args(&["ubuntu", "--engines", "e.json", "--pages", "2", "--format", "magnets", "--no-ai"])).unwrap();
+        assert_eq!(options.keyword, "ubuntu");
+        assert_eq!(options.pages, 2);
+        assert_eq!(options.format, OutputFormat::Magnets);
+        assert!(options.no_ai);
+
+        assert!(parse_args(args(&["ubuntu"])).is_err());
+        assert!(parse_args(args(&["ubuntu", "--engines", "e.json", "--format", "xml"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_outputs_json_results() {
+        let server = MockServer::start();
+        let hash = "cccccccccccccccccccccccccccccccccccccccc";
+        server.mock(|when, then| {
+            when.method(GET).path("/s");
+            then.status(200).body(format!(
+                r#"<table><tr><td><a href="/t/1">Debian 12 DVD</a></td><td>3.7 GB</td><td><a href="magnet:?xt=urn:btih:{hash}">m</a></td></tr></table>"#
+            ));
+        });
+
+        let dir = std::env::temp_dir().join(format!("cli-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let engines_path = dir.join("engines.json");
+        let engines = serde_json::json!([
+            { "name": "mock", "url_template": format!("{}/s?q={{keyword}}&p={{page}}", server.base_url()) },
+            { "name": "clmclm.com", "enabled": false }
+        ]);
+        std::fs::write(&engines_path, engines.to_string()).unwrap();
+
+        let options = parse_args(args(&["debian", "--engines", engines_path.to_str().unwrap(), "--pages", "1", "--no-ai"])).unwrap();
+        let output = run(&options).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let items = json.as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["title"], "Debian 12 DVD");
+        assert_eq!(items[0]["magnet_link"], format!("magnet:?xt=urn:btih:{hash}"));
+        assert_eq!(items[0]["provider_name"], "mock");
+        assert!(items[0]["file_list"].is_array());
+    }
+}
@@ -0,0 +1,1389 @@
+// src-tauri/src/filter.rs
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::searcher::{extract_infohash, SearchResult};
+
+/// 匹配 "数字 + 单位" 形式的文件大小，数字部分兼容多种千位分隔符与小数点写法
+static SIZE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)(\d{1,3}(?:[ \u{00a0}\u{202f}]\d{3})+(?:[.,]\d+)?|\d+(?:[.,']\d+)*)\s*(tib|tb|t|gib|gb|g|mib|mb|m|kib|kb|k|bytes|b)(?:[^a-z]|$)",
+    )
+    .unwrap()
+});
+
+/// 将文件大小文本解析为字节数（按 1024 进制）
+///
+/// 兼容欧洲格式的逗号小数（"2,1 GB"）、空格或撇号千位分隔符（"1 024 MB"），
+/// 以及单位与数字之间有无空格的写法（"700MB"）。
+pub fn parse_size_to_bytes(text: &str) -> Option<u64> {
+    let captures = SIZE_REGEX.captures(text)?;
+    let number = parse_locale_number(captures.get(1)?.as_str())?;
+
+    let multiplier = match captures.get(2)?.as_str().to_ascii_lowercase().as_str() {
+        "tib" | "tb" | "t" => 1024f64.powi(4),
+        "gib" | "gb" | "g" => 1024f64.powi(3),
+        "mib" | "mb" | "m" => 1024f64.powi(2),
+        "kib" | "kb" | "k" => 1024f64,
+        _ => 1.0,
+    };
+
+    Some((number * multiplier).round() as u64)
+}
+
+/// 格式化大小时默认保留的小数位数
+pub const DEFAULT_SIZE_PRECISION: usize = 2;
+
+/// 将字节数格式化为易读的大小文本（按 1024 进制，保留 `precision` 位小数），如 "1.50 GB"
+///
+/// 不足 1 KB 时按整数字节显示。
+pub fn format_size(bytes: u64, precision: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // 四舍五入后达到 1024 时进位到下一个单位，避免显示 "1024.00 KB"
+    let scale = 10f64.powi(precision as i32);
+    if unit > 0 && unit < UNITS.len() - 1 && (value * scale).round() / scale >= 1024.0 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.precision$} {}", UNITS[unit])
+    }
+}
+
+/// 按统一格式重新显示各结果的大小，无法解析大小的结果保持原样
+pub fn normalize_size_display(results: &mut [SearchResult], precision: usize) {
+    for result in results {
+        if let Some(bytes) = result.file_size.as_deref().and_then(parse_size_to_bytes) {
+            result.file_size = Some(format_size(bytes, precision));
+        }
+    }
+}
+
+/// 标题清理步骤，按配置的顺序依次执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleCleaningStep {
+    StripBrackets,
+    StripUrls,
+    StripReleaseTags,
+    CollapseWhitespace,
+    FixMojibake,
+}
+
+impl TitleCleaningStep {
+    pub fn apply(self, title: &str) -> String {
+        match self {
+            TitleCleaningStep::StripBrackets => strip_brackets(title),
+            TitleCleaningStep::StripUrls => strip_urls(title),
+            TitleCleaningStep::StripReleaseTags => strip_release_tags(title),
+            TitleCleaningStep::CollapseWhitespace => collapse_whitespace(title),
+            TitleCleaningStep::FixMojibake => fix_mojibake(title),
+        }
+    }
+}
+
+/// 默认的清理步骤：移除方括号广告和网址，再合并空白
+pub fn default_title_cleaning_steps() -> Vec<TitleCleaningStep> {
+    vec![
+        TitleCleaningStep::StripBrackets,
+        TitleCleaningStep::StripUrls,
+        TitleCleaningStep::CollapseWhitespace,
+    ]
+}
+
+/// 按顺序执行清理步骤
+pub fn clean_title(title: &str, steps: &[TitleCleaningStep]) -> String {
+    steps.iter().fold(title.to_string(), |title, step| step.apply(&title))
+}
+
+static BRACKETS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[.*?\]|【.*?】").unwrap());
+static URLS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(www\.\S+\.\S+|https?://\S+)").unwrap());
+static RELEASE_TAGS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(2160p|1080p|720p|480p|4K|UHD|BluRay|BDRip|REMUX|WEB-DL|WEBRip|HDTV|HDR10|HDR|x264|x265|H\.?264|H\.?265|HEVC|AVC|DTS|AC3|AAC|FLAC|10bit)\b",
+    )
+    .unwrap()
+});
+
+/// 移除方括号中的内容，如 `[y5y4.com]` 或 `【...】`
+pub fn strip_brackets(title: &str) -> String {
+    BRACKETS_RE.replace_all(title, "").into_owned()
+}
+
+/// 移除网址和推广链接
+pub fn strip_urls(title: &str) -> String {
+    URLS_RE.replace_all(title, "").into_owned()
+}
+
+/// 移除画质、来源和编码等发布标记
+pub fn strip_release_tags(title: &str) -> String {
+    RELEASE_TAGS_RE.replace_all(title, "").into_owned()
+}
+
+/// 去掉首尾空白，并把连续的空白合并为一个空格
+pub fn collapse_whitespace(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 修复按 Windows-1252 误解码的 UTF-8 文本（如 "ç”µå½±" → "电影"），无法修复时保持原样
+pub fn fix_mojibake(title: &str) -> String {
+    if title.is_ascii() {
+        return title.to_string();
+    }
+    let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(title);
+    if unmappable {
+        return title.to_string();
+    }
+    match String::from_utf8(bytes.into_owned()) {
+        Ok(fixed) if !fixed.is_ascii() => fixed,
+        _ => title.to_string(),
+    }
+}
+
+/// 标题超过 `max_chars` 个字符时在单词边界截断并追加省略号，未超出时返回 None
+///
+/// 没有合适的空白可断开（如连续的中文）时按字符截断。
+pub fn truncate_title(title: &str, max_chars: usize) -> Option<String> {
+    if title.chars().count() <= max_chars {
+        return None;
+    }
+
+    // 为省略号留出一个字符的位置
+    let keep = max_chars.saturating_sub(1);
+    let cut = title.char_indices().nth(keep).map_or(title.len(), |(index, _)| index);
+    let head = &title[..cut];
+    // 截断处恰好是空白时保留完整单词，否则只在后半段寻找空白，避免截得过短
+    let at_boundary = title[cut..].starts_with(char::is_whitespace);
+    let head = match head.rfind(char::is_whitespace) {
+        Some(space) if !at_boundary && head[..space].chars().count() >= keep / 2 => &head[..space],
+        _ => head,
+    };
+    Some(format!("{}…", head.trim_end()))
+}
+
+/// 标题被 `truncate_title` 截断后，裁掉落在省略号及之后的高亮区间，跨越截断处的区间截到可见部分为止
+pub fn clamp_highlights(highlights: &mut Vec<(usize, usize)>, truncated_title: &str) {
+    let visible = truncated_title.strip_suffix('…').unwrap_or(truncated_title).len();
+    highlights.retain(|&(start, _)| start < visible);
+    for range in highlights.iter_mut() {
+        range.1 = range.1.min(visible);
+    }
+}
+
+/// 解析带有本地化分隔符的数字
+fn parse_locale_number(raw: &str) -> Option<f64> {
+    // 空格与撇号只可能是千位分隔符
+    let compact: String = raw
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{00a0}' | '\u{202f}' | '\''))
+        .collect();
+
+    let last_comma = compact.rfind(',');
+    let last_dot = compact.rfind('.');
+
+    let normalized = match (last_comma, last_dot) {
+        // 同时存在时，最后出现的分隔符是小数点
+        (Some(comma), Some(dot)) if comma > dot => compact.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => compact.replace(',', ""),
+        (Some(_), None) => {
+            let groups: Vec<&str> = compact.split(',').collect();
+            // 单个逗号后不是三位数字时视为小数点，否则视为千位分隔符
+            if groups.len() == 2 && groups[1].len() != 3 {
+                compact.replace(',', ".")
+            } else {
+                compact.replace(',', "")
+            }
+        }
+        (None, Some(_)) if compact.matches('.').count() > 1 => compact.replace('.', ""),
+        _ => compact,
+    };
+
+    normalized.parse::<f64>().ok()
+}
+
+/// 计算标题中各关键词出现的位置（大小写不敏感）
+///
+/// 返回按起点排序、已合并重叠部分的字节区间 `(start, end)`，
+/// 区间边界均落在字符边界上，可直接用于切片多字节 UTF-8 标题。
+pub fn compute_highlights(title: &str, terms: &[&str]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+
+    for term in terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        for (start, _) in title.char_indices() {
+            if let Some(len) = match_len_ignore_case(&title[start..], term) {
+                ranges.push((start, start + len));
+            }
+        }
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 如果 `haystack` 以 `needle` 开头（大小写不敏感），返回匹配部分在 `haystack` 中的字节长度
+fn match_len_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let mut haystack_chars = haystack.char_indices();
+    let mut matched_len = 0;
+
+    for needle_char in needle.chars() {
+        let (index, hay_char) = haystack_chars.next()?;
+        if !hay_char.to_lowercase().eq(needle_char.to_lowercase()) {
+            return None;
+        }
+        matched_len = index + hay_char.len_utf8();
+    }
+
+    Some(matched_len)
+}
+
+/// 用于匹配比较的文本形式：NFKC 规范化后转小写，全角与半角、兼容字符视为相同
+///
+/// 只用于比较，不应替换展示给用户的原文。
+pub fn normalize_for_match(text: &str) -> String {
+    text.nfkc().collect::<String>().to_lowercase()
+}
+
+/// 查找搜索词命中的禁止关键词（大小写与全半角不敏感），返回第一个命中的关键词
+///
+/// 默认按子串匹配；`whole_word` 为 true 时要求关键词两侧不是字母或数字。
+pub fn find_forbidden_keyword<'a>(query: &str, forbidden: &'a [String], whole_word: bool) -> Option<&'a str> {
+    let query = normalize_for_match(query);
+    forbidden.iter().map(String::as_str).find(|term| {
+        let term = normalize_for_match(term.trim());
+        if term.is_empty() {
+            return false;
+        }
+        query.match_indices(&term).any(|(start, matched)| {
+            if !whole_word {
+                return true;
+            }
+            let before = query[..start].chars().next_back();
+            let after = query[start + matched.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+    })
+}
+
+/// 结果去重使用的键：优先使用标准化的 infohash，无法解析时退回规范化后的完整磁力链接
+pub fn dedupe_key(magnet_link: &str) -> String {
+    extract_infohash(magnet_link).unwrap_or_else(|| normalize_for_match(magnet_link.trim()))
+}
+
+/// 去掉去重键（见 `dedupe_key`）在 `excluded` 中的结果，保持原有顺序
+pub fn exclude_infohashes(results: Vec<SearchResult>, excluded: &std::collections::HashSet<String>) -> Vec<SearchResult> {
+    if excluded.is_empty() {
+        return results;
+    }
+    results.into_iter().filter(|r| !excluded.contains(&dedupe_key(&r.magnet_link))).collect()
+}
+
+/// 按 infohash 去重，保留首次出现的结果和顺序
+///
+/// 重复项中的来源引擎会合并到 `found_on`，缺失的大小、日期和详情页链接从重复项中补齐。
+pub fn dedupe_results(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut merged: Vec<SearchResult> = Vec::with_capacity(results.len());
+    let mut index_by_key: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+    for mut result in results {
+        let key = dedupe_key(&result.magnet_link);
+        match index_by_key.get(&key) {
+            Some(&index) => merge_duplicate(&mut merged[index], result),
+            None => {
+                record_own_provider(&mut result);
+                index_by_key.insert(key, merged.len());
+                merged.push(result);
+            }
+        }
+    }
+
+    merged
+}
+
+/// 确保结果自身的搜索引擎记录在 `found_on` 的首位
+fn record_own_provider(result: &mut SearchResult) {
+    if !result.provider_name.is_empty() && !result.found_on.contains(&result.provider_name) {
+        result.found_on.insert(0, result.provider_name.clone());
+    }
+}
+
+/// 将重复项的来源、命中关键词和缺失的字段并入 `existing`
+fn merge_duplicate(existing: &mut SearchResult, duplicate: SearchResult) {
+    // 重复项本身可能已合并过多个来源
+    let sources = std::iter::once(duplicate.provider_name).chain(duplicate.found_on);
+    for source in sources {
+        if !source.is_empty() && !existing.found_on.contains(&source) {
+            existing.found_on.push(source);
+        }
+    }
+    if existing.file_size.is_none() {
+        existing.file_size = duplicate.file_size;
+    }
+    if existing.upload_date.is_none() {
+        existing.upload_date = duplicate.upload_date;
+    }
+    if existing.source_url.is_none() {
+        existing.source_url = duplicate.source_url;
+    }
+    if existing.thumbnail_url.is_none() {
+        existing.thumbnail_url = duplicate.thumbnail_url;
+    }
+    existing.seeders = existing.seeders.max(duplicate.seeders);
+    for keyword in duplicate.matched_keywords {
+        if !existing.matched_keywords.contains(&keyword) {
+            existing.matched_keywords.push(keyword);
+        }
+    }
+}
+
+/// 用于相似度比较的标题：规范化后只保留字母数字，分隔符统一为单个空格
+fn similarity_title(title: &str) -> String {
+    normalize_for_match(title)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 按标题相似度进一步合并 infohash 不同的重复资源（如同一内容的不同编码）
+///
+/// 规范化标题的 Levenshtein 相似度（0.0-1.0）不低于 `threshold` 时视为同一资源，
+/// 保留做种数最多的一条（相同时保留先出现的），其余条目的来源并入该条。
+pub fn fuzzy_dedupe_results(results: Vec<SearchResult>, threshold: f64) -> Vec<SearchResult> {
+    let mut merged: Vec<(String, SearchResult)> = Vec::with_capacity(results.len());
+
+    for mut result in results {
+        record_own_provider(&mut result);
+        let title = similarity_title(&result.title);
+        let group = merged
+            .iter_mut()
+            .find(|(existing_title, _)| strsim::normalized_levenshtein(existing_title, &title) >= threshold);
+        match group {
+            Some((group_title, existing)) => {
+                if result.seeders > existing.seeders {
+                    *group_title = title;
+                    let previous = std::mem::replace(existing, result);
+                    merge_duplicate(existing, previous);
+                } else {
+                    merge_duplicate(existing, result);
+                }
+            }
+            None => merged.push((title, result)),
+        }
+    }
+
+    merged.into_iter().map(|(_, result)| result).collect()
+}
+
+/// 合并多个关键词各自的搜索结果，按 infohash 去重并在 `matched_keywords` 中记录命中的关键词
+pub fn merge_keyword_results(per_keyword: Vec<(String, Vec<SearchResult>)>) -> Vec<SearchResult> {
+    let combined = per_keyword.into_iter().flat_map(|(keyword, results)| {
+        results.into_iter().map(move |mut result| {
+            if !result.matched_keywords.contains(&keyword) {
+                result.matched_keywords.push(keyword.clone());
+            }
+            result
+        })
+    });
+    dedupe_results(combined.collect())
+}
+
+/// 将新结果合并到已有结果之后，按 infohash 去重并保持已有结果的顺序
+pub fn merge_results(existing: Vec<SearchResult>, new_results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut combined = existing;
+    combined.extend(new_results);
+    dedupe_results(combined)
+}
+
+/// 质量分中各项信号的权重，缺失的信号不参与加权
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QualityWeights {
+    pub seeders: f32,
+    pub size: f32,
+    pub purity: f32,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self { seeders: 0.4, size: 0.2, purity: 0.4 }
+    }
+}
+
+/// 做种数达到该值时视为满分
+const SEEDERS_FULL_SCORE: f32 = 1000.0;
+/// 大小在此区间内视为合理（字节）
+const REASONABLE_SIZE_RANGE: (f32, f32) = (100.0 * 1024.0 * 1024.0, 50.0 * 1024.0 * 1024.0 * 1024.0);
+
+/// 计算 0-100 的质量分：做种数（对数归一化）、大小合理度和 AI 纯净度（`score`）的加权平均
+///
+/// 缺失的信号连同其权重一起跳过；没有任何可用信号或权重全为 0 时返回 0。
+pub fn compute_quality_score(result: &SearchResult, weights: &QualityWeights) -> f32 {
+    let seeders = result
+        .seeders
+        .map(|s| ((s as f32).ln_1p() / SEEDERS_FULL_SCORE.ln_1p()).min(1.0));
+    let size = result
+        .file_size
+        .as_deref()
+        .and_then(parse_size_to_bytes)
+        .map(|bytes| size_reasonableness(bytes as f32));
+    let purity = result.score.map(|score| (score as f32 / 100.0).min(1.0));
+
+    let signals = [(seeders, weights.seeders), (size, weights.size), (purity, weights.purity)];
+    let (weighted, total_weight) = signals
+        .iter()
+        .filter_map(|(value, weight)| value.map(|v| (v * weight.max(0.0), weight.max(0.0))))
+        .fold((0.0, 0.0), |(sum, total), (v, w)| (sum + v, total + w));
+
+    if total_weight > 0.0 {
+        weighted / total_weight * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// 大小合理度：区间内为 1，过小或过大时按比例下降
+fn size_reasonableness(bytes: f32) -> f32 {
+    let (min, max) = REASONABLE_SIZE_RANGE;
+    if bytes <= 0.0 {
+        0.0
+    } else if bytes < min {
+        bytes / min
+    } else if bytes > max {
+        max / bytes
+    } else {
+        1.0
+    }
+}
+
+/// 为每个结果计算并写入 `quality_score`
+pub fn apply_quality_scores(results: &mut [SearchResult], weights: &QualityWeights) {
+    for result in results {
+        result.quality_score = Some(compute_quality_score(result, weights));
+    }
+}
+
+/// 可疑资源检查项，命中时以对应的 snake_case 名称写入 `suspicion_flags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspicionCheck {
+    /// 视频资源中夹带可执行文件
+    ExecutableInVideo,
+    /// 标注的画质与资源大小明显不符（如几十 MB 的 "4K"）
+    SizeMismatch,
+    /// 需要密码解压的压缩包
+    PasswordArchive,
+}
+
+impl SuspicionCheck {
+    pub fn code(self) -> &'static str {
+        match self {
+            SuspicionCheck::ExecutableInVideo => "executable_in_video",
+            SuspicionCheck::SizeMismatch => "size_mismatch",
+            SuspicionCheck::PasswordArchive => "password_archive",
+        }
+    }
+
+    fn matches(self, result: &SearchResult) -> bool {
+        match self {
+            SuspicionCheck::ExecutableInVideo => has_executable_in_video(result),
+            SuspicionCheck::SizeMismatch => has_size_mismatch(result),
+            SuspicionCheck::PasswordArchive => has_password_archive(result),
+        }
+    }
+}
+
+/// 默认启用全部检查项
+pub fn default_suspicion_checks() -> Vec<SuspicionCheck> {
+    vec![
+        SuspicionCheck::ExecutableInVideo,
+        SuspicionCheck::SizeMismatch,
+        SuspicionCheck::PasswordArchive,
+    ]
+}
+
+static VIDEO_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(mkv|mp4|avi|rmvb|wmv|mov|ts|m2ts|flv|webm)$").unwrap());
+static VIDEO_TITLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(2160p|1080p|720p|4k|uhd|bluray|blu-ray|web-?dl|webrip|hdtv|x264|x265|h\.?26[45]|hevc)\b").unwrap()
+});
+static EXECUTABLE_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(exe|scr|bat|cmd|msi|pif|vbs|lnk)$").unwrap());
+static UHD_TITLE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(2160p|4k|uhd)\b").unwrap());
+static FULL_HD_TITLE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b1080p\b").unwrap());
+static PASSWORD_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(password|passwd|pwd\.txt|密码|解压码)").unwrap());
+
+/// 标注 4K 的资源低于该大小视为可疑
+const MIN_UHD_BYTES: u64 = 1024 * 1024 * 1024;
+/// 标注 1080p 的资源低于该大小视为可疑
+const MIN_FULL_HD_BYTES: u64 = 300 * 1024 * 1024;
+
+/// 文件列表中的文件名，去掉部分站点附带的 "(1.2 GB)" 之类的大小说明
+fn listed_file_names(result: &SearchResult) -> impl Iterator<Item = &str> {
+    result
+        .file_list
+        .iter()
+        .map(|file| file.split(" (").next().unwrap_or(file).trim())
+}
+
+fn has_executable_in_video(result: &SearchResult) -> bool {
+    let has_executable = listed_file_names(result).any(|name| EXECUTABLE_FILE_REGEX.is_match(name));
+    let looks_like_video =
+        VIDEO_TITLE_REGEX.is_match(&result.title) || listed_file_names(result).any(|name| VIDEO_FILE_REGEX.is_match(name));
+    has_executable && looks_like_video
+}
+
+fn has_size_mismatch(result: &SearchResult) -> bool {
+    let Some(bytes) = result
+        .exact_length
+        .or_else(|| result.file_size.as_deref().and_then(parse_size_to_bytes))
+    else {
+        return false;
+    };
+    if UHD_TITLE_REGEX.is_match(&result.title) {
+        bytes < MIN_UHD_BYTES
+    } else if FULL_HD_TITLE_REGEX.is_match(&result.title) {
+        bytes < MIN_FULL_HD_BYTES
+    } else {
+        false
+    }
+}
+
+fn has_password_archive(result: &SearchResult) -> bool {
+    PASSWORD_REGEX.is_match(&result.title) || result.file_list.iter().any(|file| PASSWORD_REGEX.is_match(file))
+}
+
+/// 按启用的检查项返回结果命中的可疑特征
+pub fn suspicion_flags(result: &SearchResult, checks: &[SuspicionCheck]) -> Vec<String> {
+    checks
+        .iter()
+        .filter(|check| check.matches(result))
+        .map(|check| check.code().to_string())
+        .collect()
+}
+
+/// 为每个结果计算并写入 `suspicion_flags`
+pub fn apply_suspicion_flags(results: &mut [SearchResult], checks: &[SuspicionCheck]) {
+    for result in results {
+        result.suspicion_flags = suspicion_flags(result, checks);
+    }
+}
+
+/// 按文件列表和标题推断的资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Video,
+    Audio,
+    Image,
+    Software,
+    Archive,
+    Document,
+    Other,
+}
+
+static AUDIO_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(mp3|flac|ape|wav|aac|m4a|ogg|opus|dsf|dff)$").unwrap());
+static IMAGE_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(jpe?g|png|gif|bmp|webp|tiff?)$").unwrap());
+static SOFTWARE_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(exe|msi|dmg|pkg|apk|iso|deb|rpm|appimage)$").unwrap());
+static ARCHIVE_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(zip|rar|7z|tar|gz|bz2|xz)$|\.r\d{2}$|\.part\d+\.rar$").unwrap());
+static DOCUMENT_FILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\.(pdf|epub|mobi|azw3|docx?|txt|cbz|cbr)$").unwrap());
+
+fn classify_file_name(name: &str) -> Option<ContentType> {
+    [
+        (&*VIDEO_FILE_REGEX, ContentType::Video),
+        (&*AUDIO_FILE_REGEX, ContentType::Audio),
+        (&*IMAGE_FILE_REGEX, ContentType::Image),
+        (&*SOFTWARE_FILE_REGEX, ContentType::Software),
+        (&*ARCHIVE_FILE_REGEX, ContentType::Archive),
+        (&*DOCUMENT_FILE_REGEX, ContentType::Document),
+    ]
+    .into_iter()
+    .find(|(regex, _)| regex.is_match(name))
+    .map(|(_, content_type)| content_type)
+}
+
+/// 推断资源类型：以文件列表中数量最多的类型为准，数量相同时按视频、音频、图片、软件、压缩包、文档的顺序取前者；
+/// 文件列表无法识别时根据标题中的视频特征判断，仍无法判断则为 `Other`
+pub fn classify_content_type(title: &str, file_list: &[String]) -> ContentType {
+    let mut counts: Vec<(ContentType, usize)> = Vec::new();
+    for content_type in file_list
+        .iter()
+        .filter_map(|file| classify_file_name(file.split(" (").next().unwrap_or(file).trim()))
+    {
+        match counts.iter_mut().find(|(t, _)| *t == content_type) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((content_type, 1)),
+        }
+    }
+    counts.sort_by_key(|(content_type, count)| (std::cmp::Reverse(*count), *content_type as u8));
+    match counts.first() {
+        Some((content_type, _)) => *content_type,
+        None if VIDEO_TITLE_REGEX.is_match(title) => ContentType::Video,
+        None => ContentType::Other,
+    }
+}
+
+/// 结果排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// 按 AI 纯净度从高到低
+    ScoreDesc,
+    /// 按文件大小从大到小
+    SizeDesc,
+    /// 按质量分从高到低
+    QualityDesc,
+}
+
+/// 按 `key` 排序，缺失的值排在最后；主键相同时按 `original_rank` 保留站点原有顺序
+pub fn sort_results(results: &mut [SearchResult], key: SortKey) {
+    results.sort_by(|a, b| {
+        let primary = match key {
+            SortKey::ScoreDesc => b.score.cmp(&a.score),
+            SortKey::SizeDesc => {
+                let size = |r: &SearchResult| r.file_size.as_deref().and_then(parse_size_to_bytes);
+                size(b).cmp(&size(a))
+            }
+            SortKey::QualityDesc => {
+                let quality = |r: &SearchResult| r.quality_score.unwrap_or(f32::MIN);
+                quality(b).total_cmp(&quality(a))
+            }
+        };
+        primary.then_with(|| a.original_rank.cmp(&b.original_rank))
+    });
+}
+
+/// 匹配标题开头的字幕组标记，如 "[Group] Title"
+static LEADING_GROUP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*[\[【]([^\]】]+)[\]】]").unwrap());
+
+/// 匹配标题末尾的发布组，如 "Title.1080p.x264-GROUP"（可带扩展名或站点标记）
+static TRAILING_GROUP_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)-([a-z0-9]+)(?:\.(?:mkv|mp4|avi|torrent))?(?:\s*[\[【][^\]】]*[\]】])?\s*$").unwrap()
+});
+
+/// 从标题中识别发布组（统一为小写），识别不到时返回 None
+///
+/// 依次尝试末尾的 "-GROUP" 和开头的 "[Group]" 两种常见写法。
+pub fn extract_release_group(title: &str) -> Option<String> {
+    TRAILING_GROUP_REGEX
+        .captures(title)
+        .or_else(|| LEADING_GROUP_REGEX.captures(title))
+        .map(|caps| caps[1].trim().to_lowercase())
+        .filter(|group| !group.is_empty())
+}
+
+/// 从收藏行为中学到的偏好：发布组和标签的线性权重，每收藏一次加 1
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PreferenceWeights {
+    #[serde(default)]
+    pub release_group_weights: std::collections::BTreeMap<String, f32>,
+    #[serde(default)]
+    pub tag_weights: std::collections::BTreeMap<String, f32>,
+}
+
+impl PreferenceWeights {
+    /// 记录一次收藏：提升标题中发布组和各标签的权重
+    pub fn record_favorite(&mut self, title: &str, tags: &[String]) {
+        if let Some(group) = extract_release_group(title) {
+            *self.release_group_weights.entry(group).or_default() += 1.0;
+        }
+        for tag in tags.iter().map(|tag| tag.trim().to_lowercase()).filter(|tag| !tag.is_empty()) {
+            *self.tag_weights.entry(tag).or_default() += 1.0;
+        }
+    }
+
+    /// 结果的偏好加分：发布组权重与各标签权重之和
+    pub fn boost(&self, result: &SearchResult) -> f32 {
+        let group_boost = extract_release_group(&result.title)
+            .and_then(|group| self.release_group_weights.get(&group).copied())
+            .unwrap_or(0.0);
+        let tag_boost: f32 = result
+            .tags
+            .iter()
+            .flatten()
+            .filter_map(|tag| self.tag_weights.get(&tag.trim().to_lowercase()))
+            .sum();
+        group_boost + tag_boost
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.release_group_weights.is_empty() && self.tag_weights.is_empty()
+    }
+}
+
+/// 按偏好加分从高到低稳定排序，加分相同的结果保持原有顺序
+pub fn rerank_by_preferences(results: &mut [SearchResult], preferences: &PreferenceWeights) {
+    if preferences.is_empty() {
+        return;
+    }
+    // 每个结果只计算一次加分（需要解析发布组）
+    results.sort_by_cached_key(|result| std::cmp::Reverse(total_order_key(preferences.boost(result))));
+}
+
+/// 与 `f32::total_cmp` 顺序一致的整数键，便于把浮点数作为排序键缓存
+fn total_order_key(value: f32) -> i32 {
+    let bits = value.to_bits() as i32;
+    bits ^ ((((bits >> 31) as u32) >> 1) as i32)
+}
+
+/// 匹配 "2024-03-01"、"2024/3/1 12:30"、"2024.03.01 12:30:45" 等日期（可带时间）
+static UPLOAD_DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})(?:[ T]+(\d{1,2}):(\d{2})(?::(\d{2}))?)?").unwrap()
+});
+
+/// 匹配 "3天前"、"5 hours ago" 等相对时间
+static RELATIVE_DATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(\d+)\s*(分钟|小时|天|周|个月|月|年|minutes?|mins?|hours?|days?|weeks?|months?|years?)\s*(?:前|ago)").unwrap()
+});
+
+/// 将上传日期文本解析为 Unix 时间戳（秒，按 UTC 处理）
+///
+/// 支持常见的年月日格式（可带时间）以及 "3天前"、"2 days ago" 等相对时间。
+pub fn parse_upload_timestamp(text: &str) -> Option<i64> {
+    parse_upload_timestamp_at(text, chrono::Utc::now())
+}
+
+fn parse_upload_timestamp_at(text: &str, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+    let text = text.trim();
+    if let Some(caps) = UPLOAD_DATE_REGEX.captures(text) {
+        let number = |i: usize| caps.get(i).map_or(Some(0), |m| m.as_str().parse::<u32>().ok());
+        let date = chrono::NaiveDate::from_ymd_opt(caps[1].parse().ok()?, number(2)?, number(3)?)?;
+        let datetime = date.and_hms_opt(number(4)?, number(5)?, number(6)?)?;
+        return Some(datetime.and_utc().timestamp());
+    }
+
+    let lower = text.to_lowercase();
+    if lower.contains("今天") || lower == "today" {
+        return Some(now.timestamp());
+    }
+    if lower.contains("昨天") || lower == "yesterday" {
+        return Some(now.timestamp() - 86_400);
+    }
+
+    let caps = RELATIVE_DATE_REGEX.captures(&lower)?;
+    let amount: i64 = caps[1].parse().ok()?;
+    let unit = &caps[2];
+    let seconds = if unit.starts_with("min") || unit == "分钟" {
+        60
+    } else if unit.starts_with("hour") || unit == "小时" {
+        3_600
+    } else if unit.starts_with("day") || unit == "天" {
+        86_400
+    } else if unit.starts_with("week") || unit == "周" {
+        7 * 86_400
+    } else if unit.starts_with("month") || unit.ends_with('月') {
+        30 * 86_400
+    } else {
+        365 * 86_400
+    };
+    Some(now.timestamp() - amount.checked_mul(seconds)?)
+}
+
+/// 丢弃 AI 提取可信度低于 `min_confidence` 的结果，没有可信度（非 AI 提取）的结果保留
+pub fn filter_by_confidence(results: Vec<SearchResult>, min_confidence: f32) -> Vec<SearchResult> {
+    results
+        .into_iter()
+        .filter(|result| result.confidence.is_none_or(|confidence| confidence >= min_confidence))
+        .collect()
+}
+
+/// 丢弃做种数低于 `min` 的结果，做种数未知的结果按 `keep_unknown` 保留或丢弃
+pub fn filter_by_min_seeders(results: Vec<SearchResult>, min: u32, keep_unknown: bool) -> Vec<SearchResult> {
+    results
+        .into_iter()
+        .filter(|result| result.seeders.map_or(keep_unknown, |seeders| seeders >= min))
+        .collect()
+}
+
+/// 丢弃 infohash 不是40位十六进制或32位 base32 的结果，这类链接多半来自解析错误或混入的垃圾数据
+pub fn filter_invalid_infohashes(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    results
+        .into_iter()
+        .filter(|result| crate::searcher::extract_infohash(&result.magnet_link).is_some())
+        .collect()
+}
+
+/// 只保留最近 `max_age_days` 天内上传的结果
+///
+/// 使用 `upload_timestamp`，缺失时尝试解析 `upload_date`；仍无法确定上传时间的结果按 `keep_unknown` 保留或丢弃。
+pub fn filter_by_recency(results: Vec<SearchResult>, max_age_days: u32, keep_unknown: bool) -> Vec<SearchResult> {
+    let cutoff = chrono::Utc::now().timestamp() - i64::from(max_age_days) * 86_400;
+
+    results
+        .into_iter()
+        .filter(|result| {
+            let timestamp = result
+                .upload_timestamp
+                .or_else(|| result.upload_date.as_deref().and_then(parse_upload_timestamp));
+            match timestamp {
+                Some(timestamp) => timestamp >= cutoff,
+                None => keep_unknown,
+            }
+        })
+        .collect()
+}
+
+/// 标题使用的文字
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lang {
+    /// 中日韩文字（汉字、假名、谚文）
+    Cjk,
+    /// 拉丁字母
+    Latin,
+    /// 同时包含中日韩文字和拉丁字母
+    Mixed,
+    /// 没有可识别的文字（如只有数字和符号）
+    Unknown,
+}
+
+/// 判断字符是否属于中日韩文字
+fn is_cjk_char(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' |
+        '\u{3040}'..='\u{30FF}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+/// 按标题中出现的文字粗略判断语言，只区分中日韩文字与拉丁字母
+pub fn detect_title_language(title: &str) -> Lang {
+    let has_cjk = title.chars().any(is_cjk_char);
+    // 拉丁字母包括带变音符号的扩展字母
+    let has_latin = title.chars().any(|c| c.is_alphabetic() && (c as u32) < 0x0250);
+
+    match (has_cjk, has_latin) {
+        (true, true) => Lang::Mixed,
+        (true, false) => Lang::Cjk,
+        (false, true) => Lang::Latin,
+        (false, false) => Lang::Unknown,
+    }
+}
+
+/// 只保留标题语言在 `allowed` 中的结果
+///
+/// 混合文字的标题只要包含任一允许的语言即保留；无法识别文字的标题总是保留。
+pub fn filter_by_language(results: Vec<SearchResult>, allowed: &[Lang]) -> Vec<SearchResult> {
+    if allowed.is_empty() {
+        return results;
+    }
+
+    results
+        .into_iter()
+        .filter(|result| match detect_title_language(&result.title) {
+            Lang::Unknown => true,
+            Lang::Mixed => allowed.iter().any(|lang| matches!(lang, Lang::Cjk | Lang::Latin | Lang::Mixed)),
+            lang => allowed.contains(&lang),
+        })
+        .collect()
+}
+
+/// 转义 Markdown 表格单元格中的特殊字符，换行替换为空格以免破坏表格
+fn escape_markdown_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 将搜索结果导出为 Markdown 表格（标题、大小、做种数、磁力链接），便于分享
+pub fn export_results_as_markdown(results: &[SearchResult]) -> String {
+    let mut markdown = String::from("| Title | Size | Seeders | Magnet |\n| --- | --- | --- | --- |\n");
+    for result in results {
+        // 链接地址中的空白、括号和竖线会破坏链接或表格，按 URL 编码处理
+        let link = result
+            .magnet_link
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29")
+            .replace('|', "%7C");
+        markdown.push_str(&format!(
+            "| {} | {} | {} | [magnet]({}) |\n",
+            escape_markdown_cell(&result.title),
+            result.file_size.as_deref().map_or_else(|| "-".to_string(), escape_markdown_cell),
+            result.seeders.map_or_else(|| "-".to_string(), |seeders| seeders.to_string()),
+            link
+        ));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_to_bytes_locale_formats() {
+        assert_eq!(parse_size_to_bytes("2,1GB"), Some((2.1 * 1024f64.powi(3)).round() as u64));
+        assert_eq!(parse_size_to_bytes("1 024 MB"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size_to_bytes("700MB"), Some(700 * 1024 * 1024));
+        assert_eq!(parse_size_to_bytes("大小: 1.5 GB"), Some((1.5 * 1024f64.powi(3)) as u64));
+        assert_eq!(parse_size_to_bytes("1.234,5 MB"), Some((1234.5 * 1024f64.powi(2)) as u64));
+        assert_eq!(parse_size_to_bytes("no size here"), None);
+    }
+
+    #[test]
+    fn test_format_size_selects_unit_and_rounds() {
+        assert_eq!(format_size(512, 2), "512 B");
+        assert_eq!(format_size(1536, 2), "1.50 KB");
+        assert_eq!(format_size(5 * 1024 * 1024 + 1024 * 1024 / 3, 1), "5.3 MB");
+        assert_eq!(format_size(2_254_857_830, 2), "2.10 GB");
+        assert_eq!(format_size(3 * 1024u64.pow(4), 0), "3 TB");
+        // 四舍五入到 1024 时进位到下一个单位
+        assert_eq!(format_size(1024 * 1024 - 1, 2), "1.00 MB");
+    }
+
+    #[test]
+    fn test_normalize_size_display_keeps_unparseable_sizes() {
+        let mut results = vec![
+            result("A", "magnet:?xt=urn:btih:1", "a"),
+            result("B", "magnet:?xt=urn:btih:2", "a"),
+        ];
+        results[0].file_size = Some("2,1 GB".to_string());
+        results[1].file_size = Some("unknown".to_string());
+
+        normalize_size_display(&mut results, 2);
+        assert_eq!(results[0].file_size.as_deref(), Some("2.10 GB"));
+        assert_eq!(results[1].file_size.as_deref(), Some("unknown"));
+    }
+
+    #[test]
+    fn test_title_cleaning_pipeline_follows_configured_steps() {
+        let title = "[y5y4.com]  Movie 2023 1080p BluRay x264 www.example.com";
+        assert_eq!(clean_title(title, &default_title_cleaning_steps()), "Movie 2023 1080p BluRay x264");
+
+        let aggressive = [
+            TitleCleaningStep::StripBrackets,
+            TitleCleaningStep::StripUrls,
+            TitleCleaningStep::StripReleaseTags,
+            TitleCleaningStep::CollapseWhitespace,
+        ];
+        assert_eq!(clean_title(title, &aggressive), "Movie 2023");
+
+        // 只合并空白时保留其他内容
+        assert_eq!(
+            clean_title(title, &[TitleCleaningStep::CollapseWhitespace]),
+            "[y5y4.com] Movie 2023 1080p BluRay x264 www.example.com"
+        );
+        assert_eq!(clean_title(title, &[]), title);
+    }
+
+    #[test]
+    fn test_fix_mojibake_repairs_misdecoded_utf8() {
+        let (garbled, _, _) = encoding_rs::WINDOWS_1252.decode("电影 Movie".as_bytes());
+        assert_eq!(fix_mojibake(&garbled), "电影 Movie");
+        // 正常的文本保持不变
+        assert_eq!(fix_mojibake("电影 Movie"), "电影 Movie");
+        assert_eq!(fix_mojibake("Café"), "Café");
+    }
+
+    #[test]
+    fn test_truncate_title_at_word_boundary() {
+        let title = "Movie 2023 1080p BluRay visit www.example.com for more great downloads";
+        let truncated = truncate_title(title, 28).unwrap();
+        assert_eq!(truncated, "Movie 2023 1080p BluRay…");
+        assert!(truncated.chars().count() <= 28);
+        assert_eq!(truncate_title(title, 30).unwrap(), "Movie 2023 1080p BluRay visit…");
+
+        // 没有空白可断开时按字符截断
+        assert_eq!(truncate_title("电影名称第一季全集高清", 6).unwrap(), "电影名称第…");
+    }
+
+    #[test]
+    fn test_clamp_highlights_after_truncation() {
+        let title = "Movie 2023 1080p BluRay visit www.example.com";
+        let truncated = truncate_title(title, 20).unwrap();
+        assert_eq!(truncated, "Movie 2023 1080p…");
+
+        // "1080p" 在可见部分内，"BluRay" 在截断处之后，"2023 1080p Blu" 跨越截断处
+        let mut highlights = compute_highlights(title, &["1080p", "bluray"]);
+        clamp_highlights(&mut highlights, &truncated);
+        assert_eq!(highlights, vec![(11, 16)]);
+
+        let mut spanning = vec![(6, 20)];
+        clamp_highlights(&mut spanning, &truncated);
+        assert_eq!(spanning, vec![(6, 16)]);
+        assert_eq!(&truncated[6..16], "2023 1080p");
+    }
+
+    #[test]
+    fn test_truncate_title_leaves_short_titles_untouched() {
+        assert_eq!(truncate_title("Movie 2023 1080p", 30), None);
+        assert_eq!(truncate_title("exactly ten", 11), None);
+    }
+
+    #[test]
+    fn test_compute_highlights_ascii_title() {
+        let title = "Spider-Man.No.Way.Home.2021.1080p";
+        let ranges = compute_highlights(title, &["spider", "1080P"]);
+        assert_eq!(ranges, vec![(0, 6), (28, 33)]);
+        assert_eq!(&title[28..33], "1080p");
+    }
+
+    #[test]
+    fn test_compute_highlights_cjk_title() {
+        let title = "[中文字幕] 流浪地球 4K 流浪地球2";
+        let ranges = compute_highlights(title, &["流浪地球", "4k"]);
+        let highlighted: Vec<&str> = ranges.iter().map(|&(s, e)| &title[s..e]).collect();
+        assert_eq!(highlighted, vec!["流浪地球", "4K", "流浪地球"]);
+        assert_eq!(ranges[0], (15, 27));
+    }
+
+    fn result(title: &str, magnet_link: &str, provider_name: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            magnet_link: magnet_link.to_string(),
+            provider_name: provider_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_results_as_markdown_escapes_titles() {
+        let mut first = result("Movie | Part_1 [1080p]", "magnet:?xt=urn:btih:1&dn=Movie (2023)", "a");
+        first.file_size = Some("1.50 GB".to_string());
+        first.seeders = Some(12);
+        let second = result("电影\n第二部", "magnet:?xt=urn:btih:2", "b");
+
+        let markdown = export_results_as_markdown(&[first, second]);
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "| Title | Size | Seeders | Magnet |");
+        assert_eq!(lines[1], "| --- | --- | --- | --- |");
+        assert_eq!(
+            lines[2],
+            "| Movie \\| Part\\_1 \\[1080p\\] | 1.50 GB | 12 | [magnet](magnet:?xt=urn:btih:1&dn=Movie%20%282023%29) |"
+        );
+        assert_eq!(lines[3], "| 电影 第二部 | - | - | [magnet](magnet:?xt=urn:btih:2) |");
+
+        // 每一行的未转义竖线数量相同，表格列数一致
+        let columns = |line: &str| line.replace("\\|", "").matches('|').count();
+        assert!(lines.iter().all(|line| columns(line) == 5));
+    }
+
+    #[test]
+    fn test_dedupe_results_records_all_providers() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let from_clmclm = result("Movie", &format!("magnet:?xt=urn:btih:{hash}"), "clmclm.com");
+        let mut from_custom = result("Movie 1080p", &format!("magnet:?xt=urn:btih:{}&dn=Movie", hash.to_uppercase()), "MyEngine");
+        from_custom.file_size = Some("1.2GB".to_string());
+        let other = result("Other", "magnet:?xt=urn:btih:ffffffffffffffffffffffffffffffffffffffff", "MyEngine");
+
+        let merged = dedupe_results(vec![from_clmclm, from_custom, other]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].title, "Movie");
+        assert_eq!(merged[0].found_on, vec!["clmclm.com", "MyEngine"]);
+        assert_eq!(merged[0].file_size.as_deref(), Some("1.2GB"));
+        assert_eq!(merged[1].found_on, vec!["MyEngine"]);
+    }
+
+    #[test]
+    fn test_merge_second_page_into_existing_results() {
+        let first_page = dedupe_results(vec![
+            result("A", "magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "clmclm.com"),
+            result("B", "magnet:?xt=urn:btih:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb", "clmclm.com"),
+        ]);
+        let second_page = dedupe_results(vec![
+            result("B again", "magnet:?xt=urn:btih:BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB", "MyEngine"),
+            result("C", "magnet:?xt=urn:btih:cccccccccccccccccccccccccccccccccccccccc", "MyEngine"),
+        ]);
+
+        let merged = merge_results(first_page, second_page);
+
+        let titles: Vec<_> = merged.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "C"]);
+        assert_eq!(merged[1].found_on, vec!["clmclm.com", "MyEngine"]);
+    }
+
+    #[test]
+    fn test_detect_title_language() {
+        assert_eq!(detect_title_language("流浪地球"), Lang::Cjk);
+        assert_eq!(detect_title_language("The Wandering Earth"), Lang::Latin);
+        assert_eq!(detect_title_language("流浪地球 The Wandering Earth 2019"), Lang::Mixed);
+        assert_eq!(detect_title_language("2019"), Lang::Unknown);
+    }
+
+    #[test]
+    fn test_filter_by_language_keeps_mixed_titles() {
+        let results = vec![
+            result("流浪地球", "magnet:?xt=urn:btih:1", "clmclm.com"),
+            result("The Wandering Earth", "magnet:?xt=urn:btih:2", "clmclm.com"),
+            result("流浪地球 The Wandering Earth", "magnet:?xt=urn:btih:3", "clmclm.com"),
+        ];
+
+        let latin_only = filter_by_language(results.clone(), &[Lang::Latin]);
+        let titles: Vec<_> = latin_only.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["The Wandering Earth", "流浪地球 The Wandering Earth"]);
+
+        let cjk_only = filter_by_language(results, &[Lang::Cjk]);
+        let titles: Vec<_> = cjk_only.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["流浪地球", "流浪地球 The Wandering Earth"]);
+    }
+
+    fn scored(file_size: Option<&str>, seeders: Option<u32>, score: Option<u8>) -> SearchResult {
+        SearchResult {
+            file_size: file_size.map(str::to_string),
+            seeders,
+            score,
+            ..result("Movie", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "clmclm.com")
+        }
+    }
+
+    #[test]
+    fn test_movie_with_setup_exe_is_flagged() {
+        let movie = SearchResult {
+            title: "Big Buck Bunny 2008 1080p BluRay x264".to_string(),
+            file_list: vec!["Big.Buck.Bunny.2008.1080p.mkv (2.1 GB)".to_string(), "Setup.exe (1.2 MB)".to_string()],
+            ..scored(Some("2.1 GB"), None, None)
+        };
+        assert_eq!(suspicion_flags(&movie, &default_suspicion_checks()), vec!["executable_in_video"]);
+        // 未启用的检查项不产生标记
+        assert!(suspicion_flags(&movie, &[SuspicionCheck::SizeMismatch]).is_empty());
+
+        let installer = SearchResult {
+            title: "Some Tool v2.0".to_string(),
+            file_list: vec!["Setup.exe".to_string()],
+            ..scored(Some("20 MB"), None, None)
+        };
+        assert!(suspicion_flags(&installer, &default_suspicion_checks()).is_empty());
+    }
+
+    #[test]
+    fn test_tiny_4k_torrent_is_flagged() {
+        let mut results = vec![
+            SearchResult { title: "Movie 2023 4K HDR".to_string(), ..scored(Some("45 MB"), None, None) },
+            SearchResult { title: "Movie 2023 2160p UHD".to_string(), ..scored(Some("18.4 GB"), None, None) },
+            SearchResult {
+                title: "Movie 2023 4K 密码见解压码.txt".to_string(),
+                ..scored(Some("12 GB"), None, None)
+            },
+        ];
+        apply_suspicion_flags(&mut results, &default_suspicion_checks());
+
+        assert_eq!(results[0].suspicion_flags, vec!["size_mismatch"]);
+        assert!(results[1].suspicion_flags.is_empty());
+        assert_eq!(results[2].suspicion_flags, vec!["password_archive"]);
+    }
+
+    #[test]
+    fn test_classify_content_type_by_majority_of_files() {
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let album = files(&["01.flac (30 MB)", "02.flac (28 MB)", "cover.jpg (1 MB)"]);
+        assert_eq!(classify_content_type("Album 2023", &album), ContentType::Audio);
+        let movie = files(&["Movie.mkv (8 GB)", "Movie.srt"]);
+        assert_eq!(classify_content_type("Movie", &movie), ContentType::Video);
+        assert_eq!(classify_content_type("Movie 2023 1080p WEB-DL", &[]), ContentType::Video);
+        assert_eq!(classify_content_type("Some Collection", &[]), ContentType::Other);
+    }
+
+    #[test]
+    fn test_quality_score_follows_weights() {
+        let popular = scored(Some("4 GB"), Some(1000), Some(50));
+        let pure = scored(Some("4 GB"), Some(1), Some(100));
+
+        let seeder_heavy = QualityWeights { seeders: 1.0, size: 0.0, purity: 0.0 };
+        let purity_heavy = QualityWeights { seeders: 0.0, size: 0.0, purity: 1.0 };
+        assert!((compute_quality_score(&popular, &seeder_heavy) - 100.0).abs() < 1e-3);
+        assert!(compute_quality_score(&popular, &seeder_heavy) > compute_quality_score(&pure, &seeder_heavy));
+        assert!(compute_quality_score(&pure, &purity_heavy) > compute_quality_score(&popular, &purity_heavy));
+
+        let mut results = vec![popular, pure];
+        apply_quality_scores(&mut results, &purity_heavy);
+        sort_results(&mut results, SortKey::QualityDesc);
+        assert_eq!(results[0].score, Some(100));
+    }
+
+    #[test]
+    fn test_quality_score_with_missing_signals() {
+        let weights = QualityWeights::default();
+
+        // 只有大小时只按大小计算
+        let size_only = compute_quality_score(&scored(Some("4 GB"), None, None), &weights);
+        assert!((size_only - 100.0).abs() < 1e-3);
+
+        let nothing = compute_quality_score(&scored(None, None, None), &weights);
+        assert_eq!(nothing, 0.0);
+
+        let zero_weights = QualityWeights { seeders: 0.0, size: 0.0, purity: 0.0 };
+        let score = compute_quality_score(&scored(Some("4 GB"), Some(10), Some(80)), &zero_weights);
+        assert!(score.is_finite());
+        assert_eq!(score, 0.0);
+    }
+
+    #[test]
+    fn test_sort_keeps_original_rank_among_ties() {
+        let ranked = |rank: usize, score: Option<u8>| SearchResult {
+            title: format!("rank {rank}"),
+            original_rank: rank,
+            ..scored(Some("1 GB"), None, score)
+        };
+        let mut results = vec![ranked(3, Some(80)), ranked(0, Some(80)), ranked(2, Some(95)), ranked(1, Some(80)), ranked(4, None)];
+
+        sort_results(&mut results, SortKey::ScoreDesc);
+        let titles: Vec<_> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["rank 2", "rank 0", "rank 1", "rank 3", "rank 4"]);
+
+        // 大小全部相同时完全按站点原有顺序
+        sort_results(&mut results, SortKey::SizeDesc);
+        let ranks: Vec<_> = results.iter().map(|r| r.original_rank).collect();
+        assert_eq!(ranks, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_merge_keyword_results_tags_overlapping_magnet() {
+        let shared = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567";
+        let first = vec![
+            result("Movie 1080p", shared, "clmclm.com"),
+            result("Movie Extras", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "clmclm.com"),
+        ];
+        let second = vec![
+            result("Movie 1080p", shared, "MyEngine"),
+            result("Sequel", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "MyEngine"),
+        ];
+
+        let merged = merge_keyword_results(vec![("movie".to_string(), first), ("film".to_string(), second)]);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].matched_keywords, vec!["movie", "film"]);
+        assert_eq!(merged[0].found_on, vec!["clmclm.com", "MyEngine"]);
+        assert_eq!(merged[1].matched_keywords, vec!["movie"]);
+        assert_eq!(merged[2].matched_keywords, vec!["film"]);
+    }
+
+    #[test]
+    fn test_parse_upload_timestamp_formats() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-03-10T00:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        let day = |d: &str| chrono::DateTime::parse_from_rfc3339(d).unwrap().timestamp();
+
+        assert_eq!(parse_upload_timestamp_at("2024-03-01", now), Some(day("2024-03-01T00:00:00Z")));
+        assert_eq!(parse_upload_timestamp_at("2024/3/1 12:30", now), Some(day("2024-03-01T12:30:00Z")));
+        assert_eq!(parse_upload_timestamp_at("3天前", now), Some(day("2024-03-07T00:00:00Z")));
+        assert_eq!(parse_upload_timestamp_at("2 hours ago", now), Some(day("2024-03-09T22:00:00Z")));
+        assert_eq!(parse_upload_timestamp_at("2024-13-01", now), None);
+        assert_eq!(parse_upload_timestamp_at("unknown", now), None);
+    }
+
+    #[test]
+    fn test_filter_by_min_seeders_handles_unknown_counts() {
+        let mut above = result("Above", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "a");
+        above.seeders = Some(25);
+        let mut exact = result("Exact", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "a");
+        exact.seeders = Some(10);
+        let mut below = result("Below", "magnet:?xt=urn:btih:3333333333333333333333333333333333333333", "a");
+        below.seeders = Some(2);
+        let unknown = result("Unknown", "magnet:?xt=urn:btih:4444444444444444444444444444444444444444", "a");
+        let results = vec![above, exact, below, unknown];
+
+        let kept = filter_by_min_seeders(results.clone(), 10, true);
+        let titles: Vec<&str> = kept.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Above", "Exact", "Unknown"]);
+
+        let kept = filter_by_min_seeders(results, 10, false);
+        let titles: Vec<&str> = kept.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Above", "Exact"]);
+    }
+
+    #[test]
+    fn test_filter_by_recency_handles_undated_results() {
+        let now = chrono::Utc::now().timestamp();
+        let mut recent = result("Recent", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "a");
+        recent.upload_timestamp = Some(now - 2 * 86_400);
+        let mut old = result("Old", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "a");
+        old.upload_date = Some("2001-01-01".to_string());
+        let undated = result("Undated", "magnet:?xt=urn:btih:3333333333333333333333333333333333333333", "a");
+        let results = vec![recent, old, undated];
+
+        let kept = filter_by_recency(results.clone(), 7, true);
+        let titles: Vec<&str> = kept.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Recent", "Undated"]);
+
+        let kept = filter_by_recency(results, 7, false);
+        let titles: Vec<&str> = kept.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Recent"]);
+    }
+
+    #[test]
+    fn test_normalize_for_match_folds_width_variants() {
+        assert_eq!(normalize_for_match("ＡＢＣ　１０８０ｐ"), normalize_for_match("abc 1080P"));
+        // 组合字符与预组合字符视为相同
+        assert_eq!(normalize_for_match("Cafe\u{0301}"), normalize_for_match("Café"));
+
+        let full_width = dedupe_key("magnet:?xt=urn:sha1:ＡＢＣ");
+        assert_eq!(full_width, dedupe_key("magnet:?xt=urn:sha1:abc"));
+    }
+
+    #[test]
+    fn test_fuzzy_dedupe_groups_similar_titles() {
+        let mut x264 = result("Some.Movie.2023.1080p.BluRay.x264", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "a");
+        x264.seeders = Some(10);
+        let mut x265 = result("Some Movie 2023 1080p BluRay x265", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "b");
+        x265.seeders = Some(50);
+        let other = result("Another Film 2021 720p WEB", "magnet:?xt=urn:btih:3333333333333333333333333333333333333333", "a");
+
+        let grouped = fuzzy_dedupe_results(vec![x264.clone(), x265.clone(), other.clone()], 0.9);
+        assert_eq!(grouped.len(), 2);
+        // 保留做种数最多的一条，并记录另一条的来源
+        assert_eq!(grouped[0].title, "Some Movie 2023 1080p BluRay x265");
+        assert_eq!(grouped[0].found_on, vec!["b", "a"]);
+        assert_eq!(grouped[1].title, "Another Film 2021 720p WEB");
+
+        // 阈值高于相似度时保持分开
+        let separate = fuzzy_dedupe_results(vec![x264, x265, other], 0.99);
+        assert_eq!(separate.len(), 3);
+    }
+
+    #[test]
+    fn test_extract_release_group() {
+        assert_eq!(extract_release_group("Some.Movie.2023.1080p.BluRay.x264-SPARKS").as_deref(), Some("sparks"));
+        assert_eq!(extract_release_group("Show.S01E01.720p.WEB-DL.x265-NTb.mkv [rartv]").as_deref(), Some("ntb"));
+        assert_eq!(extract_release_group("[SubsPlease] Anime - 01 (1080p)").as_deref(), Some("subsplease"));
+        assert_eq!(extract_release_group("Plain title without group"), None);
+    }
+
+    #[test]
+    fn test_rerank_by_preferences_boosts_favorited_group() {
+        let mut preferences = PreferenceWeights::default();
+        preferences.record_favorite("Old.Movie.2020.1080p.BluRay.x264-SPARKS", &["1080p".to_string()]);
+        preferences.record_favorite("Another.Movie.2021.2160p.BluRay.x265-SPARKS", &[]);
+
+        let mut results = vec![
+            result("New.Movie.2024.1080p.WEB.x264-OTHER", "magnet:?xt=urn:btih:1111111111111111111111111111111111111111", "a"),
+            result("Unrelated title", "magnet:?xt=urn:btih:2222222222222222222222222222222222222222", "a"),
+            result("New.Movie.2024.1080p.BluRay.x264-SPARKS", "magnet:?xt=urn:btih:3333333333333333333333333333333333333333", "a"),
+        ];
+        results[1].tags = Some(vec!["1080P".to_string()]);
+        rerank_by_preferences(&mut results, &preferences);
+
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["New.Movie.2024.1080p.BluRay.x264-SPARKS", "Unrelated title", "New.Movie.2024.1080p.WEB.x264-OTHER"]);
+    }
+}
@@ -13,11 +13,16 @@ pub enum ErrorCode {
     SearchNoEngines,
     SearchTimeout,
     SearchFailed(String),
+    SearchForbiddenKeyword(String),
+    MagnetInvalid,
+    InfohashInvalid,
     
     // 收藏相关错误
     FavoritesDuplicate,
     FavoritesNotFound,
     FavoritesQuotaExceeded,
+    FolderNotFound,
+    FolderInvalidParent,
     
     // 搜索引擎相关错误
     EngineNotFound,
@@ -46,9 +51,14 @@ impl ErrorCode {
             ErrorCode::SearchNoEngines => "ERR_SEARCH_NO_ENGINES".to_string(),
             ErrorCode::SearchTimeout => "ERR_SEARCH_TIMEOUT".to_string(),
             ErrorCode::SearchFailed(_) => "ERR_SEARCH_FAILED".to_string(),
+            ErrorCode::SearchForbiddenKeyword(_) => "ERR_SEARCH_FORBIDDEN_KEYWORD".to_string(),
+            ErrorCode::MagnetInvalid => "ERR_MAGNET_INVALID".to_string(),
+            ErrorCode::InfohashInvalid => "ERR_INFOHASH_INVALID".to_string(),
             ErrorCode::FavoritesDuplicate => "ERR_FAVORITES_DUPLICATE".to_string(),
             ErrorCode::FavoritesNotFound => "ERR_FAVORITES_NOT_FOUND".to_string(),
             ErrorCode::FavoritesQuotaExceeded => "ERR_FAVORITES_QUOTA_EXCEEDED".to_string(),
+            ErrorCode::FolderNotFound => "ERR_FOLDER_NOT_FOUND".to_string(),
+            ErrorCode::FolderInvalidParent => "ERR_FOLDER_INVALID_PARENT".to_string(),
             ErrorCode::EngineNotFound => "ERR_ENGINE_NOT_FOUND".to_string(),
             ErrorCode::EngineNotDeletable => "ERR_ENGINE_NOT_DELETABLE".to_string(),
             ErrorCode::EngineInvalid => "ERR_ENGINE_INVALID".to_string(),
@@ -70,6 +80,11 @@ impl ErrorCode {
                 params.insert("details".to_string(), details.clone());
                 Some(params)
             }
+            ErrorCode::SearchForbiddenKeyword(keyword) => {
+                let mut params = HashMap::new();
+                params.insert("keyword".to_string(), keyword.clone());
+                Some(params)
+            }
             ErrorCode::UnknownError(code) => {
                 let mut params = HashMap::new();
                 params.insert("code".to_string(), code.clone());
@@ -98,6 +113,49 @@ impl ErrorCode {
     }
 }
 
+/// 命令返回给前端的错误，序列化为 `{ kind, code, params, message }`
+///
+/// 前端通过 `kind` 区分需要用户修改设置的配置错误和其他失败；`message` 为已本地化的提示。
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// 配置问题，例如没有启用任何搜索引擎
+    Config(ErrorCode),
+    /// 其他错误，只有本地化后的信息
+    Other(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Config(code) => f.write_str(&translate_error(code)),
+            AppError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (kind, error) = match self {
+            AppError::Config(code) => ("config", Some(code.to_frontend_error())),
+            AppError::Other(_) => ("other", None),
+        };
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("code", &error.as_ref().map(|e| &e.code))?;
+        state.serialize_field("params", &error.and_then(|e| e.params))?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
 /// 国际化消息结构
 #[derive(Debug, Clone, Deserialize)]
 pub struct Messages {
@@ -242,9 +300,14 @@ impl I18nManager {
             ErrorCode::SearchNoEngines => "errors.search_no_engines",
             ErrorCode::SearchTimeout => "errors.search_timeout",
             ErrorCode::SearchFailed(_) => "errors.search_failed",
+            ErrorCode::SearchForbiddenKeyword(_) => "errors.search_forbidden_keyword",
+            ErrorCode::MagnetInvalid => "errors.magnet_invalid",
+            ErrorCode::InfohashInvalid => "errors.infohash_invalid",
             ErrorCode::FavoritesDuplicate => "errors.favorites_duplicate",
             ErrorCode::FavoritesNotFound => "errors.favorites_not_found",
             ErrorCode::FavoritesQuotaExceeded => "errors.favorites_quota_exceeded",
+            ErrorCode::FolderNotFound => "errors.folder_not_found",
+            ErrorCode::FolderInvalidParent => "errors.folder_invalid_parent",
             ErrorCode::EngineNotFound => "errors.engine_not_found",
             ErrorCode::EngineNotDeletable => "errors.engine_not_deletable",
             ErrorCode::EngineInvalid => "errors.engine_invalid",
@@ -362,6 +425,19 @@ mod tests {
         assert!(error_with_params.get_params().is_some());
     }
 
+    #[test]
+    fn test_app_error_serializes_kind_and_code() {
+        let config = serde_json::to_value(AppError::Config(ErrorCode::SearchNoEngines)).unwrap();
+        assert_eq!(config["kind"], "config");
+        assert_eq!(config["code"], "ERR_SEARCH_NO_ENGINES");
+        assert_eq!(config["message"], translate_error(&ErrorCode::SearchNoEngines));
+
+        let other = serde_json::to_value(AppError::from("boom".to_string())).unwrap();
+        assert_eq!(other["kind"], "other");
+        assert!(other["code"].is_null());
+        assert_eq!(other["message"], "boom");
+    }
+
     #[test]
     fn test_i18n_manager() {
         let manager = I18nManager::new();
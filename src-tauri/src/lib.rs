@@ -1,6 +1,13 @@
-// This file is now primarily for module declarations if needed.
-// All application logic and command handlers are now in main.rs.
+// 核心库：搜索、抓取、分析与应用状态，不依赖 Tauri 运行时即可使用（CLI、无界面服务等）。
+// Tauri 命令处理仍在 main.rs 中，只做参数转换和状态保存。
 
 pub mod searcher;
 pub mod llm_service;
+pub mod app_state;
 pub mod i18n;
+pub mod filter;
+pub mod net;
+pub mod trace;
+
+#[cfg(test)]
+mod test_support;
@@ -2,8 +2,14 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use crate::trace::{self, trace_println};
 
 /// 智能处理API Base URL，为不同的API服务添加正确的路径
 fn normalize_api_base(api_base: &str) -> String {
@@ -37,12 +43,33 @@ pub struct LlmConfig {
     pub model: String,
     #[serde(default = "default_batch_size")]
     pub batch_size: u32,
+    /// 单独分析一个项目的时限（秒），为 0 时不限制
+    #[serde(default = "default_single_item_timeout_secs")]
+    pub single_item_timeout_secs: u64,
+    /// 同时进行的 LLM 请求上限，为 0 时按 1 处理；为 None 时保持原有行为：提取不限制，分析逐批进行
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// 第一阶段提取时发送给模型的 HTML 最大字符数，超出部分截断；为 None 时使用默认值，分析阶段不使用
+    #[serde(default)]
+    pub max_html_chars: Option<usize>,
 }
 
 fn default_batch_size() -> u32 {
     5
 }
 
+pub fn default_single_item_timeout_secs() -> u64 {
+    30
+}
+
+/// 默认的 HTML 截断长度：80k 字符约 120k tokens，给 250k tokens 的模型留出余量
+pub const DEFAULT_MAX_HTML_CHARS: usize = 80_000;
+
+/// 实际使用的 HTML 截断长度，未设置或为 0 时使用默认值
+pub fn html_char_limit(max_html_chars: Option<usize>) -> usize {
+    max_html_chars.filter(|&chars| chars > 0).unwrap_or(DEFAULT_MAX_HTML_CHARS)
+}
+
 // --- 1. 第一阶段：从HTML中提取基础信息 ---
 
 /// 第一阶段：从HTML中提取的单个原始、未经处理的磁力链接信息
@@ -52,6 +79,16 @@ pub struct ExtractedBasicInfo {
     pub magnet_link: String,
     pub file_size: Option<String>,
     pub source_url: Option<String>,
+    /// 条目的海报/缩略图地址，可能是相对地址
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+    /// 模型认为该条目是真实种子而非误识别的把握（0.0-1.0），未给出时为 1.0
+    #[serde(default = "default_confidence")]
+    pub confidence: f32,
+}
+
+pub fn default_confidence() -> f32 {
+    1.0
 }
 
 /// 第一阶段：批量提取结果
@@ -60,19 +97,52 @@ pub struct BatchExtractBasicInfoResult {
     pub results: Vec<ExtractedBasicInfo>,
 }
 
+/// 第一阶段模型返回的文本无法解析为JSON，保留原始响应供调用方补救
+#[derive(Debug, Clone)]
+pub struct ExtractionParseError {
+    /// 模型返回的原始文本
+    pub raw_response: String,
+    /// serde_json 给出的解析错误
+    pub message: String,
+}
+
+impl std::fmt::Display for ExtractionParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "解析第一阶段JSON失败: {}. Raw text: {}", self.message, self.raw_response)
+    }
+}
+
+impl std::error::Error for ExtractionParseError {}
+
+/// 用示例HTML测试第一阶段提取的结果，用于排查提示词和模型问题
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExtractionSampleResult {
+    /// 成功解析出的条目，解析失败时为空
+    pub results: Vec<ExtractedBasicInfo>,
+    /// 模型返回的原始文本
+    pub raw_response: String,
+    /// JSON 解析失败时的错误信息
+    pub parse_error: Option<String>,
+}
+
 // --- 2. 第二阶段：分析分数和标签 ---
 
 /// 第二阶段：对单个磁力链接的文件列表进行详细分析后的最终结果
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DetailedAnalysisResult {
     pub title: String,           // 精简后的标题
-    pub purity_score: u8,        // 纯净度分数 (由LLM计算)
+    #[serde(default)]
+    pub original_title: String,  // AI 清理前的标题
+    pub purity_score: Option<u8>, // 纯净度分数 (由LLM计算，分析失败时可为空)
     pub tags: Vec<String>,       // 智能标签
     pub magnet_link: String,     // 原始磁力链接 (从第一阶段透传)
     pub file_size: Option<String>, // 原始文件大小 (从第一阶段透传)
     pub file_list: Vec<String>, // 文件列表
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,   // 错误信息 (如果分析失败)
+    /// 计入纯净度后重新计算的质量分，见 `filter::compute_quality_score`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f32>,
 }
 
 // （已移除未使用的 LlmFileAnalysis 结构体）
@@ -92,15 +162,180 @@ pub struct BatchAnalysisResult {
     pub tags: Vec<String>,
 }
 
+/// 流式分析中单个项目的结果，`index` 为该项目在请求中的位置
+#[derive(Serialize, Debug, Clone)]
+pub struct StreamedAnalysis {
+    pub index: usize,
+    pub result: BatchAnalysisResult,
+}
+
+// --- 分析结果缓存 ---
+
+/// 分析缓存最多保留的条目数
+const ANALYSIS_CACHE_CAPACITY: usize = 2000;
+
+/// 分析缓存的统计信息
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AnalysisCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// 按 标题 + 文件列表 + 模型 缓存第二阶段的分析结果，避免重复请求LLM
+#[derive(Default)]
+struct AnalysisCache {
+    entries: HashMap<u64, BatchAnalysisResult>,
+    insertion_order: VecDeque<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+impl AnalysisCache {
+    fn get(&mut self, key: u64) -> Option<BatchAnalysisResult> {
+        match self.entries.get(&key) {
+            Some(result) => {
+                self.hits += 1;
+                Some(result.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: u64, result: BatchAnalysisResult) {
+        if self.entries.insert(key, result).is_none() {
+            self.insertion_order.push_back(key);
+        }
+        // 超出容量时淘汰最早写入的条目
+        while self.entries.len() > ANALYSIS_CACHE_CAPACITY {
+            match self.insertion_order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+static ANALYSIS_CACHE: Lazy<Mutex<AnalysisCache>> = Lazy::new(|| Mutex::new(AnalysisCache::default()));
+
+/// 计算分析缓存的键
+fn analysis_cache_key(item: &BatchAnalysisItem, model: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    item.title.hash(&mut hasher);
+    item.file_list.hash(&mut hasher);
+    model.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 获取分析缓存的统计信息
+pub fn analysis_cache_stats() -> AnalysisCacheStats {
+    let cache = ANALYSIS_CACHE.lock().unwrap();
+    AnalysisCacheStats {
+        entries: cache.entries.len(),
+        hits: cache.hits,
+        misses: cache.misses,
+    }
+}
+
+/// 清空分析缓存
+pub fn clear_analysis_cache() {
+    *ANALYSIS_CACHE.lock().unwrap() = AnalysisCache::default();
+}
+
+// --- 分析成本估算 ---
+
+/// 批量分析提示词本身的大致 token 数，按批次分摊到每个项目
+const ANALYSIS_PROMPT_TOKENS: f64 = 1200.0;
+/// 每个项目文件列表的大致 token 数
+const FILE_LIST_TOKENS_PER_ITEM: f64 = 80.0;
+/// 每个项目输出结果的大致 token 数
+const OUTPUT_TOKENS_PER_ITEM: f64 = 40.0;
+
+/// 模型单价（美元 / 百万 token）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelPrice {
+    pub model: String,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// 内置的模型价格表，可通过设置中的 price_table 覆盖
+pub fn default_price_table() -> Vec<ModelPrice> {
+    [
+        ("gemini-2.5-pro", 1.25, 10.0),
+        ("gemini-2.5-flash", 0.30, 2.50),
+        ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ]
+    .into_iter()
+    .map(|(model, input, output)| ModelPrice {
+        model: model.to_string(),
+        input_per_million: input,
+        output_per_million: output,
+    })
+    .collect()
+}
+
+/// 分析成本的估算范围
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CostEstimate {
+    pub model: String,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub min_usd: f64,
+    pub max_usd: f64,
+}
+
+/// 估算第二阶段分析的成本
+///
+/// 标题按每 token 1～4 个字符估算（中日韩文字接近 1 个字符一个 token，拉丁文字约 4 个），
+/// 由此得到一个上下限。`overrides` 中的价格优先于内置价格表。
+pub fn estimate_analysis_cost(
+    result_count: usize,
+    avg_title_len: usize,
+    model: &str,
+    batch_size: u32,
+    overrides: &[ModelPrice],
+) -> Result<CostEstimate> {
+    let price = overrides
+        .iter()
+        .cloned()
+        .chain(default_price_table())
+        .find(|price| price.model == model)
+        .ok_or_else(|| anyhow::anyhow!("No pricing configured for model '{}'", model))?;
+
+    let prompt_per_item = ANALYSIS_PROMPT_TOKENS / batch_size.max(1) as f64;
+    let count = result_count as f64;
+    let input_tokens = |chars_per_token: f64| {
+        count * (prompt_per_item + FILE_LIST_TOKENS_PER_ITEM + avg_title_len as f64 / chars_per_token)
+    };
+    let (min_input, max_input) = (input_tokens(4.0), input_tokens(1.0));
+    let output = count * OUTPUT_TOKENS_PER_ITEM;
+    let usd = |input: f64| (input * price.input_per_million + output * price.output_per_million) / 1_000_000.0;
+
+    Ok(CostEstimate {
+        model: price.model.clone(),
+        min_tokens: (min_input + output).round() as u64,
+        max_tokens: (max_input + output).round() as u64,
+        min_usd: usd(min_input),
+        max_usd: usd(max_input),
+    })
+}
+
 // --- 3. LLM客户端定义 ---
 
 #[async_trait]
 pub trait LlmClient: Send + Sync {
-    /// 第一阶段：从HTML页面批量提取基础、原始的磁力链接信息
+    /// 第一阶段：从HTML页面批量提取基础、原始的磁力链接信息，`max_results` 限制提取的条目数
     async fn batch_extract_basic_info_from_html(
         &self,
         html_content: &str,
         extraction_config: &LlmConfig,
+        max_results: Option<usize>,
     ) -> Result<BatchExtractBasicInfoResult>;
 
     /// 第二阶段：根据文件列表批量分析分数和标签（单个项目）
@@ -117,15 +352,43 @@ pub trait LlmClient: Send + Sync {
         items: &[BatchAnalysisItem],
         analysis_config: &LlmConfig,
     ) -> Result<Vec<BatchAnalysisResult>>;
+
+    /// 第二阶段：流式批量分析，每得到一个项目的结果就立即通过 `sender` 发送
+    ///
+    /// 默认实现供不支持流式响应的提供商使用：整批分析完成后再依次发送。
+    async fn batch_analyze_multiple_items_streaming(
+        &self,
+        items: &[BatchAnalysisItem],
+        analysis_config: &LlmConfig,
+        sender: mpsc::Sender<StreamedAnalysis>,
+    ) -> Result<()> {
+        let results = self.batch_analyze_multiple_items(items, analysis_config).await?;
+        for (index, result) in results.into_iter().enumerate() {
+            if sender.send(StreamedAnalysis { index, result }).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct GeminiClient {
     client: Client,
 }
 
+/// LLM 接口使用的 HTTP 客户端，与搜索引擎的客户端分开创建
+///
+/// 显式启用 gzip/brotli/deflate 解压：部分 LLM 网关会返回压缩后的响应体，
+/// reqwest 据此发送 `Accept-Encoding` 并在读取时自动解压。
+fn llm_client_builder() -> reqwest::ClientBuilder {
+    crate::net::client_builder().gzip(true).brotli(true).deflate(true)
+}
+
 impl GeminiClient {
     pub fn new() -> Self {
-        let client = Client::new();
+        let client = llm_client_builder()
+            .build()
+            .expect("Failed to create HTTP client");
         Self { client }
     }
 }
@@ -142,8 +405,10 @@ impl LlmClient for GeminiClient {
         &self,
         html_content: &str,
         extraction_config: &LlmConfig,
+        max_results: Option<usize>,
     ) -> Result<BatchExtractBasicInfoResult> {
-        self.batch_extract_basic_info_impl(html_content, extraction_config).await
+        // 每次 LLM 调用使用独立的请求 ID，嵌套在所属搜索请求之下
+        trace::with_request_id(trace::next_request_id("llm"), self.batch_extract_basic_info_impl(html_content, extraction_config, max_results)).await
     }
 
     async fn batch_analyze_scores_and_tags(
@@ -152,8 +417,11 @@ impl LlmClient for GeminiClient {
         file_list: &[String],
         analysis_config: &LlmConfig,
     ) -> Result<(String, u8, Vec<String>)> {
-        self.batch_analyze_scores_and_tags_impl(original_title, file_list, analysis_config)
-            .await
+        trace::with_request_id(
+            trace::next_request_id("llm"),
+            self.batch_analyze_scores_and_tags_impl(original_title, file_list, analysis_config),
+        )
+        .await
     }
 
     async fn batch_analyze_multiple_items(
@@ -161,7 +429,16 @@ impl LlmClient for GeminiClient {
         items: &[BatchAnalysisItem],
         analysis_config: &LlmConfig,
     ) -> Result<Vec<BatchAnalysisResult>> {
-        self.batch_analyze_multiple_items_impl(items, analysis_config).await
+        trace::with_request_id(trace::next_request_id("llm"), self.batch_analyze_multiple_items_impl(items, analysis_config)).await
+    }
+
+    async fn batch_analyze_multiple_items_streaming(
+        &self,
+        items: &[BatchAnalysisItem],
+        analysis_config: &LlmConfig,
+        sender: mpsc::Sender<StreamedAnalysis>,
+    ) -> Result<()> {
+        trace::with_request_id(trace::next_request_id("llm"), self.stream_analyze_multiple_items_impl(items, analysis_config, sender)).await
     }
 }
 
@@ -182,19 +459,23 @@ struct Part {
     text: String,
 }
 
+// 流式响应的最后一块可能只包含用量信息，因此字段都允许缺省
 #[derive(Deserialize, Debug)]
 struct GeminiResponse {
+    #[serde(default)]
     candidates: Vec<Candidate>,
 }
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct Candidate {
+    #[serde(default)]
     content: ContentResponse,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 struct ContentResponse {
+    #[serde(default)]
     parts: Vec<PartResponse>,
 }
 
@@ -211,65 +492,26 @@ impl GeminiClient {
         &self,
         html_content: &str,
         config: &LlmConfig,
+        max_results: Option<usize>,
     ) -> Result<BatchExtractBasicInfoResult> {
+        let raw_text = self.request_basic_info_raw(html_content, config, max_results).await?;
+        parse_basic_info_response(&raw_text)
+    }
+
+    /// 发送第一阶段提取请求，返回模型的原始文本
+    async fn request_basic_info_raw(
+        &self,
+        html_content: &str,
+        config: &LlmConfig,
+        max_results: Option<usize>,
+    ) -> Result<String> {
         let normalized_base = normalize_api_base(&config.api_base);
         let url = format!(
             "{}/models/{}:generateContent?key={}",
             normalized_base, config.model, config.api_key
         );
 
-        let prompt = format!(
-            r#"
-作为数据提取引擎，你的唯一任务是从以下HTML内容中识别出所有磁力链接条目，并返回一个包含 "results" 数组的JSON对象。
-
-**重要提示**: 如果HTML内容包含乱码字符(�)或看起来不是正常的HTML，请仍然尝试提取任何可识别的磁力链接。
-
-**提取规则:**
-1.  **识别条目**: 找到包含磁力链接 (`magnet:?xt=`) 的HTML片段。磁力链接通常在以下位置：
-    - `<a href="magnet:?xt=urn:btih:...">` 标签中
-    - 可能在各种HTML结构中，如表格、列表、div等
-2.  **提取字段**:
-    *   `title`: 提取与磁力链接相关的最直接的标题文本。**重要：移除所有HTML标签（如<b>、<em>、<strong>等），只返回纯文本内容**。
-    *   `magnet_link`: 提取完整的磁力链接字符串，必须以 `magnet:?xt=` 开头。
-    *   `file_size`: 提取与该条目相关的文件大小文本（例如 "1.5GB", "899MB", "78.78G"）。如果找不到，则返回 `null`。
-    *   `source_url`: 提取与该条目相关的详情页面链接或源页面URL。通常是标题链接的href属性。如果找不到，则返回 `null`。
-3.  **严格JSON输出**: 返回的JSON对象必须只包含一个 `results` 键，其值为一个对象数组。每个对象都包含 `title`, `magnet_link`, `file_size`, `source_url` 字段。
-
-**如果找不到任何磁力链接，请返回空数组但仍要说明原因**。
-
-**重要指令:**
-*   **绝对禁止修改数据**: 你的任务是提取，不是处理。返回你找到的原始信息。
-*   **无需理解内容**: 不要尝试理解标题的含义或美化它。
-*   **保持顺序**: 尽可能按照在HTML中出现的顺序列出结果。
-*   **不要包含任何解释**: 你的输出必须是纯粹的JSON。
-
-**HTML内容:**
-```html
-{}
-```
-
-**示例输出:**
-```json
-{{
-  "results": [
-    {{
-      "title": "Some.Movie.Title.2023.1080p.BluRay.x264-GROUP[rartv]",
-      "magnet_link": "magnet:?xt=urn:btih:abcdef123456...",
-      "file_size": "2.3GB",
-      "source_url": "/details/12345"
-    }},
-    {{
-      "title": "[AD] www.example.com [AD] Another.Show.S01E01.720p.WEB-DL",
-      "magnet_link": "magnet:?xt=urn:btih:fedcba654321...",
-      "file_size": "500MB",
-      "source_url": "https://example.com/torrent/67890"
-    }}
-  ]
-}}
-```
-"#,
-            html_content
-        );
+        let prompt = build_basic_info_prompt(html_content, max_results);
 
         let request_body = GeminiRequest {
             contents: vec![Content {
@@ -282,29 +524,18 @@ impl GeminiClient {
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_default();
-            println!("❌ API请求失败: {status} - {error_body}");
+            trace_println!("❌ API请求失败: {status} - {error_body}");
             return Err(anyhow::anyhow!("API请求失败: {}", error_body));
         }
 
         let gemini_response = response.json::<GeminiResponse>().await?;
-        if let Some(candidate) = gemini_response.candidates.first() {
-            if let Some(part) = candidate.content.parts.first() {
-                let cleaned_text = part.text.trim().replace("```json", "").replace("```", "");
-                let result: BatchExtractBasicInfoResult = serde_json::from_str(&cleaned_text)
-                    .map_err(|e| {
-                        println!("❌ JSON解析失败: {e}");
-                        println!("📄 原始AI响应: {}", part.text);
-                        println!("🧹 清理后文本: {cleaned_text}");
-                        anyhow::anyhow!(
-                            "解析第一阶段JSON失败: {}. Raw text: {}",
-                            e,
-                            cleaned_text
-                        )
-                    })?;
-                return Ok(result);
-            }
-        }
-        Err(anyhow::anyhow!("Gemini响应中未找到有效内容"))
+        gemini_response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|candidate| candidate.content.parts.into_iter().next())
+            .map(|part| part.text)
+            .ok_or_else(|| anyhow::anyhow!("Gemini响应中未找到有效内容"))
     }
 
     /// **重构后的第二阶段实现**: 根据新的、更简单的逻辑分析标题、文件列表和标签（支持重试）。
@@ -314,7 +545,7 @@ impl GeminiClient {
         file_list: &[String],
         config: &LlmConfig,
     ) -> Result<(String, u8, Vec<String>)> {
-        println!("🔧 [DEBUG] Starting single analysis for '{}' using batch method, batch_size={}",
+        trace_println!("🔧 [DEBUG] Starting single analysis for '{}' using batch method, batch_size={}",
                  original_title, config.batch_size);
 
         // 转换为批量格式（单个项目）
@@ -328,7 +559,7 @@ impl GeminiClient {
 
         // 提取第一个结果
         if let Some(result) = results.first() {
-            println!("✅ [DEBUG] Single analysis via batch method succeeded");
+            trace_println!("✅ [DEBUG] Single analysis via batch method succeeded");
             Ok((result.cleaned_title.clone(), result.purity_score, result.tags.clone()))
         } else {
             Err(anyhow::anyhow!("批量分析未返回结果"))
@@ -338,42 +569,179 @@ impl GeminiClient {
     // try_single_analyze_scores_and_tags 方法已被移除
     // 现在统一使用 try_batch_analyze_multiple_items 处理单个和批量分析
 
-    /// 真正的批量分析实现，支持重试机制
+    /// 真正的批量分析实现：先查缓存，只把未命中的项目发送给LLM
     async fn batch_analyze_multiple_items_impl(
         &self,
         items: &[BatchAnalysisItem],
         config: &LlmConfig,
+    ) -> Result<Vec<BatchAnalysisResult>> {
+        let keys: Vec<u64> = items
+            .iter()
+            .map(|item| analysis_cache_key(item, &config.model))
+            .collect();
+
+        let mut results: Vec<Option<BatchAnalysisResult>> = {
+            let mut cache = ANALYSIS_CACHE.lock().unwrap();
+            keys.iter().map(|key| cache.get(*key)).collect()
+        };
+
+        let uncached_items: Vec<BatchAnalysisItem> = items
+            .iter()
+            .zip(&results)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(item, _)| item.clone())
+            .collect();
+
+        if uncached_items.len() < items.len() {
+            trace_println!("💾 [DEBUG] Analysis cache hit for {}/{} items", items.len() - uncached_items.len(), items.len());
+        }
+
+        if !uncached_items.is_empty() {
+            let fresh_results = self.analyze_with_retries(&uncached_items, config).await?;
+
+            let mut cache = ANALYSIS_CACHE.lock().unwrap();
+            let mut fresh_iter = fresh_results.into_iter();
+            for (slot, key) in results.iter_mut().zip(&keys) {
+                if slot.is_none() {
+                    let fresh = fresh_iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("批量分析未返回结果"))?;
+                    cache.insert(*key, fresh.clone());
+                    *slot = Some(fresh);
+                }
+            }
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// 批量分析（不使用缓存），支持重试机制
+    async fn analyze_with_retries(
+        &self,
+        items: &[BatchAnalysisItem],
+        config: &LlmConfig,
     ) -> Result<Vec<BatchAnalysisResult>> {
         let mut retry_count = 0;
         const MAX_RETRIES: u32 = 3;
         const RETRY_DELAY_SECONDS: u64 = 3;
 
-        println!("🔧 [DEBUG] Starting batch analysis with {} items, batch_size={}",
+        trace_println!("🔧 [DEBUG] Starting batch analysis with {} items, batch_size={}",
                  items.len(), config.batch_size);
 
         loop {
-            println!("🔧 [DEBUG] Attempt {} of {}", retry_count + 1, MAX_RETRIES + 1);
+            trace_println!("🔧 [DEBUG] Attempt {} of {}", retry_count + 1, MAX_RETRIES + 1);
             match self.try_batch_analyze_multiple_items(items, config).await {
                 Ok(results) => {
-                    println!("✅ [DEBUG] Batch analysis succeeded on attempt {}", retry_count + 1);
+                    trace_println!("✅ [DEBUG] Batch analysis succeeded on attempt {}", retry_count + 1);
                     return Ok(results);
                 }
                 Err(e) => {
                     retry_count += 1;
-                    println!("❌ [DEBUG] Batch analysis failed on attempt {retry_count}: {e}");
+                    trace_println!("❌ [DEBUG] Batch analysis failed on attempt {retry_count}: {e}");
 
                     if retry_count >= MAX_RETRIES {
-                        println!("💥 [DEBUG] Max retries reached, giving up");
+                        trace_println!("💥 [DEBUG] Max retries reached, giving up");
                         return Err(anyhow::anyhow!("批量分析失败，已重试{}次: {}", MAX_RETRIES, e));
                     }
 
-                    println!("⚠️ 批量分析失败，{RETRY_DELAY_SECONDS}秒后重试 ({retry_count}/{MAX_RETRIES}): {e}");
+                    trace_println!("⚠️ 批量分析失败，{RETRY_DELAY_SECONDS}秒后重试 ({retry_count}/{MAX_RETRIES}): {e}");
 
                     tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECONDS)).await;
-                    println!("🔄 [DEBUG] Retrying now...");
+                    trace_println!("🔄 [DEBUG] Retrying now...");
+                }
+            }
+        }
+    }
+
+    /// 流式批量分析：缓存命中的项目立即发送，其余项目随 SSE 响应逐个解析发送
+    ///
+    /// 流式请求不重试，出错时已经发送的结果仍然有效。
+    async fn stream_analyze_multiple_items_impl(
+        &self,
+        items: &[BatchAnalysisItem],
+        config: &LlmConfig,
+        sender: mpsc::Sender<StreamedAnalysis>,
+    ) -> Result<()> {
+        let keys: Vec<u64> = items
+            .iter()
+            .map(|item| analysis_cache_key(item, &config.model))
+            .collect();
+
+        let mut cached = Vec::new();
+        let mut pending = Vec::new();
+        {
+            let mut cache = ANALYSIS_CACHE.lock().unwrap();
+            for (index, key) in keys.iter().enumerate() {
+                match cache.get(*key) {
+                    Some(result) => cached.push(StreamedAnalysis { index, result }),
+                    None => pending.push(index),
+                }
+            }
+        }
+        for streamed in cached {
+            let _ = sender.send(streamed).await;
+        }
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let uncached_items: Vec<BatchAnalysisItem> = pending.iter().map(|&index| items[index].clone()).collect();
+        let url = format!(
+            "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+            normalize_api_base(&config.api_base),
+            config.model,
+            config.api_key
+        );
+        let request_body = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: build_batch_analysis_prompt(&uncached_items)? }],
+            }],
+        };
+
+        trace_println!("🔧 [DEBUG] Starting streaming analysis with {} items", uncached_items.len());
+        let mut response = self.client.post(&url).json(&request_body).send().await?;
+        if !response.status().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("API请求失败: {}", error_body));
+        }
+
+        let mut parser = StreamingResultsParser::default();
+        let mut line_buffer: Vec<u8> = Vec::new();
+        let mut delivered = 0;
+        while let Some(chunk) = response.chunk().await? {
+            line_buffer.extend_from_slice(&chunk);
+            // 按行切分 SSE 事件，不完整的行留到下一块数据
+            while let Some(newline) = line_buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = line_buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let event: GeminiResponse = serde_json::from_str(data.trim())
+                    .map_err(|e| anyhow::anyhow!("解析流式响应失败: {}. Raw text: {}", e, data))?;
+
+                for part in event.candidates.iter().flat_map(|candidate| &candidate.content.parts) {
+                    for result in parser.push::<BatchAnalysisResult>(&part.text)? {
+                        let Some(&index) = pending.get(delivered) else {
+                            return Err(anyhow::anyhow!("批量分析结果数量不匹配: 期望{}, 实际更多", pending.len()));
+                        };
+                        ANALYSIS_CACHE.lock().unwrap().insert(keys[index], result.clone());
+                        let _ = sender.send(StreamedAnalysis { index, result }).await;
+                        delivered += 1;
+                    }
                 }
             }
         }
+
+        if delivered != pending.len() {
+            return Err(anyhow::anyhow!(
+                "批量分析结果数量不匹配: 期望{}, 实际{}",
+                pending.len(),
+                delivered
+            ));
+        }
+        trace_println!("✅ [DEBUG] Streaming analysis delivered {delivered} items");
+        Ok(())
     }
 
     /// 尝试批量分析（不包含重试逻辑）
@@ -392,11 +760,216 @@ impl GeminiClient {
             normalized_base, config.model, config.api_key
         );
 
-        // 构建批量分析的 prompt
-        let items_json = serde_json::to_string_pretty(items)?;
+        let prompt = build_batch_analysis_prompt(items)?;
 
-        let prompt = format!(
-            r#"
+        // 移除详细的Prompt日志以简化输出
+        // trace_println!("[BATCH AI PROMPT] 批量分析prompt:\n---\n{}\n---", prompt);
+
+        let request_body = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: prompt }],
+            }],
+        };
+
+        let response = self.client.post(&url).json(&request_body).send().await?;
+        if !response.status().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("API请求失败: {}", error_body));
+        }
+
+        let gemini_response = response.json::<GeminiResponse>().await?;
+        if let Some(candidate) = gemini_response.candidates.first() {
+            if let Some(part) = candidate.content.parts.first() {
+                let cleaned_text = part.text.trim().replace("```json", "").replace("```", "");
+
+                // 移除详细的响应日志以简化输出
+                // trace_println!("[BATCH AI RESPONSE] 批量分析响应:\n---\n{}\n---", cleaned_text);
+
+                #[derive(Deserialize)]
+                struct BatchAnalysisResponse {
+                    results: Vec<BatchAnalysisResult>,
+                }
+
+                let batch_response: BatchAnalysisResponse = serde_json::from_str(&cleaned_text)
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "解析批量分析响应JSON失败: {}. Raw text: {}",
+                            e,
+                            cleaned_text
+                        )
+                    })?;
+
+                // 验证结果数量是否匹配
+                if batch_response.results.len() != items.len() {
+                    return Err(anyhow::anyhow!(
+                        "批量分析结果数量不匹配: 期望{}, 实际{}",
+                        items.len(),
+                        batch_response.results.len()
+                    ));
+                }
+
+                return Ok(batch_response.results);
+            }
+        }
+        Err(anyhow::anyhow!("Gemini响应中未找到有效内容"))
+    }
+}
+
+/// 从流式返回的文本中增量解析 `results` 数组里已经完整的对象，
+/// 也用于从被截断的响应中找回截断点之前的完整对象
+#[derive(Default)]
+struct StreamingResultsParser {
+    buffer: String,
+    /// 下一个待扫描的字节位置，找到 `results` 数组之前为 None
+    cursor: Option<usize>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    object_start: usize,
+}
+
+impl StreamingResultsParser {
+    /// 追加一段文本，返回这段文本补全的所有结果
+    fn push<T: serde::de::DeserializeOwned>(&mut self, text: &str) -> Result<Vec<T>> {
+        self.buffer.push_str(text);
+        let mut completed = Vec::new();
+
+        let mut cursor = match self.cursor {
+            Some(cursor) => cursor,
+            None => {
+                let Some(key) = self.buffer.find("\"results\"") else {
+                    return Ok(completed);
+                };
+                let Some(bracket) = self.buffer[key..].find('[') else {
+                    return Ok(completed);
+                };
+                key + bracket + 1
+            }
+        };
+
+        let bytes = self.buffer.as_bytes();
+        while cursor < bytes.len() {
+            let byte = bytes[cursor];
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                }
+            } else {
+                match byte {
+                    b'"' => self.in_string = true,
+                    b'{' => {
+                        if self.depth == 0 {
+                            self.object_start = cursor;
+                        }
+                        self.depth += 1;
+                    }
+                    b'}' if self.depth > 0 => {
+                        self.depth -= 1;
+                        if self.depth == 0 {
+                            let object = &self.buffer[self.object_start..=cursor];
+                            completed.push(serde_json::from_str(object).map_err(|e| {
+                                anyhow::anyhow!("解析流式分析结果失败: {}. Raw text: {}", e, object)
+                            })?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            cursor += 1;
+        }
+
+        self.cursor = Some(cursor);
+        Ok(completed)
+    }
+}
+
+/// 构建第一阶段提取的 prompt，实际请求与 token 估算共用
+pub fn build_basic_info_prompt(html_content: &str, max_results: Option<usize>) -> String {
+    // 只需要前 N 条时在提示词中说明，减少输出 token
+    let limit_rule = max_results
+        .map(|max| format!("\n4.  **数量限制**: 只返回在HTML中最先出现的 {max} 个条目，忽略其余条目。"))
+        .unwrap_or_default();
+
+    format!(
+        r#"
+作为数据提取引擎，你的唯一任务是从以下HTML内容中识别出所有磁力链接条目，并返回一个包含 "results" 数组的JSON对象。
+
+**重要提示**: 如果HTML内容包含乱码字符(�)或看起来不是正常的HTML，请仍然尝试提取任何可识别的磁力链接。
+
+**提取规则:**
+1.  **识别条目**: 找到包含磁力链接 (`magnet:?xt=`) 的HTML片段。磁力链接通常在以下位置：
+    - `<a href="magnet:?xt=urn:btih:...">` 标签中
+    - 可能在各种HTML结构中，如表格、列表、div等
+2.  **提取字段**:
+    *   `title`: 提取与磁力链接相关的最直接的标题文本。**重要：移除所有HTML标签（如<b>、<em>、<strong>等），只返回纯文本内容**。
+    *   `magnet_link`: 提取完整的磁力链接字符串，必须以 `magnet:?xt=` 开头。
+    *   `file_size`: 提取与该条目相关的文件大小文本（例如 "1.5GB", "899MB", "78.78G"）。如果找不到，则返回 `null`。
+    *   `source_url`: 提取与该条目相关的详情页面链接或源页面URL。通常是标题链接的href属性。如果找不到，则返回 `null`。
+    *   `thumbnail_url`: 提取该条目中海报或缩略图 `<img>` 的 `src` 属性（懒加载图片使用 `data-src`）。如果没有图片，则返回 `null`。
+    *   `confidence`: 0.0 到 1.0 之间的数字，表示你有多确定该条目是真实的种子资源而不是广告、导航或误识别的链接。
+3.  **严格JSON输出**: 返回的JSON对象必须只包含一个 `results` 键，其值为一个对象数组。每个对象都包含 `title`, `magnet_link`, `file_size`, `source_url`, `thumbnail_url`, `confidence` 字段。{limit_rule}
+
+**如果找不到任何磁力链接，请返回空数组但仍要说明原因**。
+
+**重要指令:**
+*   **绝对禁止修改数据**: 你的任务是提取，不是处理。返回你找到的原始信息。
+*   **无需理解内容**: 不要尝试理解标题的含义或美化它。
+*   **保持顺序**: 尽可能按照在HTML中出现的顺序列出结果。
+*   **不要包含任何解释**: 你的输出必须是纯粹的JSON。
+
+**HTML内容:**
+```html
+{}
+```
+
+**示例输出:**
+```json
+{{
+  "results": [
+    {{
+      "title": "Some.Movie.Title.2023.1080p.BluRay.x264-GROUP[rartv]",
+      "magnet_link": "magnet:?xt=urn:btih:abcdef123456...",
+      "file_size": "2.3GB",
+      "source_url": "/details/12345",
+      "thumbnail_url": "/posters/12345.jpg",
+      "confidence": 0.95
+    }},
+    {{
+      "title": "[AD] www.example.com [AD] Another.Show.S01E01.720p.WEB-DL",
+      "magnet_link": "magnet:?xt=urn:btih:fedcba654321...",
+      "file_size": "500MB",
+      "source_url": "https://example.com/torrent/67890",
+      "thumbnail_url": null,
+      "confidence": 0.6
+    }}
+  ]
+}}
+```
+"#,
+        html_content
+    )
+}
+
+/// 粗略估算一段 prompt 的 token 数
+///
+/// ASCII 字符（HTML 标签、拉丁文字）按约 4 个字符一个 token，其余字符（中日韩文字等）按 1 个字符一个 token。
+pub fn estimate_prompt_tokens(prompt: &str) -> u64 {
+    let (ascii, other) = prompt
+        .chars()
+        .fold((0u64, 0u64), |(ascii, other), c| if c.is_ascii() { (ascii + 1, other) } else { (ascii, other + 1) });
+    ascii.div_ceil(4) + other
+}
+
+/// 构建批量分析的 prompt，流式与非流式请求共用
+fn build_batch_analysis_prompt(items: &[BatchAnalysisItem]) -> Result<String> {
+    let items_json = serde_json::to_string_pretty(items)?;
+
+    Ok(format!(
+        r#"
 作为媒体资源批量分析引擎，请对以下多个项目进行分析。对每个项目，你需要根据以下三项独立任务进行分析，并严格按照JSON格式返回结果。
 
 **任务1：精简标题**
@@ -456,60 +1029,30 @@ impl GeminiClient {
 }}
 ```
 "#,
-            items_json
-        );
-
-        // 移除详细的Prompt日志以简化输出
-        // println!("[BATCH AI PROMPT] 批量分析prompt:\n---\n{}\n---", prompt);
-
-        let request_body = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part { text: prompt }],
-            }],
-        };
-
-        let response = self.client.post(&url).json(&request_body).send().await?;
-        if !response.status().is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!("API请求失败: {}", error_body));
-        }
-
-        let gemini_response = response.json::<GeminiResponse>().await?;
-        if let Some(candidate) = gemini_response.candidates.first() {
-            if let Some(part) = candidate.content.parts.first() {
-                let cleaned_text = part.text.trim().replace("```json", "").replace("```", "");
-
-                // 移除详细的响应日志以简化输出
-                // println!("[BATCH AI RESPONSE] 批量分析响应:\n---\n{}\n---", cleaned_text);
-
-                #[derive(Deserialize)]
-                struct BatchAnalysisResponse {
-                    results: Vec<BatchAnalysisResult>,
-                }
-
-                let batch_response: BatchAnalysisResponse = serde_json::from_str(&cleaned_text)
-                    .map_err(|e| {
-                        anyhow::anyhow!(
-                            "解析批量分析响应JSON失败: {}. Raw text: {}",
-                            e,
-                            cleaned_text
-                        )
-                    })?;
-
-                // 验证结果数量是否匹配
-                if batch_response.results.len() != items.len() {
-                    return Err(anyhow::anyhow!(
-                        "批量分析结果数量不匹配: 期望{}, 实际{}",
-                        items.len(),
-                        batch_response.results.len()
-                    ));
-                }
+        items_json
+    ))
+}
 
-                return Ok(batch_response.results);
+/// 解析第一阶段模型返回的文本，兼容包裹在 Markdown 代码块中的 JSON
+fn parse_basic_info_response(raw_text: &str) -> Result<BatchExtractBasicInfoResult> {
+    let cleaned_text = raw_text.trim().replace("```json", "").replace("```", "");
+    serde_json::from_str(&cleaned_text).or_else(|e| {
+        // 响应可能因 token 上限被截断，尽量保留截断点之前已经完整的结果
+        if let Ok(results) = StreamingResultsParser::default().push::<ExtractedBasicInfo>(&cleaned_text) {
+            if !results.is_empty() {
+                trace_println!("⚠️ JSON不完整（{e}），从截断的响应中找回 {} 个结果", results.len());
+                return Ok(BatchExtractBasicInfoResult { results });
             }
         }
-        Err(anyhow::anyhow!("Gemini响应中未找到有效内容"))
-    }
+        trace_println!("❌ JSON解析失败: {e}");
+        trace_println!("📄 原始AI响应: {raw_text}");
+        trace_println!("🧹 清理后文本: {cleaned_text}");
+        Err(ExtractionParseError {
+            raw_response: raw_text.to_string(),
+            message: e.to_string(),
+        }
+        .into())
+    })
 }
 
 // --- 6. 公共API函数 ---
@@ -525,7 +1068,7 @@ pub async fn test_connection(config: &LlmConfig) -> Result<String> {
     );
 
     // 简化调试信息
-    println!("🔧 Testing connection to: {url}");
+    trace_println!("🔧 Testing connection to: {url}");
     let request_body = GeminiRequest {
         contents: vec![Content {
             parts: vec![Part {
@@ -533,16 +1076,16 @@ pub async fn test_connection(config: &LlmConfig) -> Result<String> {
             }],
         }],
     };
-    let client = Client::new();
+    let client = llm_client_builder().build()?;
     let response = client.post(&url).json(&request_body).send().await?;
 
     let status = response.status();
     if status.is_success() {
-        println!("✅ Connection successful (Status: {status}).");
+        trace_println!("✅ Connection successful (Status: {status}).");
         Ok("连接成功".to_string())
     } else {
         let error_body = response.text().await.unwrap_or_default();
-        println!("❌ Connection failed (Status: {status}): {error_body}");
+        trace_println!("❌ Connection failed (Status: {status}): {error_body}");
 
         // 为常见错误提供更友好的提示
         let error_message = match status.as_u16() {
@@ -556,4 +1099,251 @@ pub async fn test_connection(config: &LlmConfig) -> Result<String> {
 
         Err(anyhow::anyhow!("{}: {}", error_message, error_body))
     }
-}
\ No newline at end of file
+}
+
+/// 用给定的HTML运行第一阶段提取，同时返回解析结果和模型原始响应
+///
+/// 请求失败时返回错误；JSON 解析失败不视为错误，而是记录在 `parse_error` 中，便于对照原始响应排查。
+pub async fn test_extraction_with_sample(config: &LlmConfig, html: &str) -> Result<ExtractionSampleResult> {
+    let raw_response = GeminiClient::new().request_basic_info_raw(html, config, None).await?;
+
+    Ok(match parse_basic_info_response(&raw_response) {
+        Ok(parsed) => ExtractionSampleResult {
+            results: parsed.results,
+            raw_response,
+            parse_error: None,
+        },
+        Err(e) => ExtractionSampleResult {
+            results: Vec::new(),
+            raw_response,
+            parse_error: Some(e.to_string()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::llm_config;
+    use httpmock::prelude::*;
+
+    fn gemini_body(text: &str) -> String {
+        serde_json::json!({
+            "candidates": [{ "content": { "parts": [{ "text": text }] } }]
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_analysis_hits_cache() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/v1beta/models/cache-test-model:generateContent");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(gemini_body(r#"{"results":[{"cleaned_title":"Cached Movie","purity_score":88,"tags":["1080p"]}]}"#));
+        });
+
+        let config = LlmConfig { api_base: server.base_url(), model: "cache-test-model".to_string(), ..llm_config() };
+        let items = vec![BatchAnalysisItem {
+            title: "Cached.Movie.2023.1080p-unique-cache-test".to_string(),
+            file_list: vec!["Cached.Movie.mkv".to_string()],
+        }];
+
+        let client = GeminiClient::new();
+        let first = client.batch_analyze_multiple_items(&items, &config).await.unwrap();
+        let hits_before = analysis_cache_stats().hits;
+        let second = client.batch_analyze_multiple_items(&items, &config).await.unwrap();
+
+        mock.assert_hits(1);
+        assert_eq!(first[0].cleaned_title, "Cached Movie");
+        assert_eq!(second[0].cleaned_title, first[0].cleaned_title);
+        assert_eq!(second[0].purity_score, 88);
+        assert!(analysis_cache_stats().hits > hits_before);
+    }
+
+    #[tokio::test]
+    async fn test_gzipped_llm_response_is_decoded() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(gemini_body(r#"{"results":[{"cleaned_title":"Gzipped Movie","purity_score":77,"tags":["720p"]}]}"#).as_bytes())
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1beta/models/gzip-test-model:generateContent")
+                .header_exists("accept-encoding");
+            then.status(200)
+                .header("content-type", "application/json")
+                .header("content-encoding", "gzip")
+                .body(compressed);
+        });
+
+        let config = LlmConfig { api_base: server.base_url(), model: "gzip-test-model".to_string(), ..llm_config() };
+        let items = vec![BatchAnalysisItem {
+            title: "Gzipped.Movie.2023.720p-unique-gzip-test".to_string(),
+            file_list: vec!["Gzipped.Movie.mkv".to_string()],
+        }];
+
+        let results = GeminiClient::new().batch_analyze_multiple_items(&items, &config).await.unwrap();
+        mock.assert();
+        assert_eq!(results[0].cleaned_title, "Gzipped Movie");
+        assert_eq!(results[0].purity_score, 77);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_analysis_delivers_items_incrementally() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        // 手写的 SSE 服务端：第一个事件包含第一个完整结果和第二个结果的开头，收到信号后才发送剩余部分
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+
+            let event = |text: &str| format!("data: {}\r\n\r\n", gemini_body(text));
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let first = r#"```json
+{"results":[{"cleaned_title":"First","purity_score":90,"tags":[]},{"cleaned_ti"#;
+            socket.write_all(event(first).as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+
+            release_rx.await.unwrap();
+            let rest = r#"tle":"Second {braced}","purity_score":80,"tags":["1080p"]}]}
+```"#;
+            socket.write_all(event(rest).as_bytes()).await.unwrap();
+        });
+
+        let config = LlmConfig {
+            api_base: format!("http://{address}"),
+            model: "stream-test-model".to_string(),
+            ..llm_config()
+        };
+        let items: Vec<BatchAnalysisItem> = ["First.unique-stream-test", "Second.unique-stream-test"]
+            .iter()
+            .map(|title| BatchAnalysisItem {
+                title: title.to_string(),
+                file_list: vec![format!("{title}.mkv")],
+            })
+            .collect();
+
+        let (sender, mut receiver) = mpsc::channel(8);
+        let analysis = tokio::spawn(async move {
+            GeminiClient::new().batch_analyze_multiple_items_streaming(&items, &config, sender).await
+        });
+
+        // 服务端还没有发送第二个结果时，第一个结果已经送达
+        let first = receiver.recv().await.unwrap();
+        assert_eq!(first.index, 0);
+        assert_eq!(first.result.cleaned_title, "First");
+
+        release_tx.send(()).unwrap();
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(second.index, 1);
+        assert_eq!(second.result.cleaned_title, "Second {braced}");
+        assert_eq!(second.result.tags, vec!["1080p".to_string()]);
+
+        analysis.await.unwrap().unwrap();
+        assert!(receiver.recv().await.is_none());
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_cost_estimate_scales_linearly_with_result_count() {
+        let ten = estimate_analysis_cost(10, 60, "gemini-2.5-flash", 5, &[]).unwrap();
+        let thirty = estimate_analysis_cost(30, 60, "gemini-2.5-flash", 5, &[]).unwrap();
+
+        assert!(ten.min_usd > 0.0 && ten.min_usd < ten.max_usd);
+        assert!((thirty.min_usd - ten.min_usd * 3.0).abs() < 1e-12);
+        assert!((thirty.max_usd - ten.max_usd * 3.0).abs() < 1e-12);
+        assert_eq!(estimate_analysis_cost(0, 60, "gemini-2.5-flash", 5, &[]).unwrap().max_usd, 0.0);
+
+        // 覆盖价格优先于内置价格，未知模型报错
+        let free = ModelPrice { model: "gemini-2.5-flash".to_string(), input_per_million: 0.0, output_per_million: 0.0 };
+        assert_eq!(estimate_analysis_cost(10, 60, "gemini-2.5-flash", 5, &[free]).unwrap().max_usd, 0.0);
+        assert!(estimate_analysis_cost(10, 60, "unknown-model", 5, &[]).is_err());
+    }
+
+    #[test]
+    fn test_truncated_extraction_response_keeps_complete_entries() {
+        let raw = r#"```json
+{
+  "results": [
+    {"title": "Movie {A} 1080p", "magnet_link": "magnet:?xt=urn:btih:aaaa", "file_size": "1.2 GB", "source_url": null},
+    {"title": "Movie \"B\" 720p", "magnet_link": "magnet:?xt=urn:btih:bbbb", "file_size": null, "source_url": "https://example.com/b"},
+    {"title": "Movie C", "magnet_link": "magnet:?xt=urn:bt"#;
+
+        let parsed = parse_basic_info_response(raw).unwrap();
+
+        let titles: Vec<_> = parsed.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["Movie {A} 1080p", "Movie \"B\" 720p"]);
+        assert_eq!(parsed.results[1].source_url.as_deref(), Some("https://example.com/b"));
+
+        // 截断在第一个对象之内时仍报告解析错误，保留原始响应
+        let error = parse_basic_info_response(r#"{"results": [{"title": "Mov"#).unwrap_err();
+        assert!(error.downcast_ref::<ExtractionParseError>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_extraction_with_sample_returns_results_and_raw_response() {
+        let server = MockServer::start();
+        let raw = r#"```json
+{"results":[{"title":"Sample Movie 1080p","magnet_link":"magnet:?xt=urn:btih:abc","file_size":"1.2GB","source_url":"/detail/1"}]}
+```"#;
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/v1beta/models/sample-model:generateContent")
+                .body_contains("Sample Movie 1080p");
+            then.status(200).header("content-type", "application/json").body(gemini_body(raw));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/v1beta/models/broken-model:generateContent");
+            then.status(200).header("content-type", "application/json").body(gemini_body("I could not find any torrents."));
+        });
+
+        let config = |model: &str| LlmConfig {
+            api_key: "test-key".to_string(),
+            api_base: format!("{}/v1beta", server.base_url()),
+            model: model.to_string(),
+            ..llm_config()
+        };
+        let html = r#"<a href="/detail/1">Sample Movie 1080p</a> <a href="magnet:?xt=urn:btih:abc">magnet</a>"#;
+
+        let sample = test_extraction_with_sample(&config("sample-model"), html).await.unwrap();
+        assert_eq!(sample.results.len(), 1);
+        assert_eq!(sample.results[0].title, "Sample Movie 1080p");
+        assert_eq!(sample.raw_response, raw);
+        assert!(sample.parse_error.is_none());
+
+        // 模型没有返回 JSON 时保留原始响应并给出解析错误
+        let sample = test_extraction_with_sample(&config("broken-model"), html).await.unwrap();
+        assert!(sample.results.is_empty());
+        assert_eq!(sample.raw_response, "I could not find any torrents.");
+        assert!(sample.parse_error.unwrap().contains("解析第一阶段JSON失败"));
+    }
+}
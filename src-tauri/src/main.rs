@@ -1,19 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-// 引入我们的新模块
-mod llm_service;
-use crate::llm_service::LlmClient;
-// 引入需要的模块
-mod searcher;
-mod app_state;
-mod i18n;
-
-use tauri::Manager;
-use regex::Regex;
+// 核心模块来自库目标，main.rs 只保留 Tauri 命令层
+use ai_magnet_assistant_lib::{app_state, filter, i18n, llm_service, net, searcher};
+use llm_service::LlmClient;
+
+use tauri::{Emitter, Manager};
 use searcher::SearchCore;
+use futures::stream::{self, StreamExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // ============ 辅助函数 ============
 
+/// 将搜索设置中的网络选项应用到之后创建的 HTTP 客户端
+fn apply_network_settings(settings: &app_state::SearchSettings) {
+    net::set_network_options(net::NetworkOptions {
+        prefer_ipv6: settings.prefer_ipv6,
+        doh_resolver: settings.doh_resolver.clone(),
+        max_response_bytes: settings.max_response_bytes,
+        default_headers: settings.default_headers.clone(),
+    });
+}
+
 /// 从 AppState 构建 LLM 配置
 fn build_llm_configs(app_state: &app_state::AppState) -> (Option<llm_service::LlmConfig>, Option<llm_service::LlmConfig>) {
     let llm_config = app_state::get_llm_config(app_state);
@@ -25,6 +32,9 @@ fn build_llm_configs(app_state: &app_state::AppState) -> (Option<llm_service::Ll
             api_base: llm_config.extraction_config.api_base.clone(),
             model: llm_config.extraction_config.model.clone(),
             batch_size: llm_config.extraction_config.batch_size,
+            single_item_timeout_secs: llm_config.extraction_config.single_item_timeout_secs,
+            max_concurrency: llm_config.extraction_config.max_concurrency,
+            max_html_chars: llm_config.extraction_config.max_html_chars,
         })
     } else {
         None
@@ -37,6 +47,9 @@ fn build_llm_configs(app_state: &app_state::AppState) -> (Option<llm_service::Ll
             api_base: llm_config.analysis_config.api_base.clone(),
             model: llm_config.analysis_config.model.clone(),
             batch_size: llm_config.analysis_config.batch_size,
+            single_item_timeout_secs: llm_config.analysis_config.single_item_timeout_secs,
+            max_concurrency: llm_config.analysis_config.max_concurrency,
+            max_html_chars: None,
         })
     } else {
         None
@@ -61,71 +74,239 @@ fn get_priority_keywords(app_state: &app_state::AppState) -> Vec<String> {
         .collect()
 }
 
-/// 创建 SearchCore 实例
-fn create_search_core(
-    state: &app_state::AppState,
-    include_clmclm: bool,
-    include_others: bool,
-) -> Result<SearchCore, String> {
+/// 搜索命令覆盖的引擎范围
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EngineScope {
+    /// 所有启用的引擎
+    All,
+    /// 只搜索 clmclm.com
+    ClmclmOnly,
+    /// 只搜索 clmclm.com 以外的引擎
+    OthersOnly,
+}
+
+impl EngineScope {
+    fn includes_clmclm(self) -> bool {
+        matches!(self, EngineScope::All | EngineScope::ClmclmOnly)
+    }
+
+    fn includes_others(self) -> bool {
+        matches!(self, EngineScope::All | EngineScope::OthersOnly)
+    }
+}
+
+/// 按引擎范围创建 SearchCore 实例，所有搜索命令共用
+///
+/// 没有启用任何引擎时返回 `SearchNoEngines` 配置错误，前端据此提示用户启用引擎；
+/// 有启用的引擎但都不在本次范围内时返回 `Ok(None)`。
+fn build_search_core(state: &app_state::AppState, scope: EngineScope) -> Result<Option<SearchCore>, i18n::AppError> {
     let (extraction_config, analysis_config) = build_llm_configs(state);
     let priority_keyword_strings = get_priority_keywords(state);
     let enabled_engines = get_active_engines(state);
 
-    let clmclm_is_enabled_in_settings = enabled_engines.iter().any(|e| e.name == "clmclm.com");
+    if enabled_engines.is_empty() {
+        return Err(i18n::AppError::Config(i18n::ErrorCode::SearchNoEngines));
+    }
+
+    let clmclm_engine = enabled_engines.iter().find(|e| e.name == "clmclm.com");
 
-    let custom_engine_tuples: Vec<(String, String)> = if include_others {
+    let custom_engines: Vec<searcher::CustomEngineConfig> = if scope.includes_others() {
         enabled_engines
             .iter()
             .filter(|e| e.name != "clmclm.com")
-            .map(|e| (e.name.clone(), e.url_template.clone()))
+            .map(|e| {
+                searcher::CustomEngineConfig::new(e.name.clone(), e.url_template.clone())
+                    .with_use_ai(e.use_ai)
+                    .with_selectors(e.generic_selectors.clone())
+                    .with_headers(e.headers.clone())
+                    .with_json_api(e.json_api.clone())
+                    .with_torznab(e.torznab.clone())
+                    .with_parse_order(e.parse_order.clone())
+                    .with_auth(e.auth.clone())
+            })
             .collect()
     } else {
         Vec::new()
     };
 
-    let final_clmclm_status = include_clmclm && clmclm_is_enabled_in_settings;
+    let final_clmclm_status = scope.includes_clmclm() && clmclm_engine.is_some();
 
-    if custom_engine_tuples.is_empty() && !final_clmclm_status {
-        return Err(i18n::translate_error(&i18n::ErrorCode::SearchNoEngines));
+    if custom_engines.is_empty() && !final_clmclm_status {
+        return Ok(None);
     }
 
     println!(
         "🔧 Creating search core: Custom Engines: {}, CLMCLM: {}",
-        custom_engine_tuples.len(),
+        custom_engines.len(),
         final_clmclm_status
     );
 
-    Ok(searcher::create_ai_enhanced_search_core(
-        extraction_config,
-        analysis_config,
-        priority_keyword_strings,
-        custom_engine_tuples,
-        final_clmclm_status,
+    let search_settings = app_state::get_search_settings(state);
+
+    // clmclm.com 的分类与排序代码来自引擎配置，未配置时使用默认值
+    let clmclm_config = clmclm_engine.filter(|_| final_clmclm_status).map(|engine| {
+        let defaults = searcher::ClmclmConfig::default();
+        searcher::ClmclmConfig {
+            category_code: engine.category_code.clone().unwrap_or(defaults.category_code),
+            sort_code: engine.sort_code.clone().unwrap_or(defaults.sort_code),
+            selectors: engine.selectors.clone().unwrap_or(defaults.selectors),
+            headers: engine.headers.clone(),
+        }
+    });
+
+    Ok(Some(
+        searcher::create_ai_enhanced_search_core(
+            extraction_config,
+            analysis_config,
+            priority_keyword_strings,
+            custom_engines,
+            clmclm_config,
+            search_settings.empty_title_policy,
+        )
+        .with_deep_enrich(search_settings.deep_enrich)
+        .with_enrich_limits(
+            search_settings.enrich_concurrency,
+            search_settings.enrich_timeout_secs.map(std::time::Duration::from_secs),
+        )
+        .with_require_source_url(search_settings.require_source_url)
+        .with_min_confidence(search_settings.min_confidence)
+        .with_min_seeders(search_settings.min_seeders, search_settings.keep_unknown_seeders)
+        .with_deadline(search_settings.search_deadline_secs.map(std::time::Duration::from_secs))
+        .with_request_jitter(search_settings.request_jitter_ms)
+        .with_max_results(search_settings.max_results_per_page)
+        .with_quality_weights(search_settings.quality_weights)
+        .with_fuzzy_dedupe(search_settings.fuzzy_dedupe_threshold)
+        .with_max_title_length(search_settings.max_title_length)
+        .with_size_precision(search_settings.normalize_file_sizes.then_some(search_settings.size_precision))
+        .with_preferences(app_state::get_preference_weights(state))
+        .with_suspicion_checks(search_settings.suspicion_checks)
+        .with_strict_magnet_validation(search_settings.strict_magnet_validation)
+        .with_max_total_results(search_settings.max_total_results)
+        .with_allowed_languages(search_settings.allowed_languages)
+        .with_latency_order(&app_state::engine_median_latencies(state)),
     ))
 }
 
-// ============ AI分析命令 ============
+/// 计算搜索命令的页码范围：未指定起止页时为 1..=max_pages（默认3页）
+///
+/// `max_pages` 为 0 时按 1 处理；总页数超过 `max_pages_limit` 时截断到上限，避免误操作对站点造成压力。
+fn resolve_page_range(
+    max_pages: Option<u32>,
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    max_pages_limit: u32,
+) -> searcher::PageRange {
+    let start = start_page.unwrap_or(1).max(1);
+    let end = end_page.unwrap_or_else(|| start.saturating_add(max_pages.unwrap_or(3).max(1) - 1));
+    let capped_end = end.min(start.saturating_add(max_pages_limit.max(1) - 1));
+    if capped_end < end {
+        println!("⚠️ Requested pages {start}-{end} exceed the limit of {max_pages_limit} pages, clamped to {start}-{capped_end}");
+    }
+    searcher::PageRange::new(start, capped_end)
+}
 
-/// 统一的标题清理函数
-/// 用途：在第二阶段（分析后）回填标题时，做最少量的清理，保持人类可读性。
-/// 注意：搜索阶段的文件名生成应使用 `extract_clean_title`（searcher.rs）以保证可预期的文件名格式。
-fn clean_title_unified(title: &str) -> String {
-    if title.trim().is_empty() {
-        return "Unknown".to_string();
+/// 按当前设置中的页数上限计算搜索命令的页码范围
+fn page_range_for(
+    state: &app_state::AppState,
+    max_pages: Option<u32>,
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+) -> searcher::PageRange {
+    let max_pages_limit = app_state::get_search_settings(state).max_pages_limit;
+    resolve_page_range(max_pages, start_page, end_page, max_pages_limit)
+}
+
+/// 检查搜索词是否命中设置中的禁止关键词，命中时在发起任何请求前拒绝搜索
+fn ensure_keywords_allowed<'a>(
+    state: &app_state::AppState,
+    keywords: impl IntoIterator<Item = &'a str>,
+) -> Result<(), String> {
+    let settings = app_state::get_search_settings(state);
+    for keyword in keywords {
+        if let Some(term) = filter::find_forbidden_keyword(keyword, &settings.forbidden_keywords, settings.forbidden_keywords_whole_word) {
+            println!("🚫 Search for '{keyword}' blocked by forbidden keyword '{term}'");
+            return Err(i18n::translate_error(&i18n::ErrorCode::SearchForbiddenKeyword(term.to_string())));
+        }
+    }
+    Ok(())
+}
+
+/// 在指定范围内执行搜索，三个搜索命令共用此逻辑以保证行为一致
+///
+/// 同时返回本次搜索各提供商的请求统计，供 `track_engine_health` 使用。
+async fn run_scoped_search(
+    state: &app_state::AppState,
+    keyword: &str,
+    pages: searcher::PageRange,
+    scope: EngineScope,
+) -> Result<(Vec<searcher::SearchResult>, Vec<searcher::ProviderMetrics>), i18n::AppError> {
+    ensure_keywords_allowed(state, [keyword])?;
+    match build_search_core(state, scope)? {
+        Some(search_core) => {
+            searcher::reset_last_search_metrics();
+            let outcome = search_core.search_multi_page(keyword, pages).await;
+            let metrics = search_core.metrics();
+            outcome.map(|results| (results, metrics)).map_err(|e| i18n::AppError::Other(e.to_string()))
+        }
+        None => Ok((Vec::new(), Vec::new())), // 范围内的引擎未启用，但其他引擎可用
+    }
+}
+
+/// `hide_favorited` 为 true 时去掉 infohash 已在收藏夹中的结果
+fn hide_favorited_results(
+    state: &app_state::AppState,
+    results: Vec<searcher::SearchResult>,
+    hide_favorited: Option<bool>,
+) -> Vec<searcher::SearchResult> {
+    if !hide_favorited.unwrap_or(false) {
+        return results;
+    }
+    let before = results.len();
+    let results = filter::exclude_infohashes(results, &app_state::get_favorited_infohashes(state));
+    if results.len() < before {
+        println!("🙈 Hid {} already favorited results", before - results.len());
+    }
+    results
+}
+
+/// 记录本次搜索中各引擎的成败与长期统计并保存，连续失败达到阈值而被自动禁用的引擎通过 `engine-auto-disabled` 事件通知前端，
+/// 疑似页面结构变化的引擎通过 `engine-parse-warning` 事件通知前端
+fn track_engine_health(
+    app_handle: &tauri::AppHandle,
+    state: &app_state::AppState,
+    metrics: &[searcher::ProviderMetrics],
+) {
+    if metrics.is_empty() {
+        return;
     }
 
-    // 移除常见的广告标记，如 [y5y4.com] 或 【...】
-    let re_brackets = Regex::new(r"\[.*?\]|【.*?】").unwrap();
-    let title = re_brackets.replace_all(title, "");
+    for name in app_state::record_engine_statistics(state, metrics) {
+        println!("⚠️ Engine '{name}' returned no results from a non-empty page, its layout may have changed");
+        if let Err(e) = app_handle.emit("engine-parse-warning", &name) {
+            println!("⚠️ Failed to emit engine-parse-warning: {e}");
+        }
+    }
+    let threshold = app_state::get_search_settings(state).auto_disable_after_failures;
+    for name in app_state::record_engine_outcomes(state, metrics, threshold) {
+        println!("🚫 Engine '{name}' disabled after repeated failures");
+        if let Err(e) = app_handle.emit("engine-auto-disabled", &name) {
+            println!("⚠️ Failed to emit engine-auto-disabled: {e}");
+        }
+    }
 
-    // 移除常见的URL和推广信息
-    let re_urls = Regex::new(r"(?i)(www\.\S+\.\S+|https?://\S+)").unwrap();
-    let title = re_urls.replace_all(&title, "");
+    // 保存状态到文件；统计只是附带信息，保存失败不影响本次搜索的结果
+    if let Err(e) = app_state::save_app_state(app_handle, state) {
+        println!("⚠️ Failed to save engine health: {e}");
+    }
+}
 
-    // 清理多余的空格
-    let cleaned = title.trim().replace("  ", " ");
+// ============ AI分析命令 ============
 
-    if cleaned.is_empty() {
+/// 回退标题：AI 没有给出精简标题时，按配置的清理步骤处理原始标题，保持人类可读性
+/// 注意：搜索阶段的文件名生成应使用 `extract_clean_title`（searcher.rs）以保证可预期的文件名格式。
+fn fallback_title(title: &str, steps: &[filter::TitleCleaningStep]) -> String {
+    let cleaned = filter::clean_title(title, steps);
+    if cleaned.trim().is_empty() {
         "Unknown".to_string()
     } else {
         cleaned
@@ -136,26 +317,59 @@ fn clean_title_unified(title: &str) -> String {
 fn create_analysis_result(
     original_result: &searcher::SearchResult,
     cleaned_title: Option<String>,
-    purity_score: u8,
+    purity_score: Option<u8>,
     tags: Vec<String>,
     error: Option<String>,
+    fallback_steps: &[filter::TitleCleaningStep],
 ) -> llm_service::DetailedAnalysisResult {
-    let final_title = cleaned_title.unwrap_or_else(|| clean_title_unified(&original_result.title));
+    let final_title = cleaned_title.unwrap_or_else(|| fallback_title(&original_result.title, fallback_steps));
 
     llm_service::DetailedAnalysisResult {
         title: final_title,
+        original_title: original_result.title.clone(),
         purity_score,
         tags,
         magnet_link: original_result.magnet_link.clone(),
         file_size: original_result.file_size.clone(),
         file_list: original_result.file_list.clone(),
         error,
+        quality_score: None,
+    }
+}
+
+/// 用分析得到的纯净度重新计算质量分
+///
+/// 搜索阶段还没有 AI 分数，`purity` 权重只有在分析结果合并回来之后才能生效。
+fn apply_analysis_quality_scores(
+    results: &[searcher::SearchResult],
+    analyses: &mut [llm_service::DetailedAnalysisResult],
+    weights: &filter::QualityWeights,
+) {
+    let by_magnet: std::collections::HashMap<&str, &searcher::SearchResult> =
+        results.iter().map(|r| (r.magnet_link.as_str(), r)).collect();
+    for analysis in analyses {
+        if let Some(original) = by_magnet.get(analysis.magnet_link.as_str()) {
+            let scored = searcher::SearchResult { score: analysis.purity_score, ..(*original).clone() };
+            analysis.quality_score = Some(filter::compute_quality_score(&scored, weights));
+        }
     }
 }
 
+/// 按配置为分析失败的项目生成结果，`tag_key` 为默认标签的翻译键
+fn create_failed_analysis_result(
+    original_result: &searcher::SearchResult,
+    failure: &app_state::AnalysisFailureConfig,
+    tag_key: &str,
+    error: String,
+) -> llm_service::DetailedAnalysisResult {
+    let tag = failure.tag.clone().unwrap_or_else(|| i18n::t(tag_key));
+    create_analysis_result(original_result, None, failure.score, vec![tag], Some(error), &failure.fallback_title_steps)
+}
+
 
 #[tauri::command]
 async fn analyze_resource(
+    state: tauri::State<'_, app_state::AppState>,
     result: searcher::SearchResult,
     llm_config: llm_service::LlmConfig,
 ) -> Result<llm_service::DetailedAnalysisResult, String> {
@@ -167,19 +381,24 @@ async fn analyze_resource(
             println!("[AI] Analyzed: '{}' -> '{}'", result.title, cleaned_title);
 
             let final_title = if cleaned_title.is_empty() {
-                clean_title_unified(&result.title)
+                let failure = app_state::get_llm_config(&state).analysis_config.failure;
+                fallback_title(&result.title, &failure.fallback_title_steps)
             } else {
                 cleaned_title
             };
 
+            let weights = app_state::get_search_settings(&state).quality_weights;
+            let quality_score = filter::compute_quality_score(&searcher::SearchResult { score: Some(score), ..result.clone() }, &weights);
             Ok(llm_service::DetailedAnalysisResult {
+                original_title: result.title.clone(),
                 title: final_title,
-                purity_score: score,
+                purity_score: Some(score),
                 tags,
                 magnet_link: result.magnet_link,
                 file_size: result.file_size,
                 file_list: result.file_list,
                 error: None,
+                quality_score: Some(quality_score),
             })
         }
         Err(e) => Err(e.to_string()),
@@ -207,6 +426,43 @@ async fn add_to_favorites(
     Ok(result)
 }
 
+/// 批量收藏搜索结果，全部处理完后只保存一次
+#[tauri::command]
+async fn add_many_to_favorites(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    results: Vec<searcher::SearchResult>,
+) -> Result<app_state::BatchFavoriteResult, String> {
+    let summary = app_state::add_many_to_favorites(&state, results);
+
+    // 保存状态到文件
+    if summary.added > 0 {
+        app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+    }
+
+    Ok(summary)
+}
+
+#[tauri::command]
+async fn get_preference_weights(
+    state: tauri::State<'_, app_state::AppState>,
+) -> Result<filter::PreferenceWeights, String> {
+    Ok(app_state::get_preference_weights(&state))
+}
+
+#[tauri::command]
+async fn reset_preference_weights(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+) -> Result<(), String> {
+    app_state::reset_preference_weights(&state);
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 #[tauri::command]
 async fn get_all_favorites(state: tauri::State<'_, app_state::AppState>) -> Result<Vec<app_state::FavoriteItem>, String> {
     Ok(app_state::get_all_favorites(&state))
@@ -226,6 +482,44 @@ async fn remove_from_favorites(
     Ok(())
 }
 
+#[tauri::command]
+async fn mark_favorite_downloaded(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    downloaded: bool,
+) -> Result<(), String> {
+    app_state::mark_favorite_downloaded(&state, id, downloaded).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn mark_favorite_link_dead(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    link_dead: bool,
+) -> Result<(), String> {
+    app_state::mark_favorite_link_dead(&state, id, link_dead).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_favorites_by_downloaded(
+    state: tauri::State<'_, app_state::AppState>,
+    downloaded: bool,
+) -> Result<Vec<app_state::FavoriteItem>, String> {
+    Ok(app_state::get_favorites_by_downloaded(&state, downloaded))
+}
+
 #[tauri::command]
 async fn search_favorites(
     state: tauri::State<'_, app_state::AppState>,
@@ -234,46 +528,404 @@ async fn search_favorites(
     Ok(app_state::search_favorites(&state, query))
 }
 
+#[tauri::command]
+async fn dedupe_favorites(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    strategy: app_state::DedupeStrategy,
+    dry_run: Option<bool>,
+) -> Result<usize, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let merged = app_state::dedupe_favorites(&state, strategy, dry_run);
+
+    if !dry_run && merged > 0 {
+        // 保存状态到文件
+        app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+    }
+
+    Ok(merged)
+}
+
+/// 批量删除符合条件的收藏，`dry_run` 为 true 时只返回将被删除的数量
+#[tauri::command]
+async fn remove_favorites_matching(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    criteria: app_state::FavoriteFilter,
+    dry_run: Option<bool>,
+) -> Result<usize, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let removed = app_state::remove_favorites_matching(&state, &criteria, dry_run);
+
+    if !dry_run && removed > 0 {
+        // 保存状态到文件
+        app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+    }
+
+    Ok(removed)
+}
+
+#[tauri::command]
+async fn get_auto_tag_rules(state: tauri::State<'_, app_state::AppState>) -> Result<Vec<app_state::AutoTagRule>, String> {
+    Ok(app_state::get_auto_tag_rules(&state))
+}
+
+#[tauri::command]
+async fn update_auto_tag_rules(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    rules: Vec<app_state::AutoTagRule>,
+) -> Result<(), String> {
+    app_state::update_auto_tag_rules(&state, rules).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// ============ 收藏文件夹相关命令 ============
+
+#[tauri::command]
+async fn get_all_favorite_folders(state: tauri::State<'_, app_state::AppState>) -> Result<Vec<app_state::FavoriteFolder>, String> {
+    Ok(app_state::get_all_favorite_folders(&state))
+}
+
+#[tauri::command]
+async fn create_favorite_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    name: String,
+    parent_id: Option<String>,
+) -> Result<app_state::FavoriteFolder, String> {
+    let result = app_state::create_favorite_folder(&state, name, parent_id).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+#[tauri::command]
+async fn rename_favorite_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    name: String,
+) -> Result<(), String> {
+    app_state::rename_favorite_folder(&state, id, name).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn move_favorite_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), String> {
+    app_state::move_favorite_folder(&state, id, parent_id).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_favorite_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    mode: app_state::FolderDeleteMode,
+) -> Result<(), String> {
+    app_state::delete_favorite_folder(&state, id, mode).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn move_favorite_to_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    favorite_id: String,
+    folder_id: Option<String>,
+) -> Result<(), String> {
+    app_state::move_favorite_to_folder(&state, favorite_id, folder_id).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 
 
 #[tauri::command]
 async fn search_multi_page(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, app_state::AppState>,
     keyword: String,
     max_pages: Option<u32>,
-) -> Result<Vec<searcher::SearchResult>, String> {
-    let pages = max_pages.unwrap_or(3);
-    let search_core = create_search_core(&state, true, true)?;
-    search_core.search_multi_page(keyword.as_str(), pages).await.map_err(|e| e.to_string())
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<searcher::ResultPage, i18n::AppError> {
+    let pages = page_range_for(&state, max_pages, start_page, end_page);
+    let (results, metrics) = run_scoped_search(&state, &keyword, pages, EngineScope::All).await?;
+    track_engine_health(&app_handle, &state, &metrics);
+    Ok(first_result_page(&app_handle, &state, hide_favorited_results(&state, results, hide_favorited)))
+}
+
+/// 按设置的每页结果数分页并返回首页，其余页由前端凭 `search_id` 调用 `get_next_result_page` 取回；
+/// 未设置每页结果数时首页即包含全部结果
+fn first_result_page(
+    app_handle: &tauri::AppHandle,
+    state: &app_state::AppState,
+    results: Vec<searcher::SearchResult>,
+) -> searcher::ResultPage {
+    let page_size = app_state::get_search_settings(state).results_page_size.unwrap_or(results.len());
+    app_handle.state::<searcher::ResultPager>().paginate(results, page_size)
+}
+
+/// 取回分页搜索的下一页结果，没有更多结果时返回 None
+#[tauri::command]
+fn get_next_result_page(
+    pager: tauri::State<'_, searcher::ResultPager>,
+    search_id: String,
+) -> Option<searcher::ResultPage> {
+    pager.next_page(&search_id)
 }
 
 #[tauri::command]
 async fn search_clmclm_first(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, app_state::AppState>,
     keyword: String,
     max_pages: Option<u32>,
-) -> Result<Vec<searcher::SearchResult>, String> {
-    let pages = max_pages.unwrap_or(3);
-    match create_search_core(&state, true, false) {
-        Ok(search_core) => search_core.search_multi_page(keyword.as_str(), pages).await.map_err(|e| e.to_string()),
-        Err(_) => Ok(Vec::new()), // 如果clmclm未启用，则返回空结果
-    }
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<searcher::ResultPage, i18n::AppError> {
+    let pages = page_range_for(&state, max_pages, start_page, end_page);
+    let (results, metrics) = run_scoped_search(&state, &keyword, pages, EngineScope::ClmclmOnly).await?;
+    track_engine_health(&app_handle, &state, &metrics);
+    Ok(first_result_page(&app_handle, &state, hide_favorited_results(&state, results, hide_favorited)))
 }
 
 #[tauri::command]
 async fn search_other_engines(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, app_state::AppState>,
     keyword: String,
     max_pages: Option<u32>,
-) -> Result<Vec<searcher::SearchResult>, String> {
-    let pages = max_pages.unwrap_or(3);
-    match create_search_core(&state, false, true) {
-        Ok(search_core) => search_core.search_multi_page(keyword.as_str(), pages).await.map_err(|e| e.to_string()),
-        Err(_) => Ok(Vec::new()), // 如果没有其他引擎，则返回空结果
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<searcher::ResultPage, i18n::AppError> {
+    let pages = page_range_for(&state, max_pages, start_page, end_page);
+    let (results, metrics) = run_scoped_search(&state, &keyword, pages, EngineScope::OthersOnly).await?;
+    track_engine_health(&app_handle, &state, &metrics);
+    Ok(first_result_page(&app_handle, &state, hide_favorited_results(&state, results, hide_favorited)))
+}
+
+
+
+/// 搜索指定页码范围，并将新结果合并到前端已有的结果中（用于“加载更多”）
+#[tauri::command]
+#[allow(clippy::too_many_arguments)] // 命令参数由前端逐个传入
+async fn search_and_merge(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    existing: Vec<searcher::SearchResult>,
+    keyword: String,
+    max_pages: Option<u32>,
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<Vec<searcher::SearchResult>, i18n::AppError> {
+    let pages = page_range_for(&state, max_pages, start_page, end_page);
+    let (new_results, metrics) = run_scoped_search(&state, &keyword, pages, EngineScope::All).await?;
+    track_engine_health(&app_handle, &state, &metrics);
+    Ok(filter::merge_results(existing, hide_favorited_results(&state, new_results, hide_favorited)))
+}
+
+/// 多关键词搜索：分别搜索每个关键词，合并去重后返回，结果记录命中的关键词
+#[tauri::command]
+async fn search_multi_keyword(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    keywords: Vec<String>,
+    max_pages: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<Vec<searcher::SearchResult>, i18n::AppError> {
+    ensure_keywords_allowed(&state, keywords.iter().map(String::as_str))?;
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(Vec::new());
+    };
+    searcher::reset_last_search_metrics();
+    let pages = page_range_for(&state, max_pages, None, None);
+    let results = search_core.search_multi_keyword(&keywords, pages).await.map_err(|e| e.to_string())?;
+    track_engine_health(&app_handle, &state, &search_core.metrics());
+    Ok(hide_favorited_results(&state, results, hide_favorited))
+}
+
+/// 按 infohash（或完整的磁力链接）在所有已启用的引擎中查找资源
+#[tauri::command]
+async fn search_by_infohash(
+    state: tauri::State<'_, app_state::AppState>,
+    infohash: String,
+) -> Result<Vec<searcher::SearchResult>, i18n::AppError> {
+    let Some(infohash) = searcher::parse_infohash_input(&infohash) else {
+        return Err(i18n::translate_error(&i18n::ErrorCode::InfohashInvalid).into());
+    };
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(Vec::new());
+    };
+    search_core.search_by_infohash(&infohash).await.map_err(|e| i18n::AppError::Other(e.to_string()))
+}
+
+/// 流式搜索：每批结果完成后通过 `search-results-batch` 事件推送给前端，最终返回去重后的全部结果
+#[tauri::command]
+async fn search_multi_page_stream(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    keyword: String,
+    max_pages: Option<u32>,
+    start_page: Option<u32>,
+    end_page: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<Vec<searcher::SearchResult>, i18n::AppError> {
+    ensure_keywords_allowed(&state, [keyword.as_str()])?;
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(Vec::new());
+    };
+    searcher::reset_last_search_metrics();
+    let pages = page_range_for(&state, max_pages, start_page, end_page);
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<searcher::SearchBatch>(16);
+
+    let forward = async {
+        let mut batches = Vec::new();
+        while let Some(mut batch) = rx.recv().await {
+            batch.results = hide_favorited_results(&state, batch.results, hide_favorited);
+            if let Err(e) = app_handle.emit("search-results-batch", &batch) {
+                println!("⚠️ Failed to emit search batch: {e}");
+            }
+            batches.push(batch);
+        }
+        batches
+    };
+
+    let (outcome, batches) = tokio::join!(search_core.search_stream(&keyword, pages, tx), forward);
+    outcome.map_err(|e| e.to_string())?;
+    track_engine_health(&app_handle, &state, &search_core.metrics());
+    Ok(search_core.finalize_results(searcher::merge_batches(batches)))
+}
+
+/// 快速搜索：立即返回不经 AI 的解析结果，需要 AI 提取的页面在后台处理
+///
+/// 每完成一页发送一次 "quick-search-refined" 事件（`SearchBatch`），全部完成后发送 "quick-search-refined-done"。
+/// 结果的评分与标签仍由前端调用批量分析命令获取。
+#[tauri::command]
+async fn quick_search(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    keyword: String,
+    pages: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<Vec<searcher::SearchResult>, i18n::AppError> {
+    ensure_keywords_allowed(&state, [keyword.as_str()])?;
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(Vec::new());
+    };
+    let pages = page_range_for(&state, pages, None, None);
+    let (results, deferred) = search_core.quick_search(&keyword, pages).await.map_err(|e| e.to_string())?;
+    let hidden = if hide_favorited.unwrap_or(false) {
+        app_state::get_favorited_infohashes(&state)
+    } else {
+        Default::default()
+    };
+    let results = filter::exclude_infohashes(results, &hidden);
+
+    if !deferred.is_empty() {
+        println!("⏳ {} pages queued for background AI extraction", deferred.len());
+        tauri::async_runtime::spawn(async move {
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<searcher::SearchBatch>(16);
+            let forward = async {
+                while let Some(mut batch) = rx.recv().await {
+                    batch.results = filter::exclude_infohashes(batch.results, &hidden);
+                    if let Err(e) = app_handle.emit("quick-search-refined", &batch) {
+                        println!("⚠️ Failed to emit refined results: {e}");
+                    }
+                }
+            };
+            tokio::join!(search_core.refine_deferred(&keyword, deferred, tx), forward);
+            if let Err(e) = app_handle.emit("quick-search-refined-done", &keyword) {
+                println!("⚠️ Failed to emit refinement completion: {e}");
+            }
+        });
     }
+    Ok(results)
+}
+
+/// 获取 clmclm.com 的分类列表（缓存一天），供界面提供分类下拉框
+#[tauri::command]
+async fn get_clmclm_categories() -> Result<Vec<searcher::ClmclmCategory>, String> {
+    searcher::clmclm_categories(&searcher::ClmclmProvider::new(), false)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 忽略缓存，重新抓取 clmclm.com 的分类列表
+#[tauri::command]
+async fn refresh_clmclm_categories() -> Result<Vec<searcher::ClmclmCategory>, String> {
+    searcher::clmclm_categories(&searcher::ClmclmProvider::new(), true)
+        .await
+        .map_err(|e| e.to_string())
 }
 
+/// 各提供商最近一次搜索的请求统计
+#[tauri::command]
+async fn get_last_search_metrics() -> Result<Vec<searcher::ProviderMetrics>, String> {
+    Ok(searcher::last_search_metrics())
+}
+
+/// 调试用：用指定提供商解析粘贴的HTML，不发起网络请求
+///
+/// 已启用的引擎使用当前配置（包括AI和选择器），其他名称按基础的自定义引擎解析。
+#[tauri::command]
+async fn parse_html_with_provider(
+    state: tauri::State<'_, app_state::AppState>,
+    provider_name: String,
+    url_template: String,
+    html: String,
+) -> Result<Vec<searcher::SearchResult>, String> {
+    let configured = build_search_core(&state, EngineScope::All)
+        .ok()
+        .flatten()
+        .and_then(|core| core.provider(&provider_name));
+    let provider: std::sync::Arc<dyn searcher::SearchProvider> = match configured {
+        Some(provider) => provider,
+        None if provider_name == "clmclm.com" => std::sync::Arc::new(searcher::ClmclmProvider::new()),
+        None => std::sync::Arc::new(searcher::GenericProvider::new(provider_name, url_template.clone())),
+    };
+
+    searcher::parse_html_with_provider(provider.as_ref(), &url_template, &html)
+        .await
+        .map_err(|e| e.to_string())
+}
 
+#[tauri::command]
+async fn get_provider_capabilities() -> Result<std::collections::BTreeMap<String, searcher::ProviderCapabilities>, String> {
+    Ok(searcher::builtin_provider_capabilities())
+}
 
 // ============ 搜索引擎相关命令 ============
 
@@ -310,6 +962,234 @@ async fn update_search_engine(
     Ok(())
 }
 
+#[tauri::command]
+async fn update_engine_url_codes(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    category_code: Option<String>,
+    sort_code: Option<String>,
+) -> Result<(), String> {
+    app_state::update_engine_url_codes(&state, id, category_code, sort_code)
+        .map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_selectors(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    selectors: Option<searcher::ClmclmSelectors>,
+) -> Result<(), String> {
+    app_state::update_engine_selectors(&state, id, selectors)
+        .map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_generic_selectors(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    selectors: Option<searcher::GenericSelectors>,
+) -> Result<(), String> {
+    app_state::update_engine_generic_selectors(&state, id, selectors).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_json_api(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    mapping: Option<searcher::JsonApiMapping>,
+) -> Result<(), String> {
+    app_state::update_engine_json_api(&state, id, mapping).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_torznab(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    config: Option<searcher::TorznabConfig>,
+) -> Result<(), String> {
+    app_state::update_engine_torznab(&state, id, config).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_auth(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    auth: Option<searcher::EngineAuth>,
+) -> Result<(), String> {
+    app_state::update_engine_auth(&state, id, auth).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_parse_order(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    order: Vec<searcher::ParseStrategy>,
+) -> Result<(), String> {
+    app_state::update_engine_parse_order(&state, id, order).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_headers(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    headers: std::collections::BTreeMap<String, String>,
+) -> Result<(), String> {
+    app_state::update_engine_headers(&state, id, headers).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 用固定关键词搜索所有已启用引擎的第一页，报告哪些引擎没有返回结果（解析可能已失效）
+#[tauri::command]
+async fn self_test_engines(
+    state: tauri::State<'_, app_state::AppState>,
+    keyword: String,
+) -> Result<Vec<searcher::EngineSelfTest>, i18n::AppError> {
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(Vec::new());
+    };
+    println!("🩺 Self-testing engines with keyword '{keyword}'");
+    Ok(search_core.self_test(&keyword).await)
+}
+
+/// 用同一关键词分别搜索两个已启用的引擎，返回各自的结果以及按 infohash 计算的重合和独有数量
+#[tauri::command]
+async fn compare_engines(
+    state: tauri::State<'_, app_state::AppState>,
+    engine_a: String,
+    engine_b: String,
+    keyword: String,
+    pages: Option<u32>,
+) -> Result<searcher::EngineComparison, i18n::AppError> {
+    ensure_keywords_allowed(&state, [keyword.as_str()])?;
+    let search_core = build_search_core(&state, EngineScope::All)?
+        .filter(|core| core.provider(&engine_a).is_some() && core.provider(&engine_b).is_some())
+        .ok_or_else(|| i18n::translate_error(&i18n::ErrorCode::EngineNotFound))?;
+    let pages = page_range_for(&state, pages, None, None);
+    println!("⚖️ Comparing engines '{engine_a}' and '{engine_b}' with keyword '{keyword}'");
+    search_core
+        .compare_providers(&engine_a, &engine_b, &keyword, pages)
+        .await
+        .map_err(|e| i18n::AppError::Other(e.to_string()))
+}
+
+/// 抓取指定引擎的一页结果，估算第一阶段 AI 提取会消耗的 token 数，便于调整 `max_html_chars`
+#[tauri::command]
+async fn estimate_extraction_tokens(
+    state: tauri::State<'_, app_state::AppState>,
+    engine_name: String,
+    keyword: String,
+) -> Result<searcher::ExtractionTokenEstimate, String> {
+    let engine = app_state::get_all_engines(&state)
+        .into_iter()
+        .find(|e| e.name == engine_name)
+        .ok_or_else(|| i18n::translate_error(&i18n::ErrorCode::EngineNotFound))?;
+    let max_html_chars = llm_service::html_char_limit(app_state::get_llm_config(&state).extraction_config.max_html_chars);
+
+    let provider = searcher::GenericProvider::new(engine.name, engine.url_template).with_headers(engine.headers);
+    provider
+        .estimate_extraction_tokens(&keyword, max_html_chars)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取各引擎的长期搜索统计（搜索次数、成功次数、平均结果数）
+#[tauri::command]
+async fn get_engine_statistics(
+    state: tauri::State<'_, app_state::AppState>,
+) -> Result<std::collections::BTreeMap<String, app_state::EngineStatistics>, String> {
+    Ok(app_state::get_engine_statistics(&state))
+}
+
+#[tauri::command]
+async fn reset_engine_statistics(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+) -> Result<(), String> {
+    app_state::reset_engine_statistics(&state);
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 重新启用（可能已被自动禁用的）搜索引擎，并清零连续失败次数
+#[tauri::command]
+async fn reenable_engine(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+) -> Result<(), String> {
+    app_state::reenable_engine(&state, id).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_engine_use_ai(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    id: String,
+    use_ai: bool,
+) -> Result<(), String> {
+    app_state::update_engine_use_ai(&state, id, use_ai).map_err(|e| e.to_string())?;
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 #[tauri::command]
 async fn get_all_engines(state: tauri::State<'_, app_state::AppState>) -> Result<Vec<app_state::SearchEngine>, String> {
     Ok(app_state::get_all_engines(&state))
@@ -393,10 +1273,32 @@ async fn test_extraction_connection(config: app_state::SingleLlmConfig) -> Resul
         api_base: config.api_base,
         model: config.model,
         batch_size: config.batch_size,
+        single_item_timeout_secs: config.single_item_timeout_secs,
+        max_concurrency: config.max_concurrency,
+        max_html_chars: config.max_html_chars,
     };
     llm_service::test_connection(&llm_config).await.map_err(|e| e.to_string())
 }
 
+/// 用粘贴的示例HTML测试提取配置，返回解析结果与模型原始响应
+#[tauri::command]
+async fn test_extraction_with_sample(
+    config: app_state::SingleLlmConfig,
+    html: String,
+) -> Result<llm_service::ExtractionSampleResult, String> {
+    let llm_config = llm_service::LlmConfig {
+        provider: config.provider,
+        api_key: config.api_key,
+        api_base: config.api_base,
+        model: config.model,
+        batch_size: config.batch_size,
+        single_item_timeout_secs: config.single_item_timeout_secs,
+        max_concurrency: config.max_concurrency,
+        max_html_chars: config.max_html_chars,
+    };
+    llm_service::test_extraction_with_sample(&llm_config, &html).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn test_analysis_connection(config: app_state::SingleLlmConfig) -> Result<String, String> {
     let llm_config = llm_service::LlmConfig {
@@ -405,23 +1307,249 @@ async fn test_analysis_connection(config: app_state::SingleLlmConfig) -> Result<
         api_base: config.api_base,
         model: config.model,
         batch_size: config.batch_size,
+        single_item_timeout_secs: config.single_item_timeout_secs,
+        max_concurrency: config.max_concurrency,
+        max_html_chars: None,
     };
     llm_service::test_connection(&llm_config).await.map_err(|e| e.to_string())
 }
 
-// 注意：load_llm_config_from_app 和 load_llm_config_from_file 函数已被删除
-// 因为它们未被使用，LLM配置现在通过前端直接传递
+// 注意：load_llm_config_from_app 和 load_llm_config_from_file 函数已被删除
+// 因为它们未被使用，LLM配置现在通过前端直接传递
+
+// ============ LLM 配置相关命令 ============
+
+#[tauri::command]
+async fn get_llm_config(state: tauri::State<'_, app_state::AppState>) -> Result<app_state::LlmConfig, String> {
+    let config = app_state::get_llm_config(&state);
+    println!("🔧 Get LLM config: extraction_batch_size={}, analysis_batch_size={}", config.extraction_config.batch_size, config.analysis_config.batch_size);
+    Ok(config)
+}
+
+
+
+/// 单个批次内的分析条目：原始搜索结果及其对应的分析输入
+type AnalysisEntry<'a> = (&'a searcher::SearchResult, llm_service::BatchAnalysisItem);
+
+/// 将批量分析返回的结果与原始搜索结果对应起来
+fn collect_batch_results(
+    entries: &[AnalysisEntry<'_>],
+    batch_results: Vec<llm_service::BatchAnalysisResult>,
+    failure: &app_state::AnalysisFailureConfig,
+) -> Vec<llm_service::DetailedAnalysisResult> {
+    entries
+        .iter()
+        .zip(batch_results)
+        .map(|((original_result, _), analysis_result)| {
+            let cleaned_title = if analysis_result.cleaned_title.is_empty() {
+                None
+            } else {
+                Some(analysis_result.cleaned_title)
+            };
+
+            create_analysis_result(
+                original_result,
+                cleaned_title,
+                Some(analysis_result.purity_score),
+                analysis_result.tags,
+                None,
+                &failure.fallback_title_steps,
+            )
+        })
+        .collect()
+}
+
+/// 单独分析一个项目（只尝试一次，带超时）
+async fn analyze_single_entry(
+    client: &dyn LlmClient,
+    entry: &AnalysisEntry<'_>,
+    llm_config: &llm_service::LlmConfig,
+    failure: &app_state::AnalysisFailureConfig,
+) -> llm_service::DetailedAnalysisResult {
+    let (original_result, item) = entry;
+    // 将单个项目包装为批量格式
+    let single_item = vec![item.clone()];
+
+    // 时限为 0 时不限制，便于使用较慢的本地模型
+    let timeout_secs = llm_config.single_item_timeout_secs;
+    let analysis = client.batch_analyze_multiple_items(&single_item, llm_config);
+    let outcome = if timeout_secs == 0 {
+        Ok(analysis.await)
+    } else {
+        tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), analysis).await
+    };
+
+    match outcome {
+        Ok(Ok(batch_results)) => {
+            if let Some(result) = collect_batch_results(std::slice::from_ref(entry), batch_results, failure).pop() {
+                result
+            } else {
+                println!("⚠️ Individual analysis for '{}' returned no results", item.title);
+                create_failed_analysis_result(
+                    original_result,
+                    failure,
+                    "system.analysis_no_results",
+                    "Individual analysis returned no results".to_string(),
+                )
+            }
+        }
+        Ok(Err(individual_error)) => {
+            println!("⚠️ Individual analysis for '{}' failed: {}", item.title, individual_error);
+            create_failed_analysis_result(
+                original_result,
+                failure,
+                "system.analysis_failed",
+                format!("Individual analysis failed: {individual_error}"),
+            )
+        }
+        Err(_timeout) => {
+            println!("⚠️ Individual analysis for '{}' timed out", item.title);
+            create_failed_analysis_result(
+                original_result,
+                failure,
+                "system.analysis_timeout",
+                format!("Analysis timed out after {timeout_secs} seconds"),
+            )
+        }
+    }
+}
+
+/// 二分重试：失败的批次拆成两半分别重试，直到定位到出错的单个项目
+async fn analyze_with_binary_split(
+    client: &dyn LlmClient,
+    entries: &[AnalysisEntry<'_>],
+    llm_config: &llm_service::LlmConfig,
+    failure: &app_state::AnalysisFailureConfig,
+) -> Vec<llm_service::DetailedAnalysisResult> {
+    let mut pending = std::collections::VecDeque::new();
+    let mid = entries.len() / 2;
+    pending.push_back(&entries[..mid]);
+    pending.push_back(&entries[mid..]);
+
+    let mut results = Vec::new();
+    while let Some(part) = pending.pop_front() {
+        match part.len() {
+            0 => {}
+            1 => results.push(analyze_single_entry(client, &part[0], llm_config, failure).await),
+            len => {
+                let items: Vec<_> = part.iter().map(|(_, item)| item.clone()).collect();
+                match client.batch_analyze_multiple_items(&items, llm_config).await {
+                    Ok(batch_results) => results.extend(collect_batch_results(part, batch_results, failure)),
+                    Err(e) => {
+                        println!("⚠️ Split batch of {len} items failed, splitting further: {e}");
+                        // 保持原有顺序：先处理前半部分
+                        pending.push_front(&part[len / 2..]);
+                        pending.push_front(&part[..len / 2]);
+                    }
+                }
+            }
+        }
+    }
+
+    results
+}
+
+/// 分批分析搜索结果，批次失败时按 `retry_strategy` 回退
+async fn analyze_results_in_batches(
+    client: &dyn LlmClient,
+    results: &[searcher::SearchResult],
+    llm_config: &llm_service::LlmConfig,
+    retry_strategy: app_state::BatchRetryStrategy,
+    failure: &app_state::AnalysisFailureConfig,
+) -> Result<Vec<llm_service::DetailedAnalysisResult>, String> {
+    // 转换为批量分析格式，并保留与原始结果的对应关系
+    let batch_items: Vec<AnalysisEntry<'_>> = results
+        .iter()
+        .filter(|r| !r.file_list.is_empty())
+        .map(|r| (r, llm_service::BatchAnalysisItem {
+            title: r.title.clone(),
+            file_list: r.file_list.clone(),
+        }))
+        .collect();
+
+    if batch_items.is_empty() {
+        println!("⚠️ No valid results with file lists for batch analysis");
+        return Ok(Vec::new());
+    }
+
+    // 旧版本保存的配置可能超出范围，这里再次修正
+    let batch_size = app_state::clamp_batch_size(llm_config.batch_size) as usize;
+    let max_concurrency = llm_config.max_concurrency.unwrap_or(1).max(1) as usize;
+    let total_batches = batch_items.len().div_ceil(batch_size);
+    let failed_batches = AtomicUsize::new(0);
+    const MAX_FAILED_BATCHES: usize = 3; // 最多允许3个批次失败
 
-// ============ LLM 配置相关命令 ============
+    // 分批处理，最多同时进行 max_concurrency 个批次，结果保持原有顺序
+    let batch_outputs: Vec<Result<Vec<llm_service::DetailedAnalysisResult>, String>> = stream::iter(batch_items.chunks(batch_size).enumerate())
+        .map(|(batch_index, chunk)| {
+            let failed_batches = &failed_batches;
+            async move {
+                println!(
+                    "🔄 Frontend processing batch {}/{} ({} items)",
+                    batch_index + 1,
+                    total_batches,
+                    chunk.len()
+                );
+
+                // 如果失败的批次太多，直接返回错误
+                let failed_so_far = failed_batches.load(Ordering::SeqCst);
+                if failed_so_far >= MAX_FAILED_BATCHES {
+                    return Err(format!("Too many batch failures ({failed_so_far}/{MAX_FAILED_BATCHES}), aborting analysis"));
+                }
 
-#[tauri::command]
-async fn get_llm_config(state: tauri::State<'_, app_state::AppState>) -> Result<app_state::LlmConfig, String> {
-    let config = app_state::get_llm_config(&state);
-    println!("🔧 Get LLM config: extraction_batch_size={}, analysis_batch_size={}", config.extraction_config.batch_size, config.analysis_config.batch_size);
-    Ok(config)
-}
+                let items: Vec<_> = chunk.iter().map(|(_, item)| item.clone()).collect();
+                match client.batch_analyze_multiple_items(&items, llm_config).await {
+                    Ok(batch_results) => {
+                        println!("✅ Frontend batch {} success.", batch_index + 1);
+                        // 将批量结果转换为 DetailedAnalysisResult
+                        Ok(collect_batch_results(chunk, batch_results, failure))
+                    }
+                    Err(e) => {
+                        let failed = failed_batches.fetch_add(1, Ordering::SeqCst) + 1;
+                        println!("⚠️ Frontend batch {} failed ({}/{}): {}", batch_index + 1, failed, MAX_FAILED_BATCHES, e);
+
+                        // 如果这是最后一次尝试，直接添加失败结果而不进行单个分析
+                        if failed >= MAX_FAILED_BATCHES {
+                            return Ok(chunk
+                                .iter()
+                                .map(|(original_result, _)| create_failed_analysis_result(
+                                    original_result,
+                                    failure,
+                                    "system.analysis_aborted",
+                                    "Too many batch failures, analysis aborted".to_string(),
+                                ))
+                                .collect());
+                        }
+
+                        let mut recovered = Vec::new();
+                        match retry_strategy {
+                            // 回退到单个分析（使用批量分析处理单个项目）
+                            app_state::BatchRetryStrategy::PerItem => {
+                                for entry in chunk {
+                                    recovered.push(analyze_single_entry(client, entry, llm_config, failure).await);
+                                }
+                            }
+                            app_state::BatchRetryStrategy::BinarySplit => {
+                                recovered.extend(analyze_with_binary_split(client, chunk, llm_config, failure).await);
+                            }
+                        }
+                        Ok(recovered)
+                    }
+                }
+            }
+        })
+        .buffered(max_concurrency)
+        .collect()
+        .await;
 
+    let mut all_results = Vec::new();
+    for output in batch_outputs {
+        all_results.extend(output?);
+    }
 
+    println!("🎉 Frontend batch analysis completed: {} results processed", all_results.len());
+    Ok(all_results)
+}
 
 #[tauri::command]
 async fn batch_analyze_resources(
@@ -436,160 +1564,271 @@ async fn batch_analyze_resources(
         return Ok(Vec::new());
     }
 
-    // 转换为批量分析格式
-    let batch_items: Vec<llm_service::BatchAnalysisItem> = results
-        .iter()
-        .filter(|r| !r.file_list.is_empty())
-        .map(|r| llm_service::BatchAnalysisItem {
-            title: r.title.clone(),
-            file_list: r.file_list.clone(),
-        })
-        .collect();
-
-    if batch_items.is_empty() {
-        println!("⚠️ No valid results with file lists for batch analysis");
-        return Ok(Vec::new());
-    }
-
     // 转换配置
+    let retry_strategy = config.analysis_config.batch_retry_strategy;
+    let failure = config.analysis_config.failure;
     let llm_config = llm_service::LlmConfig {
         provider: config.analysis_config.provider,
         api_key: config.analysis_config.api_key,
         api_base: config.analysis_config.api_base,
         model: config.analysis_config.model,
         batch_size: config.analysis_config.batch_size,
+        single_item_timeout_secs: config.analysis_config.single_item_timeout_secs,
+        max_concurrency: config.analysis_config.max_concurrency,
+        max_html_chars: None,
     };
 
     let client = llm_service::GeminiClient::new();
-    let batch_size = config.analysis_config.batch_size as usize;
-    let mut all_results = Vec::new();
-    let mut failed_batches = 0;
-    const MAX_FAILED_BATCHES: usize = 3; // 最多允许3个批次失败
+    let mut analyses = analyze_results_in_batches(&client, &results, &llm_config, retry_strategy, &failure).await?;
+    apply_analysis_quality_scores(&results, &mut analyses, &app_state::get_search_settings(&state).quality_weights);
+    Ok(analyses)
+}
 
-    // 分批处理
-    for (batch_index, chunk) in batch_items.chunks(batch_size).enumerate() {
-        use std::num::NonZeroUsize;
-        let Some(nz_batch) = NonZeroUsize::new(batch_size) else { continue };
-        println!(
-            "🔄 Frontend processing batch {}/{} ({} items)",
-            batch_index + 1,
-            batch_items.len().div_ceil(nz_batch.get()),
-            chunk.len()
-        );
+/// 按指定方式排序（前端已合并分析结果的）搜索结果
+///
+/// 排序前用当前权重重新计算质量分，使 AI 纯净度参与 `quality_desc` 排序；主键相同时保留站点原有顺序。
+#[tauri::command]
+async fn sort_search_results(
+    state: tauri::State<'_, app_state::AppState>,
+    mut results: Vec<searcher::SearchResult>,
+    key: filter::SortKey,
+) -> Result<Vec<searcher::SearchResult>, String> {
+    filter::apply_quality_scores(&mut results, &app_state::get_search_settings(&state).quality_weights);
+    filter::sort_results(&mut results, key);
+    Ok(results)
+}
+
+/// 流式分析：每个项目分析完成后立即通过 `analysis-item` 事件推送给前端，`index` 对应 `results` 中的位置
+#[tauri::command]
+async fn batch_analyze_resources_streaming(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    results: Vec<searcher::SearchResult>,
+) -> Result<(), String> {
+    let config = app_state::get_llm_config(&state);
+    let llm_config = llm_service::LlmConfig {
+        provider: config.analysis_config.provider,
+        api_key: config.analysis_config.api_key,
+        api_base: config.analysis_config.api_base,
+        model: config.analysis_config.model,
+        batch_size: config.analysis_config.batch_size,
+        single_item_timeout_secs: config.analysis_config.single_item_timeout_secs,
+        max_concurrency: config.analysis_config.max_concurrency,
+        max_html_chars: None,
+    };
 
-        // 如果失败的批次太多，直接返回错误
-        if failed_batches >= MAX_FAILED_BATCHES {
-            return Err(format!("Too many batch failures ({failed_batches}/{MAX_FAILED_BATCHES}), aborting analysis"));
+    // 只分析带文件列表的结果，同时记录它们在原列表中的位置
+    let entries: Vec<(usize, llm_service::BatchAnalysisItem)> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| !r.file_list.is_empty())
+        .map(|(index, r)| (index, llm_service::BatchAnalysisItem {
+            title: r.title.clone(),
+            file_list: r.file_list.clone(),
+        }))
+        .collect();
+    println!("🔧 Frontend streaming analysis: {} results", entries.len());
+
+    let client = llm_service::GeminiClient::new();
+    stream_analysis_in_batches(&client, &entries, &llm_config, |streamed| {
+        if let Err(e) = app_handle.emit("analysis-item", &streamed) {
+            println!("⚠️ Failed to emit analysis item: {e}");
         }
+    })
+    .await
+}
 
-        match client.batch_analyze_multiple_items(chunk, &llm_config).await {
-            Ok(batch_results) => {
-                // 将批量结果转换为 DetailedAnalysisResult
-                for (i, analysis_result) in batch_results.iter().enumerate() {
-                    if let Some(original_result) = results.get(batch_index * batch_size + i) {
-                        let cleaned_title = if analysis_result.cleaned_title.is_empty() {
-                            None
-                        } else {
-                            Some(analysis_result.cleaned_title.clone())
-                        };
-
-                        all_results.push(create_analysis_result(
-                            original_result,
-                            cleaned_title,
-                            analysis_result.purity_score,
-                            analysis_result.tags.clone(),
-                            None,
-                        ));
-                    }
-                }
-                println!("✅ Frontend batch {} success.", batch_index + 1);
-            }
-            Err(e) => {
-                failed_batches += 1;
-                println!("⚠️ Frontend batch {} failed ({}/{}): {}", batch_index + 1, failed_batches, MAX_FAILED_BATCHES, e);
-
-                // 如果这是最后一次尝试，直接添加失败结果而不进行单个分析
-                if failed_batches >= MAX_FAILED_BATCHES {
-                    for (i, _item) in chunk.iter().enumerate() {
-                        if let Some(original_result) = results.get(batch_index * batch_size + i) {
-                            all_results.push(create_analysis_result(
-                                original_result,
-                                None,
-                                50, // 默认分数
-                                vec!["Analysis Failed - Too Many Failures".to_string()],
-                                Some("Too many batch failures, analysis aborted".to_string()),
-                            ));
-                        }
-                    }
-                    continue;
+/// 分批流式分析 `entries`（原列表位置与分析项），最多同时进行 `max_concurrency` 个批次
+///
+/// 每得到一个项目的结果就调用 `on_item`，其中的 `index` 已换算为原列表中的位置；任一批次失败时返回该错误。
+async fn stream_analysis_in_batches(
+    client: &dyn LlmClient,
+    entries: &[(usize, llm_service::BatchAnalysisItem)],
+    llm_config: &llm_service::LlmConfig,
+    on_item: impl Fn(llm_service::StreamedAnalysis),
+) -> Result<(), String> {
+    let batch_size = app_state::clamp_batch_size(llm_config.batch_size) as usize;
+    let max_concurrency = llm_config.max_concurrency.unwrap_or(1).max(1) as usize;
+    let on_item = &on_item;
+
+    let outcomes: Vec<anyhow::Result<()>> = stream::iter(entries.chunks(batch_size))
+        .map(|chunk| async move {
+            let items: Vec<_> = chunk.iter().map(|(_, item)| item.clone()).collect();
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<llm_service::StreamedAnalysis>(16);
+
+            let forward = async {
+                while let Some(mut streamed) = rx.recv().await {
+                    streamed.index = chunk[streamed.index].0;
+                    on_item(streamed);
                 }
+            };
+            let (analysis, ()) = tokio::join!(client.batch_analyze_multiple_items_streaming(&items, llm_config, tx), forward);
+            analysis
+        })
+        .buffer_unordered(max_concurrency)
+        .collect()
+        .await;
 
-                // 回退到单个分析（使用批量分析处理单个项目）
-                for (i, item) in chunk.iter().enumerate() {
-                    if let Some(original_result) = results.get(batch_index * batch_size + i) {
-                        // 将单个项目包装为批量格式
-                        let single_item = vec![item.clone()];
-
-                        // 单个分析只尝试一次，不进行重试
-                        match tokio::time::timeout(
-                            std::time::Duration::from_secs(30), // 30秒超时
-                            client.batch_analyze_multiple_items(&single_item, &llm_config)
-                        ).await {
-                            Ok(Ok(mut batch_results)) => {
-                                if let Some(result) = batch_results.pop() {
-                                    let cleaned_title = if result.cleaned_title.is_empty() {
-                                        None
-                                    } else {
-                                        Some(result.cleaned_title)
-                                    };
-
-                                    all_results.push(create_analysis_result(
-                                        original_result,
-                                        cleaned_title,
-                                        result.purity_score,
-                                        result.tags,
-                                        None,
-                                    ));
-                                } else {
-                                    println!("⚠️ Individual analysis for '{}' returned no results", item.title);
-                                    all_results.push(create_analysis_result(
-                                        original_result,
-                                        None,
-                                        50,
-                                        vec!["No Results".to_string()],
-                                        Some("Individual analysis returned no results".to_string()),
-                                    ));
-                                }
-                            }
-                            Ok(Err(individual_error)) => {
-                println!("⚠️ Individual analysis for '{}' failed: {}", item.title, individual_error);
-                                all_results.push(create_analysis_result(
-                                    original_result,
-                                    None,
-                                    50,
-                    vec!["Individual Analysis Failed".to_string()],
-                    Some(format!("Individual analysis failed: {individual_error}")),
-                                ));
-                            }
-                            Err(_timeout) => {
-                                println!("⚠️ Individual analysis for '{}' timed out", item.title);
-                                all_results.push(create_analysis_result(
-                                    original_result,
-                                    None,
-                                    50,
-                                    vec!["Analysis Timeout".to_string()],
-                                    Some("Analysis timed out after 30 seconds".to_string()),
-                                ));
-                            }
-                        }
-                    }
-                }
+    outcomes.into_iter().collect::<anyhow::Result<()>>().map_err(|e| e.to_string())
+}
+
+/// 边搜索边分析的结果，`analyses` 只包含最终结果中仍然保留的项目
+#[derive(Debug, serde::Serialize)]
+struct SearchAndAnalyzeOutput {
+    results: Vec<searcher::SearchResult>,
+    analyses: Vec<llm_service::DetailedAnalysisResult>,
+}
+
+/// 流水线式搜索与分析：每个提供商的一批结果到达后立即开始分析，不等待其他提供商抓取完成
+///
+/// 每批结果先按搜索设置过滤去重，之前批次已分析过的资源不再分析。
+/// 最多同时分析 `concurrency` 批结果，每批分析完成后调用 `on_analyzed`。
+#[allow(clippy::too_many_arguments)] // 搜索与分析两个阶段的配置分别传入
+async fn pipeline_search_and_analysis(
+    search_core: &SearchCore,
+    query: &str,
+    pages: searcher::PageRange,
+    client: &dyn LlmClient,
+    llm_config: &llm_service::LlmConfig,
+    retry_strategy: app_state::BatchRetryStrategy,
+    failure: &app_state::AnalysisFailureConfig,
+    concurrency: usize,
+    prepare_batch: impl Fn(searcher::SearchBatch) -> searcher::SearchBatch,
+    on_analyzed: impl Fn(&searcher::SearchBatch, &mut [llm_service::DetailedAnalysisResult]),
+) -> Result<SearchAndAnalyzeOutput, String> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<searcher::SearchBatch>(16);
+    let mut analyzed_keys = std::collections::HashSet::new();
+
+    let analyze = stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|batch| (batch, rx)) })
+        .map(|batch| {
+            let mut batch = prepare_batch(batch);
+            // 最终会被过滤掉的结果不必分析
+            batch.results = search_core.filter_results(batch.results);
+            let pending: Vec<searcher::SearchResult> = batch
+                .results
+                .iter()
+                .filter(|r| analyzed_keys.insert(filter::dedupe_key(&r.magnet_link)))
+                .cloned()
+                .collect();
+            async move {
+                let analyses = analyze_results_in_batches(client, &pending, llm_config, retry_strategy, failure).await;
+                (batch, analyses)
             }
-        }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .map(|(batch, mut analyses)| {
+            if let Ok(analyses) = &mut analyses {
+                on_analyzed(&batch, analyses);
+            }
+            (batch, analyses)
+        })
+        .collect::<Vec<_>>();
+
+    let (outcome, analyzed) = tokio::join!(search_core.search_stream(query, pages, tx), analyze);
+    outcome.map_err(|e| e.to_string())?;
+
+    let mut batches = Vec::new();
+    let mut analyses = Vec::new();
+    for (batch, batch_analyses) in analyzed {
+        analyses.extend(batch_analyses?);
+        batches.push(batch);
     }
 
-    println!("🎉 Frontend batch analysis completed: {} results processed", all_results.len());
-    Ok(all_results)
+    let results = search_core.finalize_results(searcher::merge_batches(batches));
+    let kept: std::collections::HashSet<String> = results.iter().map(|r| filter::dedupe_key(&r.magnet_link)).collect();
+    analyses.retain(|analysis| kept.contains(&filter::dedupe_key(&analysis.magnet_link)));
+    Ok(SearchAndAnalyzeOutput { results, analyses })
+}
+
+/// 搜索并分析：提取与分析同时进行，每批分析完成后通过 `search-analysis-batch` 事件推送给前端
+///
+/// `concurrency` 为同时分析的结果批次数，默认与分析阶段的并发上限相同。
+#[tauri::command]
+async fn search_and_analyze(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    keyword: String,
+    pages: Option<u32>,
+    concurrency: Option<u32>,
+    hide_favorited: Option<bool>,
+) -> Result<SearchAndAnalyzeOutput, i18n::AppError> {
+    ensure_keywords_allowed(&state, [keyword.as_str()])?;
+    let Some(search_core) = build_search_core(&state, EngineScope::All)? else {
+        return Ok(SearchAndAnalyzeOutput { results: Vec::new(), analyses: Vec::new() });
+    };
+    searcher::reset_last_search_metrics();
+    let pages = page_range_for(&state, pages, None, None);
+
+    let config = app_state::get_llm_config(&state);
+    let retry_strategy = config.analysis_config.batch_retry_strategy;
+    let failure = config.analysis_config.failure;
+    let llm_config = llm_service::LlmConfig {
+        provider: config.analysis_config.provider,
+        api_key: config.analysis_config.api_key,
+        api_base: config.analysis_config.api_base,
+        model: config.analysis_config.model,
+        batch_size: config.analysis_config.batch_size,
+        single_item_timeout_secs: config.analysis_config.single_item_timeout_secs,
+        max_concurrency: config.analysis_config.max_concurrency,
+        max_html_chars: None,
+    };
+    let concurrency = concurrency.or(llm_config.max_concurrency).unwrap_or(1) as usize;
+    println!("🔧 Search and analyze '{}': up to {} batches analyzed concurrently", keyword, concurrency.max(1));
+
+    let weights = app_state::get_search_settings(&state).quality_weights;
+    let client = llm_service::GeminiClient::new();
+    let output = pipeline_search_and_analysis(
+        &search_core,
+        &keyword,
+        pages,
+        &client,
+        &llm_config,
+        retry_strategy,
+        &failure,
+        concurrency,
+        |mut batch| {
+            batch.results = hide_favorited_results(&state, batch.results, hide_favorited);
+            batch
+        },
+        |batch, analyses| {
+            apply_analysis_quality_scores(&batch.results, analyses, &weights);
+            if let Err(e) = app_handle.emit("search-analysis-batch", &*analyses) {
+                println!("⚠️ Failed to emit analysis batch: {e}");
+            }
+        },
+    )
+    .await?;
+    track_engine_health(&app_handle, &state, &search_core.metrics());
+    Ok(output)
+}
+
+#[tauri::command]
+async fn estimate_analysis_cost(
+    state: tauri::State<'_, app_state::AppState>,
+    result_count: usize,
+    avg_title_len: usize,
+    model: String,
+) -> Result<llm_service::CostEstimate, String> {
+    let config = app_state::get_llm_config(&state);
+    llm_service::estimate_analysis_cost(
+        result_count,
+        avg_title_len,
+        &model,
+        config.analysis_config.batch_size,
+        &config.price_table,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_analysis_cache_stats() -> Result<llm_service::AnalysisCacheStats, String> {
+    Ok(llm_service::analysis_cache_stats())
+}
+
+#[tauri::command]
+async fn clear_analysis_cache() -> Result<(), String> {
+    llm_service::clear_analysis_cache();
+    Ok(())
 }
 
 #[tauri::command]
@@ -597,16 +1836,19 @@ async fn update_llm_config(
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, app_state::AppState>,
     config: app_state::LlmConfig,
-) -> Result<(), String> {
+) -> Result<Vec<String>, String> {
     println!("🔧 Updating LLM config: extraction_batch_size={}, analysis_batch_size={}", config.extraction_config.batch_size, config.analysis_config.batch_size);
 
-    app_state::update_llm_config(&state, config).map_err(|e| e.to_string())?;
+    let warnings = app_state::update_llm_config(&state, config).map_err(|e| e.to_string())?;
+    for warning in &warnings {
+        println!("⚠️ {warning}");
+    }
 
     // 保存状态到文件
     app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
 
     println!("🔧 LLM config saved.");
-    Ok(())
+    Ok(warnings)
 }
 
 // ============ 搜索设置相关命令 ============
@@ -622,6 +1864,7 @@ async fn update_search_settings(
     state: tauri::State<'_, app_state::AppState>,
     settings: app_state::SearchSettings,
 ) -> Result<(), String> {
+    apply_network_settings(&settings);
     app_state::update_search_settings(&state, settings).map_err(|e| e.to_string())?;
 
     // 保存状态到文件
@@ -630,8 +1873,40 @@ async fn update_search_settings(
     Ok(())
 }
 
+#[tauri::command]
+async fn reset_settings_section(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+    section: app_state::SettingsSection,
+) -> Result<(), String> {
+    app_state::reset_settings_section(&state, section);
+    if section == app_state::SettingsSection::Search {
+        apply_network_settings(&app_state::get_search_settings(&state));
+    }
+
+    // 保存状态到文件
+    app_state::save_app_state(&app_handle, &state).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 立即持久化所有尚未写入文件的状态
+#[tauri::command]
+async fn flush_state(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, app_state::AppState>,
+) -> Result<(), String> {
+    app_state::flush_app_state(&app_handle, &state).map_err(|e| e.to_string())
+}
+
 // ============ 下载配置相关命令 ============
 
+/// 获取合并默认值与覆盖后实际生效的配置，密钥已打码，便于排查问题
+#[tauri::command]
+async fn get_effective_config(state: tauri::State<'_, app_state::AppState>) -> Result<app_state::EffectiveConfig, String> {
+    Ok(app_state::get_effective_config(&state))
+}
+
 #[tauri::command]
 async fn get_download_config(state: tauri::State<'_, app_state::AppState>) -> Result<app_state::DownloadConfig, String> {
     Ok(app_state::get_download_config(&state))
@@ -651,12 +1926,52 @@ async fn update_download_config(
     Ok(())
 }
 
+/// 将结果导出为 Markdown 表格文本，由前端复制到剪贴板
+#[tauri::command]
+async fn export_results_as_markdown(
+    state: tauri::State<'_, app_state::AppState>,
+    mut results: Vec<searcher::SearchResult>,
+) -> Result<String, String> {
+    let extra_trackers = app_state::get_download_config(&state).extra_trackers;
+    for result in &mut results {
+        result.magnet_link = searcher::append_trackers(&result.magnet_link, &extra_trackers);
+    }
+    Ok(filter::export_results_as_markdown(&results))
+}
+
+/// 规范化磁力链接，便于比较不同来源的同一资源
+#[tauri::command]
+async fn canonicalize_magnet(magnet: String) -> Result<String, String> {
+    Ok(searcher::canonicalize_magnet(&magnet))
+}
+
+/// 校验磁力链接后交给 `open` 打开，没有有效 infohash 的链接在打开前即被拒绝
+fn open_validated_magnet(magnet: &str, open: impl FnOnce(&str) -> Result<(), String>) -> Result<(), String> {
+    let magnet = magnet.trim();
+    if !searcher::is_btih_magnet(magnet) || searcher::extract_infohash(magnet).is_none() {
+        println!("🚫 Refusing to open malformed magnet link: {magnet}");
+        return Err(i18n::translate_error(&i18n::ErrorCode::MagnetInvalid));
+    }
+    open(magnet)
+}
+
+/// 使用系统默认的种子客户端打开磁力链接
+#[tauri::command]
+async fn open_magnet(state: tauri::State<'_, app_state::AppState>, magnet: String) -> Result<(), String> {
+    let magnet = searcher::append_trackers(&magnet, &app_state::get_download_config(&state).extra_trackers);
+    open_validated_magnet(&magnet, |magnet| {
+        tauri_plugin_opener::open_url(magnet, None::<&str>)
+            .map_err(|_| "No application is configured to handle magnet links. Please configure an application path in settings.".to_string())
+    })
+}
+
 #[tauri::command]
 async fn open_magnet_link(
     state: tauri::State<'_, app_state::AppState>,
     magnet_link: String,
 ) -> Result<(), String> {
     let config = app_state::get_download_config(&state);
+    let magnet_link = searcher::append_trackers(&magnet_link, &config.extra_trackers);
 
     if let Some(ref app_path) = config.custom_app_path {
         // 检查是否是115浏览器
@@ -918,26 +2233,79 @@ fn main() {
             // 初始化应用状态
             let app_state = app_state::init_app_state(app.handle())
                 .expect("Failed to initialize app state");
+            apply_network_settings(&app_state::get_search_settings(&app_state));
             app.manage(app_state);
+            // 合并频繁的保存请求，避免批量操作时反复写文件
+            app.manage(app_state::DebouncedSaver::for_app(
+                app.handle().clone(),
+                app_state::DEFAULT_SAVE_DEBOUNCE,
+            ));
+            app.manage(searcher::ResultPager::default());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             search_multi_page,
+            get_next_result_page,
             search_clmclm_first,
             search_other_engines,
+            search_multi_page_stream,
+            quick_search,
+            search_and_merge,
+            search_multi_keyword,
+            search_by_infohash,
+            get_provider_capabilities,
+            parse_html_with_provider,
+            get_last_search_metrics,
+            get_clmclm_categories,
+            refresh_clmclm_categories,
             test_connection,
             test_extraction_connection,
+            test_extraction_with_sample,
             test_analysis_connection,
             analyze_resource,
             batch_analyze_resources,
+            sort_search_results,
+            batch_analyze_resources_streaming,
+            search_and_analyze,
             // 收藏夹命令
             add_to_favorites,
+            add_many_to_favorites,
             get_all_favorites,
             remove_from_favorites,
             search_favorites,
+            mark_favorite_downloaded,
+            mark_favorite_link_dead,
+            get_favorites_by_downloaded,
+            dedupe_favorites,
+            remove_favorites_matching,
+            get_preference_weights,
+            reset_preference_weights,
+            get_auto_tag_rules,
+            update_auto_tag_rules,
+            get_all_favorite_folders,
+            create_favorite_folder,
+            rename_favorite_folder,
+            move_favorite_folder,
+            delete_favorite_folder,
+            move_favorite_to_folder,
             // 搜索引擎命令
             add_search_engine,
             update_search_engine,
+            update_engine_url_codes,
+            update_engine_selectors,
+            update_engine_use_ai,
+            update_engine_generic_selectors,
+            update_engine_headers,
+            update_engine_json_api,
+            update_engine_torznab,
+            update_engine_auth,
+            update_engine_parse_order,
+            reenable_engine,
+            self_test_engines,
+            compare_engines,
+            estimate_extraction_tokens,
+            get_engine_statistics,
+            reset_engine_statistics,
             get_all_engines,
             update_engine_status,
             delete_engine,
@@ -948,13 +2316,22 @@ fn main() {
             // LLM 配置命令
             get_llm_config,
             update_llm_config,
+            estimate_analysis_cost,
+            get_analysis_cache_stats,
+            clear_analysis_cache,
+            flush_state,
             // 搜索设置命令
             get_search_settings,
+            get_effective_config,
             update_search_settings,
+            reset_settings_section,
             // 下载配置命令
             get_download_config,
             update_download_config,
             open_magnet_link,
+            open_magnet,
+            canonicalize_magnet,
+            export_results_as_markdown,
             browse_for_file,
             // 国际化命令
             i18n::get_system_locale,
@@ -966,6 +2343,520 @@ fn main() {
             get_app_locale,
             set_app_locale_with_persistence
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                let state = app_handle.state::<app_state::AppState>();
+                shutdown(searcher::cancel_all_searches, || app_state::flush_app_state(app_handle, &state));
+            }
+        });
+}
+
+/// 应用退出前的清理：先取消进行中的搜索，避免其在退出过程中继续修改状态，
+/// 再写入防抖中尚未落盘的状态
+fn shutdown(cancel_searches: impl FnOnce(), flush_state: impl FnOnce() -> anyhow::Result<()>) {
+    cancel_searches();
+    if let Err(e) = flush_state() {
+        eprintln!("❌ Failed to flush app state on exit: {e}");
+    }
+}
+
+#[cfg(test)]
+mod test_support;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{llm_config, MockLlmClient};
+
+    /// 任何包含 "poison" 标题的批次都会失败、其余标题转为大写的模拟 LLM 客户端
+    fn poison_llm_client() -> MockLlmClient {
+        MockLlmClient::new().with_analyze(|items| {
+            if items.iter().any(|item| item.title.contains("poison")) {
+                return Err(anyhow::anyhow!("malformed response"));
+            }
+            Ok(items
+                .iter()
+                .map(|item| llm_service::BatchAnalysisResult {
+                    cleaned_title: item.title.to_uppercase(),
+                    purity_score: 90,
+                    tags: Vec::new(),
+                })
+                .collect())
+        })
+    }
+
+    fn search_result(title: &str) -> searcher::SearchResult {
+        searcher::SearchResult {
+            title: title.to_string(),
+            magnet_link: format!("magnet:?xt=urn:btih:{title}"),
+            file_list: vec![format!("{title}.mkv")],
+            ..Default::default()
+        }
+    }
+
+    fn analysis_config(batch_size: u32) -> llm_service::LlmConfig {
+        llm_service::LlmConfig { batch_size, ..llm_config() }
+    }
+
+    /// 记录同时进行中的分析批次峰值、原样返回标题的模拟 LLM 客户端
+    fn peak_llm_client() -> MockLlmClient {
+        MockLlmClient::echo_analysis(80).with_delay(std::time::Duration::from_millis(20))
+    }
+
+    #[test]
+    fn test_analysis_purity_feeds_quality_score() {
+        let result = |hash: &str| searcher::SearchResult {
+            magnet_link: format!("magnet:?xt=urn:btih:{hash}"),
+            file_size: Some("2 GB".to_string()),
+            seeders: Some(100),
+            ..search_result(hash)
+        };
+        let results = vec![result("clean"), result("junk")];
+        let analysis = |original: &searcher::SearchResult, purity| {
+            create_analysis_result(original, None, Some(purity), Vec::new(), None, &filter::default_title_cleaning_steps())
+        };
+        let mut analyses = vec![analysis(&results[0], 100), analysis(&results[1], 0)];
+
+        apply_analysis_quality_scores(&results, &mut analyses, &filter::QualityWeights::default());
+        let (clean, junk) = (analyses[0].quality_score.unwrap(), analyses[1].quality_score.unwrap());
+        assert!(clean > junk, "purity should raise quality: {clean} vs {junk}");
+
+        // 纯净度权重为 0 时两者相同
+        let weights = filter::QualityWeights { purity: 0.0, ..Default::default() };
+        apply_analysis_quality_scores(&results, &mut analyses, &weights);
+        assert_eq!(analyses[0].quality_score, analyses[1].quality_score);
+    }
+
+    #[test]
+    fn test_hide_favorited_excludes_saved_results() {
+        let state: app_state::AppState = std::sync::Mutex::new(app_state::AppData::default());
+        let saved = "1111111111111111111111111111111111111111";
+        let result = |hash: &str| searcher::SearchResult {
+            magnet_link: format!("magnet:?xt=urn:btih:{hash}&dn=Result"),
+            ..search_result(hash)
+        };
+        // 收藏时保存的是规范化后的链接，仍按 infohash 识别
+        app_state::add_to_favorites(&state, "Saved".to_string(), format!("magnet:?xt=urn:btih:{}", saved.to_uppercase()), None, Vec::new())
+            .unwrap();
+        let results = vec![result(saved), result("2222222222222222222222222222222222222222")];
+
+        let hidden = hide_favorited_results(&state, results.clone(), Some(true));
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0].title, "2222222222222222222222222222222222222222");
+
+        assert_eq!(hide_favorited_results(&state, results.clone(), Some(false)).len(), 2);
+        assert_eq!(hide_favorited_results(&state, results, None).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_analysis_respects_max_concurrency() {
+        let results: Vec<_> = (0..6).map(|i| search_result(&format!("item{i}"))).collect();
+        let failure = app_state::AnalysisFailureConfig::default();
+
+        for (max_concurrency, expected_peak) in [(Some(1), 1), (Some(2), 2), (Some(0), 1), (None, 1)] {
+            let client = peak_llm_client();
+            let config = llm_service::LlmConfig { max_concurrency, ..analysis_config(1) };
+            let analyzed = analyze_results_in_batches(&client, &results, &config, app_state::BatchRetryStrategy::PerItem, &failure)
+                .await
+                .unwrap();
+
+            assert_eq!(client.peak(), expected_peak);
+            // 并发执行时结果仍保持原有顺序
+            let titles: Vec<_> = analyzed.iter().map(|r| r.title.as_str()).collect();
+            assert_eq!(titles, ["item0", "item1", "item2", "item3", "item4", "item5"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_analysis_respects_max_concurrency() {
+        let entries: Vec<_> = (0..6)
+            .map(|i| (i * 2, llm_service::BatchAnalysisItem { title: format!("item{i}"), file_list: vec![format!("item{i}.mkv")] }))
+            .collect();
+
+        for (max_concurrency, expected_peak) in [(None, 1), (Some(3), 3)] {
+            let client = peak_llm_client();
+            let config = llm_service::LlmConfig { max_concurrency, ..analysis_config(1) };
+            let streamed = std::sync::Mutex::new(Vec::new());
+            stream_analysis_in_batches(&client, &entries, &config, |item| streamed.lock().unwrap().push(item.index))
+                .await
+                .unwrap();
+
+            assert_eq!(client.peak(), expected_peak);
+            // 推送的位置换算为原列表中的位置
+            let mut indexes = streamed.into_inner().unwrap();
+            indexes.sort_unstable();
+            assert_eq!(indexes, [0, 2, 4, 6, 8, 10]);
+        }
+    }
+
+    /// 收到 `release` 通知后才响应的本地 HTTP 服务，返回其地址；用于控制提供商返回的先后顺序
+    async fn gated_server(body: &'static str, release: std::sync::Arc<tokio::sync::Notify>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = socket.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            release.notified().await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_search_and_analyze_starts_analysis_before_slow_provider_finishes() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/fast/1");
+            then.status(200)
+                .body(r#"<a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">Fast Movie 2023</a>"#);
+        });
+        // 慢速提供商要等第一批分析完成后才会响应
+        let release = std::sync::Arc::new(tokio::sync::Notify::new());
+        let slow_base = gated_server(
+            r#"<a href="magnet:?xt=urn:btih:2222222222222222222222222222222222222222">Slow Movie 2023</a>"#,
+            std::sync::Arc::clone(&release),
+        )
+        .await;
+        let engine = |name: &str, base: &str| {
+            searcher::CustomEngineConfig::new(name.to_string(), format!("{base}/{name}/{{page}}?q={{keyword}}")).with_use_ai(false)
+        };
+        let search_core = searcher::create_ai_enhanced_search_core(
+            None,
+            None,
+            Vec::new(),
+            vec![engine("fast", &server.base_url()), engine("slow", &slow_base)],
+            None,
+            Default::default(),
+        );
+
+        let (client, config, failure) = (peak_llm_client(), analysis_config(5), app_state::AnalysisFailureConfig::default());
+        let analyzed_order = std::sync::Mutex::new(Vec::new());
+        let pipeline = pipeline_search_and_analysis(
+            &search_core,
+            "movie",
+            1.into(),
+            &client,
+            &config,
+            app_state::BatchRetryStrategy::PerItem,
+            &failure,
+            2,
+            |batch| batch,
+            |batch, _| {
+                analyzed_order.lock().unwrap().push(batch.provider_name.clone());
+                release.notify_one();
+            },
+        );
+        // 只用于防止挂起：如果分析要等全部提供商返回才开始，慢速提供商永远不会被放行
+        let output = tokio::time::timeout(std::time::Duration::from_secs(10), pipeline)
+            .await
+            .expect("analysis did not start before the slow provider finished")
+            .unwrap();
+
+        assert_eq!(analyzed_order.into_inner().unwrap(), ["fast", "slow"]);
+        assert_eq!(output.results.len(), 2);
+        assert_eq!(output.analyses.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_and_analyze_skips_filtered_and_duplicate_results() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        for page in [1, 2] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/s/{page}"));
+                then.status(200).body(
+                    r#"<a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">Movie 2023</a>
+                       <a href="magnet:?xt=urn:btih:not-a-hash">Broken Movie 2023</a>"#,
+                );
+            });
+        }
+        let engine = searcher::CustomEngineConfig::new("site".to_string(), format!("{}/s/{{page}}?q={{keyword}}", server.base_url()))
+            .with_use_ai(false);
+        let search_core = searcher::create_ai_enhanced_search_core(None, None, Vec::new(), vec![engine], None, Default::default())
+            .with_strict_magnet_validation(true);
+
+        let analyzed = AtomicUsize::new(0);
+        let output = pipeline_search_and_analysis(
+            &search_core,
+            "movie",
+            searcher::PageRange::new(1, 2),
+            &peak_llm_client(),
+            &analysis_config(5),
+            app_state::BatchRetryStrategy::PerItem,
+            &app_state::AnalysisFailureConfig::default(),
+            1,
+            |batch| batch,
+            |_, analyses| {
+                analyzed.fetch_add(analyses.len(), Ordering::SeqCst);
+            },
+        )
+        .await
+        .unwrap();
+
+        // 无效的磁力链接不分析，第二页重复的资源也不再分析
+        assert_eq!(analyzed.load(Ordering::SeqCst), 1);
+        assert_eq!(output.results.len(), 1);
+        assert_eq!(output.analyses.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_binary_split_isolates_poison_item_with_fewer_calls() {
+        let results: Vec<_> = (0..8)
+            .map(|i| search_result(if i == 5 { "poison" } else { "item" }))
+            .collect();
+        let config = analysis_config(8);
+
+        let per_item_client = poison_llm_client();
+        let per_item = analyze_results_in_batches(&per_item_client, &results, &config, app_state::BatchRetryStrategy::PerItem, &app_state::AnalysisFailureConfig::default())
+            .await
+            .unwrap();
+
+        let split_client = poison_llm_client();
+        let split = analyze_results_in_batches(&split_client, &results, &config, app_state::BatchRetryStrategy::BinarySplit, &app_state::AnalysisFailureConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(per_item.len(), 8);
+        assert_eq!(split.len(), 8);
+        assert!(split_client.calls() < per_item_client.calls());
+
+        // 只有出错的项目标记为失败，并且顺序保持不变
+        let failed: Vec<usize> = split.iter().enumerate().filter(|(_, r)| r.error.is_some()).map(|(i, _)| i).collect();
+        assert_eq!(failed, vec![5]);
+        assert_eq!(split[0].title, "ITEM");
+    }
+
+    #[tokio::test]
+    async fn test_zero_batch_size_chunks_one_item_per_batch() {
+        let results: Vec<_> = (0..3).map(|_| search_result("item")).collect();
+        let client = poison_llm_client();
+
+        let analyzed = analyze_results_in_batches(&client, &results, &analysis_config(0), app_state::BatchRetryStrategy::PerItem, &app_state::AnalysisFailureConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(analyzed.len(), 3);
+        assert_eq!(client.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_items_use_configured_failure_score_and_tag() {
+        let results = vec![search_result("item"), search_result("poison")];
+        let client = poison_llm_client();
+
+        let sentinel = analyze_results_in_batches(&client, &results, &analysis_config(2), app_state::BatchRetryStrategy::PerItem, &app_state::AnalysisFailureConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(sentinel[0].purity_score, Some(90));
+        assert_eq!(sentinel[1].purity_score, None);
+        assert!(sentinel[1].error.is_some());
+
+        let failure = app_state::AnalysisFailureConfig {
+            score: Some(0),
+            tag: Some("未分析".to_string()),
+            ..Default::default()
+        };
+        let configured = analyze_results_in_batches(&client, &results, &analysis_config(2), app_state::BatchRetryStrategy::BinarySplit, &failure)
+            .await
+            .unwrap();
+        assert_eq!(configured[1].purity_score, Some(0));
+        assert_eq!(configured[1].tags, vec!["未分析".to_string()]);
+    }
+
+    fn state_with_engines_disabled() -> app_state::AppState {
+        let mut data = app_state::AppData::default();
+        data.search_engines.push(app_state::SearchEngine {
+            id: "custom".to_string(),
+            name: "example".to_string(),
+            url_template: "https://example.com/search?q={keyword}&p={page}".to_string(),
+            is_enabled: false,
+            is_deletable: true,
+            category_code: None,
+            sort_code: None,
+            selectors: None,
+            use_ai: true,
+            generic_selectors: None,
+            headers: Default::default(),
+            consecutive_failures: 0,
+            json_api: None,
+            torznab: None,
+            parse_order: searcher::default_parse_order(),
+            auth: None,
+        });
+        for engine in &mut data.search_engines {
+            engine.is_enabled = false;
+        }
+        std::sync::Mutex::new(data)
+    }
+
+    #[tokio::test]
+    async fn test_search_commands_consistent_without_enabled_engines() {
+        let state = state_with_engines_disabled();
+        let expected = i18n::AppError::Config(i18n::ErrorCode::SearchNoEngines);
+
+        for scope in [EngineScope::All, EngineScope::ClmclmOnly, EngineScope::OthersOnly] {
+            let result = run_scoped_search(&state, "test", searcher::PageRange::from(1), scope).await;
+            assert_eq!(result.unwrap_err(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scoped_search_without_engines_in_scope_returns_empty() {
+        let state = state_with_engines_disabled();
+        // 只启用自定义引擎，此时 clmclm 范围的搜索应返回空结果而不是错误
+        state.lock().unwrap().search_engines[1].is_enabled = true;
+
+        let result = run_scoped_search(&state, "test", searcher::PageRange::from(1), EngineScope::ClmclmOnly).await;
+        let (results, metrics) = result.unwrap();
+        assert!(results.is_empty());
+        assert!(metrics.is_empty());
+    }
+
+    #[test]
+    fn test_build_search_core_includes_providers_in_scope() {
+        let state = state_with_engines_disabled();
+        for engine in &mut state.lock().unwrap().search_engines {
+            engine.is_enabled = true;
+        }
+
+        let providers = |scope| build_search_core(&state, scope).unwrap().unwrap().provider_names();
+        assert_eq!(providers(EngineScope::All), vec!["clmclm.com", "example"]);
+        assert_eq!(providers(EngineScope::ClmclmOnly), vec!["clmclm.com"]);
+        assert_eq!(providers(EngineScope::OthersOnly), vec!["example"]);
+    }
+
+    #[test]
+    fn test_resolve_page_range_clamps_to_limit() {
+        let range = resolve_page_range(Some(500), None, None, 20);
+        assert_eq!((range.start, range.end), (1, 20));
+
+        // 显式的起止页同样受上限约束
+        let range = resolve_page_range(None, Some(5), Some(100), 20);
+        assert_eq!((range.start, range.end), (5, 24));
+
+        let range = resolve_page_range(Some(3), None, None, 20);
+        assert_eq!((range.start, range.end), (1, 3));
+    }
+
+    #[test]
+    fn test_resolve_page_range_treats_zero_as_one() {
+        let range = resolve_page_range(Some(0), None, None, 20);
+        assert_eq!((range.start, range.end), (1, 1));
+
+        let range = resolve_page_range(Some(5), None, None, 0);
+        assert_eq!((range.start, range.end), (1, 1));
+    }
+
+    #[test]
+    fn test_analysis_result_keeps_title_before_cleaning() {
+        let original = search_result("[y5y4.com] Movie 2023 1080p");
+        let result = create_analysis_result(&original, None, Some(80), Vec::new(), None, &filter::default_title_cleaning_steps());
+        assert_eq!(result.title, "Movie 2023 1080p");
+        assert_eq!(result.original_title, "[y5y4.com] Movie 2023 1080p");
+    }
+
+    #[test]
+    fn test_shutdown_cancels_searches_then_flushes_state() {
+        let steps = std::cell::RefCell::new(Vec::new());
+        shutdown(
+            || steps.borrow_mut().push("cancel"),
+            || {
+                steps.borrow_mut().push("flush");
+                Ok(())
+            },
+        );
+        assert_eq!(steps.into_inner(), vec!["cancel", "flush"]);
+
+        // 写入失败只记录日志，不影响退出
+        let flushed = std::cell::Cell::new(false);
+        shutdown(|| {}, || {
+            flushed.set(true);
+            Err(anyhow::anyhow!("disk full"))
+        });
+        assert!(flushed.get());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_single_item_timeout_follows_config() {
+        let result = search_result("slow");
+        let entry: AnalysisEntry<'_> = (
+            &result,
+            llm_service::BatchAnalysisItem { title: result.title.clone(), file_list: result.file_list.clone() },
+        );
+        let client = poison_llm_client().with_delay(std::time::Duration::from_millis(1500));
+        let failure = app_state::AnalysisFailureConfig::default();
+
+        let mut config = analysis_config(1);
+        config.single_item_timeout_secs = 1;
+        let started = tokio::time::Instant::now();
+        let timed_out = analyze_single_entry(&client, &entry, &config, &failure).await;
+        let elapsed = started.elapsed();
+        assert!(timed_out.error.as_deref().is_some_and(|e| e.contains("1 seconds")));
+        assert_eq!(elapsed, std::time::Duration::from_secs(1));
+
+        // 时限为 0 时等待模型返回
+        config.single_item_timeout_secs = 0;
+        let completed = analyze_single_entry(&client, &entry, &config, &failure).await;
+        assert!(completed.error.is_none());
+        assert_eq!(completed.title, "SLOW");
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_keyword_rejected_before_search() {
+        let state = state_with_engines_disabled();
+        state.lock().unwrap().search_engines[1].is_enabled = true;
+        state.lock().unwrap().search_settings.forbidden_keywords = vec!["Secret".to_string()];
+
+        let blocked = run_scoped_search(&state, "top SECRET files", searcher::PageRange::from(1), EngineScope::ClmclmOnly).await;
+        let expected = i18n::AppError::Other(i18n::translate_error(&i18n::ErrorCode::SearchForbiddenKeyword("Secret".to_string())));
+        assert_eq!(blocked.unwrap_err(), expected);
+
+        // 按完整单词匹配时，只作为单词一部分出现的关键词不会被拦截
+        state.lock().unwrap().search_settings.forbidden_keywords_whole_word = true;
+        let allowed = run_scoped_search(&state, "secretary", searcher::PageRange::from(1), EngineScope::ClmclmOnly).await;
+        assert!(allowed.unwrap().0.is_empty());
+        assert!(ensure_keywords_allowed(&state, ["open movie"]).is_ok());
+        assert!(ensure_keywords_allowed(&state, ["movie", "the secret"]).is_err());
+    }
+
+    #[test]
+    fn test_malformed_magnet_rejected_before_open() {
+        for magnet in ["", "https://example.com/file.torrent", "magnet:?dn=no-hash", "magnet:?xt=urn:btih:nothex"] {
+            let mut opened = false;
+            let result = open_validated_magnet(magnet, |_| {
+                opened = true;
+                Ok(())
+            });
+            assert!(result.is_err(), "{magnet} should be rejected");
+            assert!(!opened, "{magnet} must not reach the opener");
+        }
+
+        let mut opened = None;
+        let valid = "magnet:?dn=x&xt=urn:btih:ABCDEF0123456789ABCDEF0123456789ABCDEF01";
+        open_validated_magnet(&format!(" {valid} "), |magnet| {
+            opened = Some(magnet.to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(opened.as_deref(), Some(valid));
+    }
 }
@@ -0,0 +1,348 @@
+// src-tauri/src/net.rs
+// 网络请求相关的公共配置：DNS-over-HTTPS 解析、IPv6 优先与响应大小限制
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 默认的响应体大小上限（10MB）
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 网络选项，默认保持系统行为
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkOptions {
+    /// 解析结果中优先使用 IPv6 地址
+    pub prefer_ipv6: bool,
+    /// DoH 解析地址（JSON 格式，如 `https://cloudflare-dns.com/dns-query`）
+    pub doh_resolver: Option<String>,
+    /// 读取搜索页面响应体的最大字节数，超出后中止读取
+    pub max_response_bytes: u64,
+    /// 所有搜索请求附带的请求头，可被单个引擎的请求头覆盖
+    pub default_headers: BTreeMap<String, String>,
+}
+
+/// 默认的全局请求头：请求不追踪，并只声明精简的语言偏好
+pub fn privacy_default_headers() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("DNT".to_string(), "1".to_string()),
+        ("Accept-Language".to_string(), "en".to_string()),
+    ])
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            prefer_ipv6: false,
+            doh_resolver: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            default_headers: privacy_default_headers(),
+        }
+    }
+}
+
+/// 当前生效的网络选项，由设置加载和更新时写入
+static NETWORK_OPTIONS: Lazy<RwLock<NetworkOptions>> = Lazy::new(|| RwLock::new(NetworkOptions::default()));
+
+/// 更新全局网络选项，之后创建的 HTTP 客户端生效
+pub fn set_network_options(options: NetworkOptions) {
+    *NETWORK_OPTIONS.write().unwrap() = options;
+}
+
+/// 按全局网络选项创建 HTTP 客户端构建器
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let options = NETWORK_OPTIONS.read().unwrap().clone();
+    client_builder_with(&options)
+}
+
+/// 按指定网络选项创建 HTTP 客户端构建器；未启用任何选项时使用系统解析
+pub fn client_builder_with(options: &NetworkOptions) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    let doh_url = options.doh_resolver.as_deref().map(str::trim).filter(|url| !url.is_empty());
+
+    if doh_url.is_none() && !options.prefer_ipv6 {
+        return builder;
+    }
+
+    builder.dns_resolver(Arc::new(NetworkResolver {
+        doh_url: doh_url.map(str::to_string),
+        prefer_ipv6: options.prefer_ipv6,
+        // 访问 DoH 服务本身使用系统解析
+        client: reqwest::Client::new(),
+    }))
+}
+
+/// 合并全局请求头与引擎请求头（同名时引擎优先），用于单个请求
+pub fn request_headers(engine_headers: &BTreeMap<String, String>) -> reqwest::header::HeaderMap {
+    let global = NETWORK_OPTIONS.read().unwrap().default_headers.clone();
+    merge_headers(&global, engine_headers)
+}
+
+/// 按顺序写入全局与引擎请求头，后写入的同名请求头覆盖先前的值；无效的名称或值会被忽略
+pub fn merge_headers(global: &BTreeMap<String, String>, engine_headers: &BTreeMap<String, String>) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in global.iter().chain(engine_headers) {
+        match (
+            reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()),
+            reqwest::header::HeaderValue::from_str(value.trim()),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => println!("⚠️ Ignoring invalid request header: {name}: {value}"),
+        }
+    }
+    headers
+}
+
+/// 各提供商的 cookie 存储，按提供商名称区分
+static COOKIE_JARS: Lazy<Mutex<HashMap<String, Arc<reqwest::cookie::Jar>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 获取提供商的 cookie 存储
+///
+/// 同名提供商在应用运行期间共用同一个存储，第 1 页设置的会话 cookie 会在后续页面和之后的搜索中继续发送。
+pub fn cookie_jar(provider_name: &str) -> Arc<reqwest::cookie::Jar> {
+    COOKIE_JARS
+        .lock()
+        .unwrap()
+        .entry(provider_name.to_string())
+        .or_default()
+        .clone()
+}
+
+tokio::task_local! {
+    /// 当前作用域内已读取的响应体字节数，见 `measure_response_bytes`
+    static RESPONSE_BYTES: std::cell::Cell<usize>;
+}
+
+/// 运行 `future` 并统计其中通过 `read_text` 读取的响应体总字节数
+pub async fn measure_response_bytes<F: std::future::Future>(future: F) -> (F::Output, usize) {
+    RESPONSE_BYTES
+        .scope(std::cell::Cell::new(0), async {
+            let output = future.await;
+            (output, RESPONSE_BYTES.with(std::cell::Cell::get))
+        })
+        .await
+}
+
+/// 按全局大小上限读取响应文本
+pub async fn read_text(response: reqwest::Response) -> Result<String> {
+    let max_bytes = NETWORK_OPTIONS.read().unwrap().max_response_bytes;
+    read_text_limited(response, max_bytes).await
+}
+
+/// 分块读取响应体，累计超过 `max_bytes` 时立即返回错误，避免异常页面占满内存。
+/// 文本按 Content-Type 中的 charset 解码，缺省为 UTF-8（与 `Response::text` 一致）
+pub async fn read_text_limited(mut response: reqwest::Response, max_bytes: u64) -> Result<String> {
+    let url = response.url().clone();
+
+    // 服务器声明的长度已超限时无需读取
+    if let Some(length) = response.content_length().filter(|&length| length > max_bytes) {
+        return Err(anyhow!("Response too large from {}: {} bytes exceeds limit of {} bytes", url, length, max_bytes));
+    }
+
+    let encoding = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(charset_from_content_type)
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| anyhow!("Failed to read response: {}", e))? {
+        if (body.len() + chunk.len()) as u64 > max_bytes {
+            return Err(anyhow!("Response too large from {}: exceeds limit of {} bytes", url, max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let _ = RESPONSE_BYTES.try_with(|bytes| bytes.set(bytes.get() + body.len()));
+    let (text, _, _) = encoding.decode(&body);
+    Ok(text.into_owned())
+}
+
+/// 从 Content-Type 中取出 charset 参数
+fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 支持 DoH 和 IPv6 优先的解析器
+struct NetworkResolver {
+    doh_url: Option<String>,
+    prefer_ipv6: bool,
+    client: reqwest::Client,
+}
+
+/// DoH JSON 响应
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    data: String,
+}
+
+impl Resolve for NetworkResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let doh_url = self.doh_url.clone();
+        let prefer_ipv6 = self.prefer_ipv6;
+        let client = self.client.clone();
+
+        Box::pin(async move {
+            let mut addrs: Vec<SocketAddr> = match doh_url {
+                Some(url) => {
+                    let mut addrs = Vec::new();
+                    for record_type in ["A", "AAAA"] {
+                        addrs.extend(query_doh(&client, &url, &host, record_type).await?);
+                    }
+                    addrs
+                }
+                None => tokio::net::lookup_host((host.as_str(), 0)).await?.collect(),
+            };
+
+            if addrs.is_empty() {
+                return Err(format!("No addresses found for {host}").into());
+            }
+
+            // 稳定排序，保持同一地址族内的原有顺序
+            addrs.sort_by_key(|addr| addr.is_ipv6() != prefer_ipv6);
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 通过 DoH 查询一种记录类型，只保留能解析为 IP 的应答（忽略 CNAME 等）
+async fn query_doh(
+    client: &reqwest::Client,
+    url: &str,
+    host: &str,
+    record_type: &str,
+) -> Result<Vec<SocketAddr>, Box<dyn std::error::Error + Send + Sync>> {
+    let response: DohResponse = client
+        .get(url)
+        .query(&[("name", host), ("type", record_type)])
+        .header("accept", "application/dns-json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(response
+        .answer
+        .iter()
+        .filter_map(|answer| answer.data.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_client_resolves_through_doh() {
+        let site = MockServer::start();
+        let page = site.mock(|when, then| {
+            when.method(GET).path("/");
+            then.status(200).body("ok");
+        });
+
+        let doh = MockServer::start();
+        let a_query = doh.mock(|when, then| {
+            when.method(GET).path("/dns-query").query_param("name", "magnet.invalid").query_param("type", "A");
+            then.status(200).json_body(serde_json::json!({ "Answer": [{ "data": "127.0.0.1" }] }));
+        });
+        doh.mock(|when, then| {
+            when.method(GET).path("/dns-query").query_param("type", "AAAA");
+            then.status(200).json_body(serde_json::json!({ "Status": 0 }));
+        });
+
+        let options = NetworkOptions {
+            prefer_ipv6: false,
+            doh_resolver: Some(doh.url("/dns-query")),
+            ..NetworkOptions::default()
+        };
+        let client = client_builder_with(&options).build().unwrap();
+
+        // `.invalid` 域名无法通过系统 DNS 解析，只能经由 DoH 得到地址
+        let body = client
+            .get(format!("http://magnet.invalid:{}/", site.port()))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(body, "ok");
+        a_query.assert();
+        page.assert();
+    }
+
+    #[tokio::test]
+    async fn test_read_text_limited_aborts_oversized_body() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/big");
+            then.status(200).body("x".repeat(64 * 1024));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/gbk");
+            then.status(200)
+                .header("content-type", "text/html; charset=GBK")
+                .body(vec![0xC4, 0xE3, 0xBA, 0xC3]);
+        });
+
+        let client = reqwest::Client::new();
+
+        let response = client.get(server.url("/big")).send().await.unwrap();
+        let error = read_text_limited(response, 1024).await.unwrap_err();
+        assert!(error.to_string().contains("Response too large"));
+
+        let response = client.get(server.url("/big")).send().await.unwrap();
+        assert_eq!(read_text_limited(response, 64 * 1024).await.unwrap().len(), 64 * 1024);
+
+        let response = client.get(server.url("/gbk")).send().await.unwrap();
+        assert_eq!(read_text_limited(response, 1024).await.unwrap(), "你好");
+    }
+
+    #[tokio::test]
+    async fn test_engine_headers_override_global_headers() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/")
+                .header("dnt", "1")
+                .header("accept-language", "zh-CN")
+                .header("x-engine", "on");
+            then.status(200);
+        });
+
+        let engine_headers = BTreeMap::from([
+            ("Accept-Language".to_string(), "zh-CN".to_string()),
+            ("X-Engine".to_string(), "on".to_string()),
+            ("Bad Header".to_string(), "ignored".to_string()),
+        ]);
+        let headers = merge_headers(&privacy_default_headers(), &engine_headers);
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get_all("accept-language").iter().count(), 1);
+
+        reqwest::Client::new().get(server.url("/")).headers(headers).send().await.unwrap();
+        mock.assert();
+    }
+}
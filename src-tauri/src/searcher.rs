@@ -2,28 +2,34 @@ use anyhow::{Result, anyhow};
 // 移除未使用的顶层导入（reqwest 已通过具体路径使用）
 use scraper::{Html, Selector};
 use futures::future::join_all;
-use std::sync::Arc;
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use tokio::sync::mpsc;
 use crate::llm_service::{LlmClient, GeminiClient, LlmConfig};
+use crate::trace::trace_println;
 
 // 统一的日志宏
 macro_rules! search_log {
     (info, $($arg:tt)*) => {
-        println!("🔍 {}", format!($($arg)*))
+        trace_println!("🔍 {}", format!($($arg)*))
     };
     (success, $($arg:tt)*) => {
-        println!("✅ {}", format!($($arg)*))
+        trace_println!("✅ {}", format!($($arg)*))
     };
     (warn, $($arg:tt)*) => {
-        println!("⚠️ {}", format!($($arg)*))
+        trace_println!("⚠️ {}", format!($($arg)*))
     };
     (error, $($arg:tt)*) => {
-        println!("❌ {}", format!($($arg)*))
+        trace_println!("❌ {}", format!($($arg)*))
     };
     (ai, $($arg:tt)*) => {
-        println!("🤖 {}", format!($($arg)*))
+        trace_println!("🤖 {}", format!($($arg)*))
     };
     (stats, $($arg:tt)*) => {
-        println!("📊 {}", format!($($arg)*))
+        trace_println!("📊 {}", format!($($arg)*))
     };
 }
 
@@ -47,6 +53,14 @@ fn safe_truncate(s: &str, max_bytes: usize) -> &str {
     &s[..end]
 }
 
+/// 截取前 `max_chars` 个字符
+fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
 /// 清理HTML标签和实体
 fn clean_html_text(text: &str) -> String {
     // 移除HTML标签
@@ -66,7 +80,220 @@ fn clean_html_text(text: &str) -> String {
     text.trim().replace("  ", " ")
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+/// 从磁力链接中提取 infohash，并统一为小写的40位十六进制形式
+pub fn extract_infohash(magnet_link: &str) -> Option<String> {
+    // 使用 ASCII 小写保证字节偏移与原字符串一致
+    let start = magnet_link.to_ascii_lowercase().find("urn:btih:")? + "urn:btih:".len();
+    let hash: String = magnet_link[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    normalize_infohash(&hash)
+}
+
+/// 解析用户输入的 infohash，也接受完整的磁力链接，返回标准化后的哈希
+pub fn parse_infohash_input(input: &str) -> Option<String> {
+    let input = input.trim();
+    if input.to_ascii_lowercase().starts_with("magnet:") {
+        extract_infohash(input)
+    } else {
+        normalize_infohash(input)
+    }
+}
+
+/// 标准化 infohash：40位十六进制转为小写，32位 base32 解码为十六进制
+pub fn normalize_infohash(hash: &str) -> Option<String> {
+    let hash = hash.trim();
+    if hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(hash.to_ascii_lowercase());
+    }
+    if hash.len() == 32 {
+        return decode_base32_to_hex(hash);
+    }
+    None
+}
+
+/// 取出磁力链接中某个参数的第一个非空值（已 URL 解码）
+fn magnet_param(magnet: &str, key: &str) -> Option<String> {
+    let query = magnet.split_once('?')?.1.replace("&amp;", "&");
+    query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, value)| name.eq_ignore_ascii_case(key) && !value.trim().is_empty())
+        .map(|(_, value)| urlencoding::decode(value).map(|v| v.into_owned()).unwrap_or_else(|_| value.to_string()))
+}
+
+/// 磁力链接中的 `xl` 参数：资源的精确字节数
+pub fn magnet_exact_length(magnet: &str) -> Option<u64> {
+    magnet_param(magnet, "xl")?.trim().parse().ok().filter(|&bytes| bytes > 0)
+}
+
+/// 磁力链接中的 `so` 参数：只下载的文件序号，如 "0,2,4-6"
+pub fn magnet_select_only(magnet: &str) -> Option<String> {
+    let value: String = magnet_param(magnet, "so")?.chars().filter(|c| !c.is_whitespace()).collect();
+    let valid = value.split(',').all(|part| {
+        let mut bounds = part.split('-');
+        let is_index = |n: Option<&str>| n.is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        is_index(bounds.next()) && bounds.next().is_none_or(|end| is_index(Some(end))) && bounds.next().is_none()
+    });
+    valid.then_some(value)
+}
+
+/// 规范化磁力链接：小写十六进制 infohash、规范化的 `dn`、去重并排序的 `tr`
+///
+/// 同一资源来自不同站点时得到完全相同的字符串，其他参数会被丢弃。
+/// 无法解析 infohash 时只去除首尾空白后原样返回。
+pub fn canonicalize_magnet(magnet: &str) -> String {
+    let magnet = magnet.trim();
+    let Some(infohash) = extract_infohash(magnet) else {
+        return magnet.to_string();
+    };
+
+    let decode = |value: &str| {
+        let value = value.replace('+', " ");
+        urlencoding::decode(&value).map(|v| v.into_owned()).unwrap_or(value)
+    };
+
+    let query = magnet.split_once('?').map_or("", |(_, query)| query).replace("&amp;", "&");
+    let mut display_name = None;
+    let mut trackers: Vec<String> = Vec::new();
+    for (key, value) in query.split('&').filter_map(|param| param.split_once('=')) {
+        match key.to_ascii_lowercase().as_str() {
+            "dn" if display_name.is_none() => {
+                let name = decode(value).split_whitespace().collect::<Vec<_>>().join(" ");
+                display_name = Some(name).filter(|name| !name.is_empty());
+            }
+            "tr" => {
+                let tracker = decode(value).trim().to_string();
+                if !tracker.is_empty() && !trackers.contains(&tracker) {
+                    trackers.push(tracker);
+                }
+            }
+            _ => {}
+        }
+    }
+    trackers.sort();
+
+    let mut canonical = format!("magnet:?xt=urn:btih:{infohash}");
+    if let Some(name) = display_name {
+        canonical.push_str(&format!("&dn={}", urlencoding::encode(&name)));
+    }
+    for tracker in trackers {
+        canonical.push_str(&format!("&tr={}", urlencoding::encode(&tracker)));
+    }
+    canonical
+}
+
+/// 在磁力链接末尾追加 `trackers` 中尚未出现的 tracker（`&tr=` 参数）
+///
+/// 与已有 tracker 比较时忽略编码差异与首尾空白；空白项与重复项会被跳过。
+pub fn append_trackers(magnet: &str, trackers: &[String]) -> String {
+    let magnet = magnet.trim();
+    let decode = |value: &str| urlencoding::decode(value).map(|v| v.into_owned()).unwrap_or_else(|_| value.to_string());
+
+    let query = magnet.split_once('?').map_or("", |(_, query)| query).replace("&amp;", "&");
+    let mut present: Vec<String> = query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .filter(|(key, _)| key.eq_ignore_ascii_case("tr"))
+        .map(|(_, value)| decode(value).trim().to_string())
+        .collect();
+
+    let mut augmented = magnet.to_string();
+    for tracker in trackers.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if present.iter().any(|existing| existing == tracker) {
+            continue;
+        }
+        let separator = if augmented.contains('?') { '&' } else { '?' };
+        augmented.push_str(&format!("{separator}tr={}", urlencoding::encode(tracker)));
+        present.push(tracker.to_string());
+    }
+    augmented
+}
+
+/// 将 base32 编码的 infohash 解码为十六进制字符串
+fn decode_base32_to_hex(input: &str) -> Option<String> {
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    let mut hex = String::with_capacity(40);
+
+    for c in input.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            hex.push_str(&format!("{:02x}", (buffer >> bits) & 0xff));
+        }
+    }
+
+    (hex.len() == 40).then_some(hex)
+}
+
+/// 匹配磁力链接，`xt=urn:btih:` 可出现在查询参数中的任意位置（HTML 中的 `&` 可能被转义为 `&amp;`）
+static MAGNET_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r#"magnet:\?(?:[^"'<>\s]*?&(?:amp;)?)?xt=urn:btih:[a-fA-F0-9]{40}[^"'<>\s]*"#)
+        .expect("Invalid magnet regex")
+});
+
+/// 查找文本中的全部磁力链接，并还原被转义的 `&`
+fn find_magnet_links(text: &str) -> impl Iterator<Item = String> + '_ {
+    MAGNET_REGEX.find_iter(text).map(|m| m.as_str().replace("&amp;", "&"))
+}
+
+/// 匹配行首的列表标记，如 "1." "2)" "-" "*"
+static LIST_MARKER_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"^\s*(?:[-*•]|\d+[.)、])\s*").expect("Invalid list marker regex")
+});
+
+/// 从模型返回的非JSON文本中找回磁力链接，标题取同一行中磁力链接前面的文字
+///
+/// 找不到标题时留空，由空标题策略决定从磁力链接推导或丢弃。
+fn salvage_basic_info_from_text(text: &str) -> crate::llm_service::BatchExtractBasicInfoResult {
+    let mut results: Vec<crate::llm_service::ExtractedBasicInfo> = Vec::new();
+
+    for line in text.lines() {
+        let mut title_start = 0;
+        for found in MAGNET_REGEX.find_iter(line) {
+            let magnet_link = found.as_str().replace("&amp;", "&");
+            let prefix = LIST_MARKER_REGEX.replace(&line[title_start..found.start()], "");
+            let title = prefix
+                .trim_matches(|c: char| c.is_whitespace() || "*_`\"'：:-–—|()（）<>[]".contains(c))
+                .to_string();
+            title_start = found.end();
+
+            if results.iter().any(|r| r.magnet_link == magnet_link) {
+                continue;
+            }
+            results.push(crate::llm_service::ExtractedBasicInfo {
+                title,
+                magnet_link,
+                file_size: None,
+                source_url: None,
+                thumbnail_url: None,
+                confidence: crate::llm_service::default_confidence(),
+            });
+        }
+    }
+
+    crate::llm_service::BatchExtractBasicInfoResult { results }
+}
+
+/// 判断是否为带有 `xt=urn:btih:` 参数的磁力链接，不要求 `xt` 是第一个参数
+pub fn is_btih_magnet(link: &str) -> bool {
+    let Some(query) = link.strip_prefix("magnet:?") else {
+        return false;
+    };
+    query
+        .split('&')
+        .any(|param| param.strip_prefix("xt=urn:btih:").is_some_and(|hash| !hash.is_empty()))
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, Clone)]
 pub struct SearchResult {
     pub title: String,
     pub magnet_link: String,
@@ -76,6 +303,90 @@ pub struct SearchResult {
     pub source_url: Option<String>,
     pub score: Option<u8>,
     pub tags: Option<Vec<String>>,
+    /// 标题中搜索关键词与优先关键词出现的位置（字节区间，左闭右开）
+    #[serde(default)]
+    pub highlights: Vec<(usize, usize)>,
+    /// 产生该结果的搜索引擎名称
+    #[serde(default)]
+    pub provider_name: String,
+    /// 去重合并后，所有找到该资源的搜索引擎
+    #[serde(default)]
+    pub found_on: Vec<String>,
+    /// 做种数，提供商无法解析时为 None
+    #[serde(default)]
+    pub seeders: Option<u32>,
+    /// 综合做种数、大小与纯净度的质量分（0-100），见 `filter::compute_quality_score`
+    #[serde(default)]
+    pub quality_score: Option<f32>,
+    /// 多关键词搜索时，命中该结果的全部关键词
+    #[serde(default)]
+    pub matched_keywords: Vec<String>,
+    /// 由 `upload_date` 解析出的上传时间（Unix 秒，UTC），无法解析时为 None
+    #[serde(default)]
+    pub upload_timestamp: Option<i64>,
+    /// 清理前从页面或AI输出中取得的原始标题，`title` 为清理后的版本
+    #[serde(default)]
+    pub original_title: String,
+    /// 磁力链接 `xl` 参数给出的精确字节数
+    #[serde(default)]
+    pub exact_length: Option<u64>,
+    /// 磁力链接 `so` 参数给出的只下载文件序号，如 "0,2,4-6"
+    #[serde(default)]
+    pub select_only: Option<String>,
+    /// AI 提取时给出的可信度（0.0-1.0），非 AI 提取的结果为 None
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// 合并前在各引擎结果页中的先后位置，排序时作为主键相同时的次序依据，保留站点自身的相关度顺序
+    #[serde(default)]
+    pub original_rank: usize,
+    /// 可疑特征（如视频资源中夹带可执行文件），由 `filter::apply_suspicion_flags` 填写
+    #[serde(default)]
+    pub suspicion_flags: Vec<String>,
+    /// 结果行中的海报/缩略图地址（已解析为绝对地址），页面没有图片时为 None
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+impl SearchResult {
+    /// 从磁力链接中读取 `xl` 与 `so` 参数；页面没有提供大小时用 `xl` 补全
+    pub fn apply_magnet_params(&mut self) {
+        if self.exact_length.is_none() {
+            self.exact_length = magnet_exact_length(&self.magnet_link);
+        }
+        if self.select_only.is_none() {
+            self.select_only = magnet_select_only(&self.magnet_link);
+        }
+        let has_size = self.file_size.as_deref().is_some_and(|size| !size.trim().is_empty());
+        if let (false, Some(bytes)) = (has_size, self.exact_length) {
+            self.file_size = Some(crate::filter::format_size(bytes, crate::filter::DEFAULT_SIZE_PRECISION));
+        }
+    }
+}
+
+/// 搜索引擎提供商支持的功能，供前端按需启用相关选项
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProviderCapabilities {
+    /// 是否支持使用 AI 从 HTML 中提取结果
+    pub ai_extraction: bool,
+    /// 是否支持访问详情页补全文件列表
+    pub detail_enrichment: bool,
+    /// 是否能解析做种数
+    pub seeders: bool,
+    /// 是否支持 POST 方式搜索
+    pub post_search: bool,
+    /// 是否支持配置 URL 中的分类与排序代码
+    pub url_codes: bool,
+}
+
+impl ProviderCapabilities {
+    /// 全部不支持，供各提供商的 `CAPABILITIES` 常量补齐未列出的字段
+    pub const NONE: ProviderCapabilities = ProviderCapabilities {
+        ai_extraction: false,
+        detail_enrichment: false,
+        seeders: false,
+        post_search: false,
+        url_codes: false,
+    };
 }
 
 /// 搜索引擎提供商特性
@@ -83,32 +394,407 @@ pub struct SearchResult {
 pub trait SearchProvider: Send + Sync {
     #[allow(dead_code)]
     fn name(&self) -> &str;
-    async fn search(&self, query: &str, page: u32) -> Result<Vec<SearchResult>>;
+
+    /// 该提供商支持的功能（默认全部不支持）
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
+
+    /// 搜索单页。`max_results` 为结果数量提示，收集到足够的结果后可以提前停止解析
+    async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>>;
+
+    /// 按 infohash（小写40位十六进制）查找资源。支持按哈希检索的站点可以覆盖此方法，
+    /// 默认把哈希当作关键词搜索第一页
+    async fn search_by_infohash(&self, infohash: &str) -> Result<Vec<SearchResult>> {
+        self.search(infohash, 1, None).await
+    }
+
+    /// 访问结果的详情页，补全真实的文件列表和大小（默认不做任何处理）
+    async fn enrich(&self, _result: &mut SearchResult) -> Result<()> {
+        Ok(())
+    }
+
+    /// 只解析给定的搜索结果页HTML，不发起网络请求，用于调试解析逻辑（默认不支持）
+    async fn parse_html(&self, _html: &str, _page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
+        Err(anyhow!("Provider '{}' does not support parsing raw HTML", self.name()))
+    }
+
+    /// 快速搜索单页：只使用不依赖 AI 的解析，需要 AI 提取的页面随结果一起返回，留待后台处理
+    /// （默认与 `search` 相同，没有待提取的页面）
+    async fn search_quick(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<QuickPage> {
+        Ok(QuickPage { results: self.search(query, page, max_results).await?, deferred: None })
+    }
+
+    /// 对快速搜索留下的页面进行 AI 提取（默认没有结果）
+    async fn refine_quick(&self, _deferred: &DeferredHtml, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        Ok(Vec::new())
+    }
+}
+
+/// 快速搜索单页的结果
+pub struct QuickPage {
+    /// 不经 AI 解析得到的结果
+    pub results: Vec<SearchResult>,
+    /// 需要在后台进行 AI 提取的页面
+    pub deferred: Option<DeferredHtml>,
+}
+
+/// 留待 AI 提取的结果页内容
+#[derive(Debug, Clone)]
+pub struct DeferredHtml {
+    pub html: String,
+    pub page_url: url::Url,
+}
+
+/// clmclm.com 的可配置项
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClmclmConfig {
+    /// URL 中的分类代码（`search-{keyword}-{category}-{sort}-{page}.html`）
+    pub category_code: String,
+    /// URL 中的排序代码
+    pub sort_code: String,
+    /// 搜索结果页的解析选择器
+    pub selectors: ClmclmSelectors,
+    /// 附加的请求头，覆盖同名的全局请求头
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Default for ClmclmConfig {
+    fn default() -> Self {
+        Self {
+            category_code: "1".to_string(),
+            sort_code: "1".to_string(),
+            selectors: ClmclmSelectors::default(),
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+/// clmclm.com 搜索结果页使用的 CSS 选择器，站点改版时可在运行时修改
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ClmclmSelectors {
+    /// 单条结果的容器
+    pub row: String,
+    /// 结果容器内的标题链接
+    pub title: String,
+    /// 结果容器内的磁力链接
+    pub magnet: String,
+    /// 结果容器内包含 "大小:" 的元素
+    pub size: String,
+    /// 结果容器内的文件列表条目
+    pub file_list: String,
+}
+
+impl Default for ClmclmSelectors {
+    fn default() -> Self {
+        Self {
+            row: "div.ssbox".to_string(),
+            title: "div.title > h3 > a".to_string(),
+            magnet: "div.sbar a[href^=\"magnet:\"]".to_string(),
+            size: "div.sbar span".to_string(),
+            file_list: "ul > li".to_string(),
+        }
+    }
+}
+
+impl ClmclmSelectors {
+    /// 校验每个选择器，无效的选择器回退为默认值
+    pub fn validated(self) -> Self {
+        let defaults = Self::default();
+        let check = |name: &str, value: String, default: String| {
+            if Selector::parse(&value).is_ok() {
+                value
+            } else {
+                search_log!(warn, "Invalid clmclm {} selector '{}', using default '{}'", name, value, default);
+                default
+            }
+        };
+
+        Self {
+            row: check("row", self.row, defaults.row),
+            title: check("title", self.title, defaults.title),
+            magnet: check("magnet", self.magnet, defaults.magnet),
+            size: check("size", self.size, defaults.size),
+            file_list: check("file_list", self.file_list, defaults.file_list),
+        }
+    }
+}
+
+/// clmclm.com 的一个分类，`code` 对应搜索URL中的分类代码
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ClmclmCategory {
+    pub code: String,
+    pub name: String,
+}
+
+/// 分类导航链接的地址形如 `/search-{keyword}-{category}-{sort}-{page}.html` 或 `/cat-{category}.html`
+static CLMCLM_CATEGORY_HREF_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"(?:search-[^/]*?-(\d+)-\d+-\d+\.html|cat-(\d+)\.html)$").expect("Invalid category link regex")
+});
+
+/// 从 clmclm.com 页面中解析分类导航
+pub fn parse_clmclm_categories(html: &str) -> Vec<ClmclmCategory> {
+    let document = Html::parse_document(html);
+    let link_selector = Selector::parse("a[href]").unwrap();
+
+    let mut categories: Vec<ClmclmCategory> = Vec::new();
+    for link in document.select(&link_selector) {
+        let href = link.value().attr("href").unwrap_or_default().trim();
+        let Some(code) = CLMCLM_CATEGORY_HREF_REGEX
+            .captures(href)
+            .and_then(|captures| captures.get(1).or_else(|| captures.get(2)))
+            .map(|code| code.as_str().to_string())
+        else {
+            continue;
+        };
+        let name = link.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+        if !name.is_empty() && !categories.iter().any(|category| category.code == code) {
+            categories.push(ClmclmCategory { code, name });
+        }
+    }
+    categories
+}
+
+/// 各提供商单个 HTTP 请求的时限
+pub const PROVIDER_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// clmclm.com 请求使用的 User-Agent
+pub const CLMCLM_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+/// 通用 HTML 引擎请求使用的 User-Agent，JSON 接口与 Torznab 使用 reqwest 的默认值
+pub const GENERIC_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// clmclm.com 分类列表的缓存有效期
+pub const CLMCLM_CATEGORY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 抓取时间与抓取到的分类
+type CachedCategories = (std::time::Instant, Vec<ClmclmCategory>);
+
+/// 已抓取的 clmclm.com 分类
+static CLMCLM_CATEGORIES: Lazy<Mutex<Option<CachedCategories>>> = Lazy::new(|| Mutex::new(None));
+
+/// 获取 clmclm.com 的分类列表：缓存未过期时直接返回，`refresh` 为 true 或已过期时重新抓取
+pub async fn clmclm_categories(provider: &ClmclmProvider, refresh: bool) -> Result<Vec<ClmclmCategory>> {
+    if !refresh {
+        if let Some((fetched_at, categories)) = CLMCLM_CATEGORIES.lock().unwrap().as_ref() {
+            if fetched_at.elapsed() < CLMCLM_CATEGORY_TTL {
+                return Ok(categories.clone());
+            }
+        }
+    }
+
+    let categories = provider.fetch_categories().await?;
+    *CLMCLM_CATEGORIES.lock().unwrap() = Some((std::time::Instant::now(), categories.clone()));
+    Ok(categories)
+}
+
+/// 自定义引擎结果页的 CSS 选择器，配置后优先于 AI 和启发式解析
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GenericSelectors {
+    /// 单条结果的容器
+    pub row: String,
+    /// 结果容器内的标题元素，为链接时其 href 作为详情页地址
+    pub title: String,
+    /// 结果容器内的磁力链接
+    #[serde(default = "default_generic_magnet_selector")]
+    pub magnet: String,
+    /// 结果容器内的文件大小元素
+    #[serde(default)]
+    pub size: Option<String>,
+    /// 结果容器内的上传日期元素
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+fn default_generic_magnet_selector() -> String {
+    "a[href^=\"magnet:\"]".to_string()
 }
 
 /// clmclm.com 搜索引擎实现
 pub struct ClmclmProvider {
     client: reqwest::Client,
     pub base_url: String,
+    config: ClmclmConfig,
 }
 
 impl ClmclmProvider {
+    pub const CAPABILITIES: ProviderCapabilities = ProviderCapabilities {
+        detail_enrichment: true,
+        url_codes: true,
+        ..ProviderCapabilities::NONE
+    };
+
     pub fn with_base_url(base_url: &str) -> Self {
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36")
-            .timeout(std::time::Duration::from_secs(30))
+        let client = crate::net::client_builder()
+            .user_agent(CLMCLM_USER_AGENT)
+            .timeout(PROVIDER_REQUEST_TIMEOUT)
+            .cookie_provider(crate::net::cookie_jar("clmclm.com"))
             .build()
             .expect("Failed to create HTTP client");
 
         Self {
             client,
             base_url: base_url.trim_end_matches('/').to_string(),
+            config: ClmclmConfig::default(),
         }
     }
 
     pub fn new() -> Self {
         Self::with_base_url("http://clmclm.com")
     }
+
+    /// 设置分类、排序和解析选择器等配置
+    pub fn with_config(mut self, mut config: ClmclmConfig) -> Self {
+        config.selectors = config.selectors.validated();
+        self.config = config;
+        self
+    }
+
+    /// 抓取首页的分类导航，返回按出现顺序排列、按代码去重的分类
+    pub async fn fetch_categories(&self) -> Result<Vec<ClmclmCategory>> {
+        let url = format!("{}/", self.base_url);
+        let response = self.client
+            .get(&url)
+            .headers(crate::net::request_headers(&self.config.headers))
+            .send()
+            .await
+            .map_err(|e| handle_request_error(&url, e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP error {}: {}", response.status(), url));
+        }
+
+        let html = crate::net::read_text(response).await?;
+        let categories = parse_clmclm_categories(&html);
+        if categories.is_empty() {
+            return Err(anyhow!("No categories found on {}", url));
+        }
+        search_log!(stats, "Found {} clmclm categories", categories.len());
+        Ok(categories)
+    }
+
+    /// 构建搜索页URL
+    fn build_search_url(&self, encoded_query: &str, page: u32) -> String {
+        format!(
+            "{}/search-{}-{}-{}-{}.html",
+            self.base_url, encoded_query, self.config.category_code, self.config.sort_code, page
+        )
+    }
+}
+
+/// 编译后的 clmclm.com 结果页选择器
+struct CompiledClmclmSelectors {
+    row: Selector,
+    title: Selector,
+    magnet: Selector,
+    size: Selector,
+    file_list: Selector,
+}
+
+impl CompiledClmclmSelectors {
+    fn compile(selectors: &ClmclmSelectors) -> Result<Self> {
+        let parse = |selector: &str| Selector::parse(selector).map_err(|e| anyhow!("Invalid CSS selector: {}", e));
+        Ok(Self {
+            row: parse(&selectors.row)?,
+            title: parse(&selectors.title)?,
+            magnet: parse(&selectors.magnet)?,
+            size: parse(&selectors.size)?,
+            file_list: parse(&selectors.file_list)?,
+        })
+    }
+}
+
+static SIMPLE_ROW_SELECTOR_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"^\s*([A-Za-z][A-Za-z0-9]*)\.([A-Za-z0-9_-]+)\s*$").unwrap());
+static CLASS_ATTR_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"(?i)\sclass\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+
+/// 单独作为 body 片段解析时结构不变的容器标签；`tr`、`td` 等离开 `<table>` 会被 html5ever 丢弃
+const FRAGMENT_SAFE_ROW_TAGS: &[&str] = &["div", "article", "section", "li"];
+
+/// 选择器形如 `标签.类名` 且标签可以单独作为片段解析时返回 (标签, 类名)
+fn simple_row_selector(selector: &str) -> Option<(&str, &str)> {
+    let captures = SIMPLE_ROW_SELECTOR_REGEX.captures(selector)?;
+    let tag = captures.get(1)?.as_str();
+    if !FRAGMENT_SAFE_ROW_TAGS.iter().any(|safe| safe.eq_ignore_ascii_case(tag)) {
+        return None;
+    }
+    Some((tag, captures.get(2)?.as_str()))
+}
+
+/// 大小写不敏感地查找 `needle` 在 `from` 之后第一次出现的位置
+fn find_ignore_ascii_case(haystack: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    let mut position = from;
+    while let Some(offset) = haystack.get(position..)?.iter().position(|&byte| byte == needle[0]) {
+        let candidate = position + offset;
+        if haystack.get(candidate..candidate + needle.len())?.eq_ignore_ascii_case(needle) {
+            return Some(candidate);
+        }
+        position = candidate + 1;
+    }
+    None
+}
+
+/// `position` 处是否为 `<tag` 或 `</tag` 标签的开头（标签名之后是空白、`>` 或 `/`）
+fn is_tag_at(bytes: &[u8], position: usize, prefix_len: usize) -> bool {
+    bytes
+        .get(position + prefix_len)
+        .is_none_or(|next| next.is_ascii_whitespace() || *next == b'>' || *next == b'/')
+}
+
+/// 从原始 HTML 中依次切出带有指定类名的 `<tag>` 块（按同名标签的嵌套配对结束标签）
+///
+/// 只扫描文本，不构建 DOM；结束标签缺失时块延伸到文档末尾。
+fn tag_blocks<'a>(html: &'a str, tag: &str, class: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let bytes = html.as_bytes();
+    let open = format!("<{tag}").into_bytes();
+    let close = format!("</{tag}").into_bytes();
+    let mut position = 0;
+
+    std::iter::from_fn(move || loop {
+        let start = find_ignore_ascii_case(bytes, position, &open)?;
+        let open_end = html[start..].find('>').map_or(html.len(), |offset| start + offset + 1);
+        position = open_end;
+        if !is_tag_at(bytes, start, open.len()) {
+            continue;
+        }
+        let has_class = CLASS_ATTR_REGEX.captures(&html[start..open_end]).is_some_and(|captures| {
+            captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .any(|value| value.as_str().split_whitespace().any(|name| name == class))
+        });
+        if !has_class {
+            continue;
+        }
+
+        let mut depth = 1;
+        let mut cursor = open_end;
+        while depth > 0 {
+            let next_open = find_ignore_ascii_case(bytes, cursor, &open).filter(|&i| is_tag_at(bytes, i, open.len()));
+            let next_close = find_ignore_ascii_case(bytes, cursor, &close).filter(|&i| is_tag_at(bytes, i, close.len()));
+            match (next_open, next_close) {
+                (Some(o), Some(c)) if o < c => {
+                    depth += 1;
+                    cursor = o + open.len();
+                }
+                (_, Some(c)) => {
+                    depth -= 1;
+                    cursor = html[c..].find('>').map_or(html.len(), |offset| c + offset + 1);
+                }
+                (Some(o), None) => {
+                    depth += 1;
+                    cursor = o + open.len();
+                }
+                (None, None) => {
+                    cursor = html.len();
+                    break;
+                }
+            }
+        }
+        position = cursor;
+        return Some(&html[start..cursor]);
+    })
 }
 
 #[async_trait::async_trait]
@@ -117,13 +803,18 @@ impl SearchProvider for ClmclmProvider {
         "clmclm.com"
     }
 
-    async fn search(&self, query: &str, page: u32) -> Result<Vec<SearchResult>> {
+    fn capabilities(&self) -> ProviderCapabilities {
+        Self::CAPABILITIES
+    }
+
+    async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
         let encoded_query = urlencoding::encode(query);
-        let url = format!("{}/search-{}-1-1-{}.html", self.base_url, encoded_query, page);
+        let url = self.build_search_url(&encoded_query, page);
         search_log!(info, "Searching: {}", url);
 
         let response = self.client
             .get(&url)
+            .headers(crate::net::request_headers(&self.config.headers))
             .send()
             .await
             .map_err(|e| handle_request_error(&url, e))?;
@@ -133,99 +824,166 @@ impl SearchProvider for ClmclmProvider {
             return Err(anyhow!("HTTP error {}: {}", response.status(), url));
         }
 
-        let html = response.text().await?;
-        let results = self.parse_results(&html)?;
+        let html = crate::net::read_text(response).await?;
+        let results = self.parse_results(&html, max_results)?;
         search_log!(stats, "Found {} results on page {}", results.len(), page);
         Ok(results)
     }
+
+    async fn parse_html(&self, html: &str, _page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
+        self.parse_results(html, None)
+    }
+
+    async fn enrich(&self, result: &mut SearchResult) -> Result<()> {
+        let Some(detail_url) = result.source_url.clone() else {
+            return Ok(());
+        };
+
+        let response = self.client
+            .get(&detail_url)
+            .headers(crate::net::request_headers(&self.config.headers))
+            .send()
+            .await
+            .map_err(|e| handle_request_error(&detail_url, e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP error {}: {}", response.status(), detail_url));
+        }
+
+        let html = crate::net::read_text(response).await?;
+        let (file_list, file_size) = self.parse_detail_page(&html)?;
+
+        if !file_list.is_empty() {
+            result.file_list = file_list;
+        }
+        if file_size.is_some() {
+            result.file_size = file_size;
+        }
+        Ok(())
+    }
 }
 
 impl ClmclmProvider {
-    fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
+    /// 解析搜索结果页
+    ///
+    /// 结果容器选择器为简单的 `标签.类名`（默认的 `div.ssbox`）时逐块解析，避免为整页构建 DOM；
+    /// 其他选择器或找不到结果块时解析整个文档。两种方式的输出相同。
+    fn parse_results(&self, html: &str, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let selectors = CompiledClmclmSelectors::compile(&self.config.selectors)?;
+        if let Some((tag, class)) = simple_row_selector(&self.config.selectors.row) {
+            let mut results = Vec::new();
+            let mut matched_rows = 0;
+            for block in tag_blocks(html, tag, class) {
+                if reached_limit(&results, max_results) {
+                    break;
+                }
+                let fragment = Html::parse_fragment(block);
+                for element in fragment.select(&selectors.row) {
+                    if reached_limit(&results, max_results) {
+                        break;
+                    }
+                    matched_rows += 1;
+                    results.extend(self.parse_row(element, &selectors));
+                }
+            }
+            // 片段中一个结果容器都没有匹配到时，交给整页解析判断
+            if matched_rows > 0 {
+                return Ok(results);
+            }
+        }
+        self.parse_results_full(html, &selectors, max_results)
+    }
+
+    /// 构建整页 DOM 后解析
+    fn parse_results_full(&self, html: &str, selectors: &CompiledClmclmSelectors, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
         let document = Html::parse_document(html);
+        let mut results = Vec::new();
 
-        let row_selector = Selector::parse("div.ssbox")
-            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
-        let title_selector = Selector::parse("div.title > h3 > a")
-            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
-        let magnet_selector = Selector::parse("div.sbar a[href^=\"magnet:\"]")
-            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
-        let file_list_selector = Selector::parse("ul > li")
-            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
+        for element in document.select(&selectors.row) {
+            if reached_limit(&results, max_results) {
+                break;
+            }
+            results.extend(self.parse_row(element, selectors));
+        }
 
-        let mut results = Vec::new();
+        Ok(results)
+    }
 
-        for element in document.select(&row_selector) {
-            let title_element = element.select(&title_selector).next();
-            let magnet_element = element.select(&magnet_selector).next();
-
-            if let (Some(title_node), Some(magnet_node)) = (title_element, magnet_element) {
-                let title = clean_html_text(&title_node.text().collect::<String>());
-                let source_url = title_node.value().attr("href").map(|s| format!("{}{}", self.base_url, s));
-
-                if let Some(magnet_link) = magnet_node.value().attr("href") {
-                    // 尝试从所有span中找到文件大小
-                    let mut file_size = None;
-                    let span_selector = Selector::parse("div.sbar span").unwrap();
-                    for span in element.select(&span_selector) {
-                        let span_text = span.text().collect::<String>();
-                        let span_text = span_text.trim();
-                        if span_text.starts_with("大小:") {
-                            file_size = Some(span_text.replace("大小:", "").trim().to_string());
-                            break;
-                        }
-                    }
+    /// 解析单条结果的容器，缺少标题或磁力链接时返回 None
+    fn parse_row(&self, element: scraper::ElementRef<'_>, selectors: &CompiledClmclmSelectors) -> Option<SearchResult> {
+        let title_node = element.select(&selectors.title).next()?;
+        let magnet_node = element.select(&selectors.magnet).next()?;
+        let magnet_link = magnet_node.value().attr("href")?;
 
-                    // 提取真实的文件列表
-                    let mut file_list = Vec::new();
-                    for li_element in element.select(&file_list_selector) {
-                        let file_text = li_element.text().collect::<String>();
-                        let file_text = file_text.trim();
-
-                        // 解析文件名和大小，格式通常是 "文件名 大小"
-                        if !file_text.is_empty() {
-                            // 分割文件名和大小，大小通常在最后
-                            let parts: Vec<&str> = file_text.split_whitespace().collect();
-                            if parts.len() >= 2 {
-                                // 检查最后一部分是否是文件大小（包含 GB, MB, KB 等）
-                                let last_part = parts[parts.len() - 1];
-                                if last_part.contains("GB") || last_part.contains("MB") || last_part.contains("KB") || last_part.contains("TB") {
-                                    // 文件名是除了最后一部分的所有内容
-                                    let filename = parts[..parts.len() - 1].join(" ");
-                                    if !filename.is_empty() {
-                                        file_list.push(filename);
-                                    }
-                                } else {
-                                    // 如果没有识别到大小，就把整个文本作为文件名
-                                    file_list.push(file_text.to_string());
-                                }
-                            } else {
-                                // 如果只有一个部分，直接作为文件名
-                                file_list.push(file_text.to_string());
-                            }
-                        }
-                    }
+        let raw_title = title_node.text().collect::<String>();
+        let title = clean_html_text(&raw_title);
+        let source_url = title_node.value().attr("href").map(|s| format!("{}{}", self.base_url, s));
 
-                    // 如果没有解析到文件列表，使用基于标题的生成方法作为后备
-                    if file_list.is_empty() {
-                        file_list = self.extract_file_list_from_magnet(magnet_link, &title);
-                    }
+        // 尝试从所有span中找到文件大小
+        let mut file_size = None;
+        for span in element.select(&selectors.size) {
+            let span_text = span.text().collect::<String>();
+            let span_text = span_text.trim();
+            if span_text.starts_with("大小:") {
+                file_size = Some(span_text.replace("大小:", "").trim().to_string());
+                break;
+            }
+        }
 
-                    results.push(SearchResult {
-                        title,
-                        magnet_link: magnet_link.to_string(),
-                        file_size,
-                        upload_date: None, // clmclm.com doesn't provide upload date
-                        file_list,
-                        source_url,
-                        score: None,
-                        tags: None,
-                    });
-                }
+        // 提取真实的文件列表
+        let mut file_list = Vec::new();
+        for li_element in element.select(&selectors.file_list) {
+            let file_text = li_element.text().collect::<String>();
+            if let Some((filename, _)) = split_file_entry(&file_text) {
+                file_list.push(filename);
             }
         }
 
-        Ok(results)
+        // 如果没有解析到文件列表，使用基于标题的生成方法作为后备
+        if file_list.is_empty() {
+            file_list = self.extract_file_list_from_magnet(magnet_link, &title);
+        }
+
+        Some(SearchResult {
+            original_title: raw_title.trim().to_string(),
+            title,
+            magnet_link: magnet_link.to_string(),
+            file_size,
+            upload_date: None, // clmclm.com doesn't provide upload date
+            file_list,
+            source_url,
+            provider_name: self.name().to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// 解析详情页，返回完整的文件列表和总大小
+    fn parse_detail_page(&self, html: &str) -> Result<(Vec<String>, Option<String>)> {
+        let document = Html::parse_document(html);
+
+        let file_selector = Selector::parse("ul > li")
+            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
+        let span_selector = Selector::parse("span")
+            .map_err(|e| anyhow!("Invalid CSS selector: {}", e))?;
+
+        // 详情页可能包含导航等其他列表，只保留带有文件大小的条目
+        let file_list = document
+            .select(&file_selector)
+            .filter_map(|li| match split_file_entry(&li.text().collect::<String>()) {
+                Some((filename, Some(_))) => Some(filename),
+                _ => None,
+            })
+            .collect();
+
+        let file_size = document.select(&span_selector).find_map(|span| {
+            let span_text = span.text().collect::<String>();
+            span_text
+                .trim()
+                .strip_prefix("大小:")
+                .map(|size| size.trim().to_string())
+        });
+
+        Ok((file_list, file_size))
     }
 
     /// 从磁力链接和标题中提取文件列表（基于标题生成相关文件列表）
@@ -238,6 +996,32 @@ impl ClmclmProvider {
     }
 }
 
+/// 替换URL模板中的 `{keyword}` 与页码占位符（`{page}` 从 1 开始，`{page-1}` 从 0 开始）
+fn fill_url_template(template: &str, query: &str, page: u32) -> String {
+    let url = template.replace("{keyword}", query);
+
+    // Handle different page numbering systems
+    if url.contains("{page-1}") {
+        // 0-based pagination: subtract 1 from page number
+        url.replace("{page-1}", &page.saturating_sub(1).to_string())
+    } else {
+        // 1-based pagination (default)
+        url.replace("{page}", &page.to_string())
+    }
+}
+
+/// 单个引擎一页结果的第一阶段提取 token 估算
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ExtractionTokenEstimate {
+    pub engine: String,
+    /// 页面原始 HTML 的字符数
+    pub html_chars: usize,
+    /// 截断后实际发送的 HTML 的字符数
+    pub truncated_chars: usize,
+    pub prompt_chars: usize,
+    pub estimated_tokens: u64,
+}
+
 /// 通用搜索引擎提供商，支持自定义URL模板和AI智能识别
 pub struct GenericProvider {
     name: String,
@@ -246,13 +1030,95 @@ pub struct GenericProvider {
     llm_client: Option<Arc<dyn LlmClient>>,
     extraction_config: Option<LlmConfig>,  // HTML提取配置（分析由前端处理）
     priority_keywords: Vec<String>,
+    empty_title_policy: EmptyTitlePolicy,
+    selectors: Option<GenericSelectors>,
+    headers: BTreeMap<String, String>,
+    /// 所有 AI 引擎共享的提取并发限制
+    extraction_limiter: Option<Arc<tokio::sync::Semaphore>>,
+    parse_order: Vec<ParseStrategy>,
+    auth: Option<EngineAuth>,
 }
 
-impl GenericProvider {
+/// 需要登录的引擎的登录方式，凭据与其他引擎配置一样保存在应用数据中
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EngineAuth {
+    /// 登录表单的提交地址
+    pub login_url: String,
+    /// 以表单形式 POST 的字段（用户名、密码等）
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// 登录成功时响应中应包含的文本，为 None 时不检查
+    #[serde(default)]
+    pub success_text: Option<String>,
+    /// 登录成功时应设置的 cookie 名称，为 None 时不检查
+    #[serde(default)]
+    pub success_cookie: Option<String>,
+}
+
+/// 本次运行中已登录的引擎及登录时使用的配置，配置变化或会话失效后会重新登录
+///
+/// 会话 cookie 保存在该引擎的 cookie 存储中（见 `net::cookie_jar`），应用重启后需要重新登录。
+static ENGINE_SESSIONS: Lazy<tokio::sync::Mutex<std::collections::HashMap<String, EngineAuth>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(std::collections::HashMap::new()));
+
+/// 结果行中的图片，用于提取缩略图
+static IMG_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("img").unwrap());
+
+/// 通用引擎解析结果页的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseStrategy {
+    /// 配置的 CSS 选择器
+    Selectors,
+    /// AI 提取
+    Ai,
+    /// 基于磁力链接的启发式解析
+    Heuristic,
+}
+
+/// 默认的解析顺序：选择器 → AI → 启发式
+pub fn default_parse_order() -> Vec<ParseStrategy> {
+    vec![ParseStrategy::Selectors, ParseStrategy::Ai, ParseStrategy::Heuristic]
+}
+
+/// 去掉重复的策略，空列表替换为默认顺序
+pub fn normalize_parse_order(order: Vec<ParseStrategy>) -> Vec<ParseStrategy> {
+    let mut normalized = Vec::new();
+    for strategy in order {
+        if !normalized.contains(&strategy) {
+            normalized.push(strategy);
+        }
+    }
+    if normalized.is_empty() {
+        default_parse_order()
+    } else {
+        normalized
+    }
+}
+
+/// AI 提取结果标题为空时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyTitlePolicy {
+    /// 从磁力链接的 dn 参数推导标题
+    #[default]
+    DeriveFromMagnet,
+    /// 丢弃该结果
+    Drop,
+}
+
+impl GenericProvider {
+    pub const CAPABILITIES: ProviderCapabilities = ProviderCapabilities {
+        ai_extraction: true,
+        ..ProviderCapabilities::NONE
+    };
+
     pub fn new(name: String, url_template: String) -> Self {
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-            .timeout(std::time::Duration::from_secs(30))
+        let client = crate::net::client_builder()
+            .user_agent(GENERIC_USER_AGENT)
+            .timeout(PROVIDER_REQUEST_TIMEOUT)
+            // 保留站点设置的会话 cookie，翻页时继续发送
+            .cookie_provider(crate::net::cookie_jar(&name))
             // reqwest默认启用gzip/deflate解压，不需要显式设置
             .build()
             .expect("Failed to create HTTP client");
@@ -264,6 +1130,12 @@ impl GenericProvider {
             llm_client: None,
             extraction_config: None,
             priority_keywords: Vec::new(),
+            empty_title_policy: EmptyTitlePolicy::default(),
+            selectors: None,
+            headers: BTreeMap::new(),
+            extraction_limiter: None,
+            parse_order: default_parse_order(),
+            auth: None,
         }
     }
 
@@ -283,59 +1155,119 @@ impl GenericProvider {
         self.priority_keywords = keywords;
         self
     }
-}
 
-#[async_trait::async_trait]
-impl SearchProvider for GenericProvider {
-    fn name(&self) -> &str {
-        &self.name
+    /// 设置结果页的 CSS 选择器
+    pub fn with_selectors(mut self, selectors: Option<GenericSelectors>) -> Self {
+        self.selectors = selectors;
+        self
     }
 
-    async fn search(&self, query: &str, page: u32) -> Result<Vec<SearchResult>> {
-        // 替换URL模板中的占位符
-        let mut url = self.url_template
-            .replace("{keyword}", query);
+    /// 设置AI提取到空标题时的处理方式
+    pub fn with_empty_title_policy(mut self, policy: EmptyTitlePolicy) -> Self {
+        self.empty_title_policy = policy;
+        self
+    }
 
-        // Handle different page numbering systems
-        if url.contains("{page-1}") {
-            // 0-based pagination: subtract 1 from page number
-            let zero_based_page = if page > 0 { page - 1 } else { 0 };
-            url = url.replace("{page-1}", &zero_based_page.to_string());
-        } else {
-            // 1-based pagination (default)
-            url = url.replace("{page}", &page.to_string());
-        }
+    /// 设置该引擎附加的请求头，覆盖同名的内置与全局请求头
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
 
-        search_log!(info, "Searching: {}", url);
+    /// 抓取一页搜索结果，按 `max_html_chars` 截断后估算第一阶段提取 prompt 的 token 数，不调用 AI
+    pub async fn estimate_extraction_tokens(&self, query: &str, max_html_chars: usize) -> Result<ExtractionTokenEstimate> {
+        let (html, _) = self.fetch_page(query, 1).await?;
+        let truncated_html = truncate_chars(&html, max_html_chars);
+        let prompt = crate::llm_service::build_basic_info_prompt(truncated_html, None);
+
+        Ok(ExtractionTokenEstimate {
+            engine: self.name.clone(),
+            html_chars: html.chars().count(),
+            truncated_chars: truncated_html.chars().count(),
+            prompt_chars: prompt.chars().count(),
+            estimated_tokens: crate::llm_service::estimate_prompt_tokens(&prompt),
+        })
+    }
 
+    /// 设置解析策略的尝试顺序，重复的策略只保留第一次出现，为空时使用默认顺序
+    pub fn with_parse_order(mut self, order: Vec<ParseStrategy>) -> Self {
+        self.parse_order = normalize_parse_order(order);
+        self
+    }
+
+    /// 设置登录方式，搜索前先登录一次并保留会话 cookie
+    pub fn with_auth(mut self, auth: Option<EngineAuth>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// 配置了登录方式且本次运行尚未登录时，提交登录表单并按配置检查是否成功
+    async fn ensure_logged_in(&self) -> Result<()> {
+        let Some(auth) = &self.auth else {
+            return Ok(());
+        };
+        // 登录期间持有锁，避免并发请求多页时重复登录
+        let mut sessions = ENGINE_SESSIONS.lock().await;
+        if sessions.get(&self.name) == Some(auth) {
+            return Ok(());
+        }
+
+        search_log!(info, "Logging in to {}: {}", self.name, auth.login_url);
         let response = self.client
-            .get(&url)
-            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
-            .header("Accept-Language", "en-US,en;q=0.9")
-            .header("Accept-Encoding", "gzip, deflate, br")
-            .header("Cache-Control", "no-cache")
-            .header("Pragma", "no-cache")
-            .header("Sec-Ch-Ua", "\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\"")
-            .header("Sec-Ch-Ua-Mobile", "?0")
-            .header("Sec-Ch-Ua-Platform", "\"Windows\"")
-            .header("Sec-Fetch-Dest", "document")
-            .header("Sec-Fetch-Mode", "navigate")
-            .header("Sec-Fetch-Site", "cross-site")
-            .header("Sec-Fetch-User", "?1")
-            .header("Upgrade-Insecure-Requests", "1")
-            .header("Referer", "https://www.google.com/")
+            .post(&auth.login_url)
+            .headers(crate::net::request_headers(&self.headers))
+            .form(&auth.fields)
             .send()
             .await
-            .map_err(|e| handle_request_error(&url, e))?;
-
+            .map_err(|e| handle_request_error(&auth.login_url, e))?;
         if !response.status().is_success() {
-            search_log!(error, "HTTP error {} for {}", response.status(), url);
-            return Err(anyhow!("HTTP error: {}", response.status()));
+            search_log!(error, "Login to {} failed with HTTP {}", self.name, response.status());
+            return Err(anyhow!("Login to {} failed: HTTP error {}", self.name, response.status()));
+        }
+
+        let final_url = response.url().clone();
+        let body = crate::net::read_text(response).await?;
+        if let Some(text) = auth.success_text.as_deref().filter(|text| !body.contains(*text)) {
+            search_log!(error, "Login to {} failed: response does not contain \"{}\"", self.name, text);
+            return Err(anyhow!("Login to {} failed: response does not contain \"{text}\"", self.name));
+        }
+        if let Some(cookie) = &auth.success_cookie {
+            use reqwest::cookie::CookieStore;
+            let cookies = crate::net::cookie_jar(&self.name).cookies(&final_url);
+            let has_cookie = cookies
+                .as_ref()
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.split("; ").any(|pair| pair.split('=').next() == Some(cookie.as_str())));
+            if !has_cookie {
+                search_log!(error, "Login to {} failed: cookie {} was not set", self.name, cookie);
+                return Err(anyhow!("Login to {} failed: cookie {cookie} was not set", self.name));
+            }
         }
 
-        // 获取响应文本（reqwest自动处理压缩）
-        let html = response.text().await
-            .map_err(|e| anyhow!("Failed to read response: {}", e))?;
+        search_log!(success, "Logged in to {}", self.name);
+        sessions.insert(self.name.clone(), auth.clone());
+        Ok(())
+    }
+
+    /// 设置 HTML 提取的并发限制，多个引擎传入同一个信号量即共享上限；为 None 时不限制
+    pub fn with_extraction_limiter(mut self, limiter: Option<Arc<tokio::sync::Semaphore>>) -> Self {
+        self.extraction_limiter = limiter;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for GenericProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        Self::CAPABILITIES
+    }
+
+    async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let (html, page_url) = self.fetch_page(query, page).await?;
 
         // 检查响应内容类型
         let is_javascript = html.trim_start().starts_with("\"use strict\"") ||
@@ -355,9 +1287,9 @@ impl SearchProvider for GenericProvider {
         if html.contains('�') || is_javascript {
             let preview = safe_truncate(&html, 500);
             search_log!(info, "HTML preview (前500字符，用于诊断):");
-            println!("---START---");
-            println!("{preview}");
-            println!("---END---");
+            trace_println!("---START---");
+            trace_println!("{preview}");
+            trace_println!("---END---");
         }
 
         // 简单检查内容
@@ -369,80 +1301,269 @@ impl SearchProvider for GenericProvider {
             }
         }
 
-        // 对于自定义搜索引擎，使用AI智能识别流程
-        let results = if let Some(llm_client) = &self.llm_client {
-            self.analyze_html_with_ai(&html, llm_client.clone()).await?
-        } else {
-            self.parse_generic_results(&html)?
-        };
-
+        let results = self.parse_page(&html, Some(&page_url), max_results).await?;
         search_log!(stats, "Found {} results on page {}", results.len(), page);
         Ok(results)
     }
+
+    async fn parse_html(&self, html: &str, page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
+        self.parse_page(html, page_url, None).await
+    }
+
+    async fn search_quick(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<QuickPage> {
+        let (html, page_url) = self.fetch_page(query, page).await?;
+        let ai_position = self
+            .llm_client
+            .as_ref()
+            .and_then(|_| self.parse_order.iter().position(|strategy| *strategy == ParseStrategy::Ai));
+        let Some(ai_position) = ai_position else {
+            let results = self.parse_page(&html, Some(&page_url), max_results).await?;
+            return Ok(QuickPage { results, deferred: None });
+        };
+
+        // AI 之前的策略有结果时与普通搜索一致，不需要 AI
+        let results = self
+            .parse_with_strategies(&self.parse_order[..ai_position], &html, Some(&page_url), max_results)
+            .await
+            .unwrap_or_default();
+        if !results.is_empty() {
+            return Ok(QuickPage { results, deferred: None });
+        }
+
+        // 先用 AI 之后的策略给出初步结果，AI 提取留给后台
+        let results = self
+            .parse_with_strategies(&self.parse_order[ai_position + 1..], &html, Some(&page_url), max_results)
+            .await
+            .unwrap_or_default();
+        Ok(QuickPage { results, deferred: Some(DeferredHtml { html, page_url }) })
+    }
+
+    async fn refine_quick(&self, deferred: &DeferredHtml, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        match &self.llm_client {
+            Some(llm_client) => {
+                self.analyze_html_with_ai(&deferred.html, Some(&deferred.page_url), max_results, llm_client.clone()).await
+            }
+            None => Ok(Vec::new()),
+        }
+    }
 }
 
 impl GenericProvider {
-    /// 使用AI分析整个HTML内容
-    async fn analyze_html_with_ai(&self, html: &str, llm_client: Arc<dyn LlmClient>) -> Result<Vec<SearchResult>> {
-        search_log!(ai, "Phase 1: Extracting basic info from HTML...");
+    /// 请求搜索结果页，返回页面 HTML 与重定向后的最终地址
+    async fn fetch_page(&self, query: &str, page: u32) -> Result<(String, url::Url)> {
+        let url = fill_url_template(&self.url_template, query, page);
+        self.ensure_logged_in().await?;
+        let mut response = self.send_search_request(&url).await?;
+        // 会话失效时丢弃记录的登录状态，重新登录后再请求一次
+        if self.session_expired(&response) {
+            search_log!(warn, "Session for {} expired (HTTP {}, {}), logging in again", self.name, response.status(), response.url());
+            ENGINE_SESSIONS.lock().await.remove(&self.name);
+            self.ensure_logged_in().await?;
+            response = self.send_search_request(&url).await?;
+        }
 
-        // 第一阶段：让AI从HTML中提取所有磁力链接和基础信息
-        match self.extract_torrents_from_html_with_ai(html, llm_client.clone()).await {
-            Ok(results) => {
-                if results.is_empty() {
-                    search_log!(warn, "AI extraction found no results. Falling back to basic parsing");
-                    return self.parse_generic_results(html);
-                }
+        if !response.status().is_success() {
+            search_log!(error, "HTTP error {} for {}", response.status(), url);
+            return Err(anyhow!("HTTP error: {}", response.status()));
+        }
 
-                search_log!(ai, "Phase 2: Separating priority results...");
-                let (priority_results, regular_results) = self.separate_priority_results(results);
+        // 记录重定向后的最终地址，相对的 source_url 以它为基准解析
+        let page_url = response.url().clone();
+        if page_url.as_str() != url {
+            search_log!(info, "Redirected to: {}", page_url);
+        }
 
-                search_log!(success, "AI extraction completed: {} priority and {} regular results",
-                         priority_results.len(), regular_results.len());
+        // 获取响应文本（reqwest自动处理压缩），超过大小上限时中止读取
+        let html = crate::net::read_text(response).await?;
+        Ok((html, page_url))
+    }
 
-                // 合并结果：优先结果在前，普通结果在后
-                let mut final_results = priority_results;
-                final_results.extend(regular_results);
-                Ok(final_results)
-            }
-            Err(e) => {
-                search_log!(warn, "AI HTML analysis failed: {}, falling back to basic parsing", e);
-                self.parse_generic_results(html)
+    /// 发送结果页请求，不检查状态码
+    async fn send_search_request(&self, url: &str) -> Result<reqwest::Response> {
+        search_log!(info, "Searching: {}", url);
+        self.client
+            .get(url)
+            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Cache-Control", "no-cache")
+            .header("Pragma", "no-cache")
+            .header("Sec-Ch-Ua", "\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\"")
+            .header("Sec-Ch-Ua-Mobile", "?0")
+            .header("Sec-Ch-Ua-Platform", "\"Windows\"")
+            .header("Sec-Fetch-Dest", "document")
+            .header("Sec-Fetch-Mode", "navigate")
+            .header("Sec-Fetch-Site", "cross-site")
+            .header("Sec-Fetch-User", "?1")
+            .header("Upgrade-Insecure-Requests", "1")
+            .header("Referer", "https://www.google.com/")
+            .headers(crate::net::request_headers(&self.headers))
+            .send()
+            .await
+            .map_err(|e| handle_request_error(url, e))
+    }
+
+    /// 配置了登录方式时，401/403 或被重定向到登录页都视为会话已失效
+    fn session_expired(&self, response: &reqwest::Response) -> bool {
+        let Some(auth) = &self.auth else {
+            return false;
+        };
+        if matches!(response.status(), reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) {
+            return true;
+        }
+        let without_query = |url: &url::Url| format!("{}{}", url.origin().ascii_serialization(), url.path());
+        url::Url::parse(&auth.login_url).is_ok_and(|login_url| without_query(&login_url) == without_query(response.url()))
+    }
+
+    /// 按 `parse_order` 依次尝试各解析策略，返回第一个得到结果的策略的结果
+    ///
+    /// 未配置选择器或 LLM 的策略直接跳过；所有策略都没有结果时返回空列表，
+    /// 全部失败时返回最后一个错误。
+    async fn parse_page(&self, html: &str, page_url: Option<&url::Url>, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        self.parse_with_strategies(&self.parse_order, html, page_url, max_results).await
+    }
+
+    /// 按给定顺序依次尝试解析策略，规则同 `parse_page`
+    async fn parse_with_strategies(
+        &self,
+        strategies: &[ParseStrategy],
+        html: &str,
+        page_url: Option<&url::Url>,
+        max_results: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        let mut last_error = None;
+        let mut any_succeeded = false;
+
+        for strategy in strategies {
+            let outcome = match strategy {
+                ParseStrategy::Selectors => match &self.selectors {
+                    Some(selectors) => self.parse_with_selectors(html, selectors, page_url, max_results),
+                    None => continue,
+                },
+                ParseStrategy::Ai => match &self.llm_client {
+                    Some(llm_client) => self.analyze_html_with_ai(html, page_url, max_results, llm_client.clone()).await,
+                    None => continue,
+                },
+                ParseStrategy::Heuristic => self.parse_generic_results(html, page_url, max_results),
+            };
+
+            match outcome {
+                Ok(results) if !results.is_empty() => {
+                    search_log!(info, "Parsed {} results via {:?} strategy", results.len(), strategy);
+                    return Ok(results);
+                }
+                Ok(_) => {
+                    any_succeeded = true;
+                    search_log!(warn, "{:?} strategy found no results, falling back", strategy);
+                }
+                Err(e) => {
+                    search_log!(warn, "{:?} strategy failed: {}, falling back", strategy, e);
+                    last_error = Some(e);
+                }
             }
         }
+
+        match last_error {
+            Some(e) if !any_succeeded => Err(e),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// 使用AI分析整个HTML内容，没有结果或失败时交给 `parse_page` 尝试下一个策略
+    async fn analyze_html_with_ai(
+        &self,
+        html: &str,
+        page_url: Option<&url::Url>,
+        max_results: Option<usize>,
+        llm_client: Arc<dyn LlmClient>,
+    ) -> Result<Vec<SearchResult>> {
+        search_log!(ai, "Phase 1: Extracting basic info from HTML...");
+
+        // 第一阶段：让AI从HTML中提取所有磁力链接和基础信息
+        let results = self.extract_torrents_from_html_with_ai(html, page_url, max_results, llm_client).await?;
+        if results.is_empty() {
+            return Ok(results);
+        }
+
+        search_log!(ai, "Phase 2: Separating priority results...");
+        let (priority_results, regular_results) = self.separate_priority_results(results);
+
+        search_log!(success, "AI extraction completed: {} priority and {} regular results",
+                 priority_results.len(), regular_results.len());
+
+        // 合并结果：优先结果在前，普通结果在后
+        let mut final_results = priority_results;
+        final_results.extend(regular_results);
+        Ok(final_results)
     }
 
     /// 使用AI从HTML中提取种子信息
-    async fn extract_torrents_from_html_with_ai(&self, html: &str, llm_client: Arc<dyn LlmClient>) -> Result<Vec<SearchResult>> {
-        // 限制HTML长度以避免超出AI token限制 (250k tokens模型，使用80k字符约120k tokens)
-        let truncated_html = if html.len() > 80000 {
-            search_log!(info, "HTML too long ({} chars), truncating to 80k chars", html.len());
-            safe_truncate(html, 80000)
-        } else {
-            html
-        };
+    async fn extract_torrents_from_html_with_ai(
+        &self,
+        html: &str,
+        page_url: Option<&url::Url>,
+        max_results: Option<usize>,
+        llm_client: Arc<dyn LlmClient>,
+    ) -> Result<Vec<SearchResult>> {
+        // 限制HTML长度以避免超出AI token限制
+        let max_html_chars = crate::llm_service::html_char_limit(
+            self.extraction_config.as_ref().and_then(|config| config.max_html_chars),
+        );
+        let truncated_html = truncate_chars(html, max_html_chars);
+        if truncated_html.len() < html.len() {
+            search_log!(info, "HTML too long ({} chars), truncating to {} chars", html.chars().count(), max_html_chars);
+        }
 
         // 直接传递原始HTML给AI服务，让llm_service.rs负责构建提示词
-        match self.call_ai_for_html_analysis(truncated_html, llm_client).await {
+        match self.call_ai_for_html_analysis(truncated_html, page_url, max_results, llm_client).await {
             Ok(ai_results) => Ok(ai_results),
             Err(e) => Err(anyhow!("AI HTML analysis failed: {}", e))
         }
     }
 
     /// 直接调用AI进行HTML分析
-    async fn call_ai_for_html_analysis(&self, html_content: &str, llm_client: Arc<dyn LlmClient>) -> Result<Vec<SearchResult>> {
+    async fn call_ai_for_html_analysis(
+        &self,
+        html_content: &str,
+        page_url: Option<&url::Url>,
+        max_results: Option<usize>,
+        llm_client: Arc<dyn LlmClient>,
+    ) -> Result<Vec<SearchResult>> {
         // 获取提取配置
         let extraction_config = self.extraction_config.as_ref()
             .ok_or_else(|| anyhow!("Extraction config not available"))?;
 
+        // 等待提取并发名额，请求结束后自动归还
+        let _permit = match &self.extraction_limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+
         // 将原始HTML传递给AI服务，由llm_service.rs构建提示词
-        match llm_client.batch_extract_basic_info_from_html(html_content, extraction_config).await {
+        match llm_client.batch_extract_basic_info_from_html(html_content, extraction_config, max_results).await {
             Ok(batch_result) => {
                 // AI返回的JSON响应被解析到batch_result.results中
-                // 我们需要将整个结果传递给解析函数
-                self.parse_ai_html_response_from_batch(batch_result)
+                // 我们需要将整个结果传递给解析函数；AI 可能不遵守数量要求，这里再截断一次
+                let mut results = self.parse_ai_html_response_from_batch(batch_result, page_url)?;
+                if let Some(max) = max_results {
+                    results.truncate(max);
+                }
+                Ok(results)
             }
             Err(e) => {
+                // 模型没有按要求返回JSON时，尝试从原始文本中找回磁力链接
+                if let Some(parse_error) = e.downcast_ref::<crate::llm_service::ExtractionParseError>() {
+                    let salvaged = salvage_basic_info_from_text(&parse_error.raw_response);
+                    if !salvaged.results.is_empty() {
+                        search_log!(warn, "AI返回的不是JSON，从原始文本中找回 {} 个磁力链接", salvaged.results.len());
+                        let mut results = self.parse_ai_html_response_from_batch(salvaged, page_url)?;
+                        if let Some(max) = max_results {
+                            results.truncate(max);
+                        }
+                        return Ok(results);
+                    }
+                }
+
                 search_log!(error, "AI HTML分析失败: {}", e);
                 search_log!(ai, "发送给AI的HTML长度: {} 字符", html_content.len());
                 search_log!(ai, "HTML前500字符预览: {}", safe_truncate(html_content, 500));
@@ -452,34 +1573,62 @@ impl GenericProvider {
     }
 
     /// 解析AI返回的HTML分析结果
-    fn parse_ai_html_response_from_batch(&self, batch_result: crate::llm_service::BatchExtractBasicInfoResult) -> Result<Vec<SearchResult>> {
+    fn parse_ai_html_response_from_batch(&self, batch_result: crate::llm_service::BatchExtractBasicInfoResult, page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
         // 直接从BatchExtractBasicInfoResult转换为SearchResult
         let mut results = Vec::new();
+        let mut seen_keys = std::collections::HashSet::new();
 
         for basic_info in batch_result.results {
             // 验证磁力链接格式
-            if !basic_info.magnet_link.starts_with("magnet:?xt=urn:btih:") {
-                println!("⚠️ Invalid magnet link format, skipping: {}", basic_info.magnet_link);
+            if !is_btih_magnet(&basic_info.magnet_link) {
+                trace_println!("⚠️ Invalid magnet link format, skipping: {}", basic_info.magnet_link);
+                continue;
+            }
+
+            // AI 偶尔重复返回同一个磁力链接，保留首次出现的条目
+            if !seen_keys.insert(crate::filter::dedupe_key(&basic_info.magnet_link)) {
+                trace_println!("⚠️ Duplicate magnet in AI output, skipping: {}", basic_info.magnet_link);
                 continue;
             }
 
+            // AI 偶尔返回空标题，按配置从磁力链接推导或直接丢弃
+            let title = clean_html_text(&basic_info.title);
+            let title = if !title.trim().is_empty() {
+                title
+            } else {
+                match self.empty_title_policy {
+                    EmptyTitlePolicy::DeriveFromMagnet => self.extract_title_from_magnet(&basic_info.magnet_link),
+                    EmptyTitlePolicy::Drop => {
+                        trace_println!("⚠️ Empty title from AI, skipping: {}", basic_info.magnet_link);
+                        continue;
+                    }
+                }
+            };
+
             // 第一阶段AI只提取基础信息，文件列表需要根据标题生成
-            let file_list = generate_file_list_from_title(&basic_info.title);
+            let file_list = generate_file_list_from_title(&title);
 
             // 处理 source_url：统一使用 normalize_source_url
             let source_url = basic_info
                 .source_url
-                .map(|href| self.normalize_source_url(&href));
+                .map(|href| self.normalize_source_url(&href, page_url));
+            let thumbnail_url = basic_info
+                .thumbnail_url
+                .filter(|src| !src.trim().is_empty())
+                .map(|src| self.normalize_source_url(src.trim(), page_url));
 
             results.push(SearchResult {
-                title: clean_html_text(&basic_info.title),
+                original_title: basic_info.title.trim().to_string(),
+                title,
                 magnet_link: basic_info.magnet_link,
                 file_size: basic_info.file_size,
                 upload_date: None, // 第一阶段不提取上传日期
                 file_list,
                 source_url,
-                score: None,
-                tags: None,
+                confidence: Some(basic_info.confidence.clamp(0.0, 1.0)),
+                thumbnail_url,
+                provider_name: self.name.clone(),
+                ..Default::default()
             });
         }
 
@@ -498,9 +1647,13 @@ impl GenericProvider {
     }
 
     /// 标准化source_url，将相对路径转换为绝对路径
-    fn normalize_source_url(&self, href: &str) -> String {
+    ///
+    /// 有搜索页的最终地址（重定向之后）时以它为基准，否则使用URL模板中的域名。
+    fn normalize_source_url(&self, href: &str, page_url: Option<&url::Url>) -> String {
         if href.starts_with("http://") || href.starts_with("https://") {
             href.to_string()
+        } else if let Some(resolved) = page_url.and_then(|base| base.join(href).ok()) {
+            resolved.to_string()
         } else if href.starts_with("/") {
             // 相对路径，需要从URL模板中提取基础域名
             self.extract_base_url_from_template()
@@ -511,6 +1664,18 @@ impl GenericProvider {
         }
     }
 
+    /// 取结果行中第一张图片的地址作为缩略图，懒加载图片优先使用 `data-src`，忽略内联的 data: 占位图
+    fn row_thumbnail(&self, row: &scraper::ElementRef, page_url: Option<&url::Url>) -> Option<String> {
+        row.select(&IMG_SELECTOR).find_map(|img| {
+            ["data-src", "data-original", "src"]
+                .iter()
+                .filter_map(|attr| img.value().attr(attr))
+                .map(str::trim)
+                .find(|src| !src.is_empty() && !src.starts_with("data:"))
+                .map(|src| self.normalize_source_url(src, page_url))
+        })
+    }
+
     // 注意：parse_ai_html_response 函数已被删除，因为现在直接使用 BatchExtractBasicInfoResult
 
     /// 分离优先结果和普通结果
@@ -525,7 +1690,7 @@ impl GenericProvider {
         });
 
         if !priority_results.is_empty() {
-            println!("🌟 Found {} priority results.", priority_results.len());
+            trace_println!("🌟 Found {} priority results.", priority_results.len());
         }
 
         (priority_results, regular_results)
@@ -534,22 +1699,89 @@ impl GenericProvider {
     // 注意：apply_detailed_ai_analysis 方法已被移除
     // 现在统一使用前端的并行分析流程，提供更好的用户体验
 
-    fn parse_generic_results(&self, html: &str) -> Result<Vec<SearchResult>> {
+    /// 按配置的 CSS 选择器解析结果页，缺少标题或磁力链接的条目会被跳过
+    fn parse_with_selectors(
+        &self,
+        html: &str,
+        selectors: &GenericSelectors,
+        page_url: Option<&url::Url>,
+        max_results: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        let parse = |selector: &str| Selector::parse(selector).map_err(|e| anyhow!("Invalid CSS selector '{}': {}", selector, e));
+        let row_selector = parse(&selectors.row)?;
+        let title_selector = parse(&selectors.title)?;
+        let magnet_selector = parse(&selectors.magnet)?;
+        let size_selector = selectors.size.as_deref().map(parse).transpose()?;
+        let date_selector = selectors.date.as_deref().map(parse).transpose()?;
+
         let document = Html::parse_document(html);
+        let text_of = |row: &scraper::ElementRef, selector: &Option<Selector>| {
+            selector
+                .as_ref()
+                .and_then(|s| row.select(s).next())
+                .map(|element| element.text().collect::<String>().trim().to_string())
+                .filter(|text| !text.is_empty())
+        };
+
         let mut results = Vec::new();
+        for row in document.select(&row_selector) {
+            if reached_limit(&results, max_results) {
+                break;
+            }
+
+            let Some(magnet_link) = row
+                .select(&magnet_selector)
+                .find_map(|element| element.value().attr("href"))
+                .filter(|href| is_btih_magnet(href))
+            else {
+                continue;
+            };
+            let Some(title_element) = row.select(&title_selector).next() else {
+                continue;
+            };
+
+            let raw_title = title_element.text().collect::<String>();
+            let title = clean_html_text(&raw_title);
+            let title = if title.trim().is_empty() { self.extract_title_from_magnet(magnet_link) } else { title };
+            let source_url = title_element
+                .value()
+                .attr("href")
+                .filter(|href| !href.starts_with("magnet:"))
+                .map(|href| self.normalize_source_url(href, page_url));
+            let file_list = generate_file_list_from_title(&title);
+
+            results.push(SearchResult {
+                original_title: raw_title.trim().to_string(),
+                thumbnail_url: self.row_thumbnail(&row, page_url),
+                title,
+                magnet_link: magnet_link.to_string(),
+                file_size: text_of(&row, &size_selector),
+                upload_date: text_of(&row, &date_selector),
+                file_list,
+                source_url,
+                provider_name: self.name.clone(),
+                ..Default::default()
+            });
+        }
 
-        println!("🔍 Parsing generic HTML content...");
+        Ok(results)
+    }
+
+    fn parse_generic_results(&self, html: &str, page_url: Option<&url::Url>, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let document = Html::parse_document(html);
+        let mut results = Vec::new();
 
-        // 尝试查找常见的磁力链接模式
-        let magnet_regex = regex::Regex::new(r"magnet:\?xt=urn:btih:[a-fA-F0-9]{40}[^&\s]*")
-            .map_err(|e| anyhow!("Invalid regex: {}", e))?;
+        trace_println!("🔍 Parsing generic HTML content...");
 
         // 尝试解析表格结构（最常见的种子站点布局）
         if let Ok(table_selector) = Selector::parse("table") {
-            for table in document.select(&table_selector) {
+            'tables: for table in document.select(&table_selector) {
                 if let Ok(row_selector) = Selector::parse("tr") {
                     for row in table.select(&row_selector) {
-                        if let Some(result) = self.parse_table_row(&row, &magnet_regex) {
+                        if reached_limit(&results, max_results) {
+                            break 'tables;
+                        }
+                        if let Some(result) = self.parse_table_row(&row, page_url) {
                             results.push(result);
                         }
                     }
@@ -559,19 +1791,19 @@ impl GenericProvider {
 
         // 如果表格解析没有结果，尝试通用解析
         if results.is_empty() {
-            results = self.parse_generic_fallback(&document, &magnet_regex)?;
+            results = self.parse_generic_fallback(&document, max_results)?;
         }
 
-        println!("📊 Extracted {} unique results from generic HTML", results.len());
+        trace_println!("📊 Extracted {} unique results from generic HTML", results.len());
         Ok(results)
     }
 
     /// 解析表格行，提取标题、磁力链接和文件大小
-    fn parse_table_row(&self, row: &scraper::ElementRef, magnet_regex: &regex::Regex) -> Option<SearchResult> {
+    fn parse_table_row(&self, row: &scraper::ElementRef, page_url: Option<&url::Url>) -> Option<SearchResult> {
         let row_html = row.html();
 
         // 查找磁力链接
-        let magnet_link = magnet_regex.find(&row_html)?.as_str().to_string();
+        let magnet_link = find_magnet_links(&row_html).next()?;
 
         // 提取单元格
         let cell_selector = Selector::parse("td").ok()?;
@@ -582,6 +1814,7 @@ impl GenericProvider {
         }
 
         let mut title = None;
+        let mut raw_title = None;
         let mut file_size = None;
         let mut upload_date = None;
         let mut source_url = None;
@@ -597,9 +1830,10 @@ impl GenericProvider {
                         let link_text = link.text().collect::<String>().trim().to_string();
                         if !link_text.is_empty() && !link_text.starts_with("magnet:") {
                             title = Some(clean_html_text(&link_text));
+                            raw_title = Some(link_text);
                             // 提取source_url
                             if let Some(href) = link.value().attr("href") {
-                                source_url = Some(self.normalize_source_url(href));
+                                source_url = Some(self.normalize_source_url(href, page_url));
                             }
                         }
                     }
@@ -607,6 +1841,7 @@ impl GenericProvider {
                 // 如果没有链接，使用单元格文本
                 if title.is_none() && !cell_text.is_empty() && cell_text.len() > 5 {
                     title = Some(clean_html_text(&cell_text));
+                    raw_title = Some(cell_text.clone());
                 }
             }
 
@@ -627,38 +1862,38 @@ impl GenericProvider {
         let file_list = generate_file_list_from_title(&final_title);
 
         Some(SearchResult {
+            original_title: raw_title.unwrap_or_else(|| final_title.clone()),
+            thumbnail_url: self.row_thumbnail(row, page_url),
             title: final_title,
             magnet_link,
             file_size,
             upload_date,
             file_list,
             source_url,
-            score: None,
-            tags: None,
+            provider_name: self.name.clone(),
+            ..Default::default()
         })
     }
 
     /// 通用回退解析方法
-    fn parse_generic_fallback(&self, document: &Html, magnet_regex: &regex::Regex) -> Result<Vec<SearchResult>> {
+    fn parse_generic_fallback(&self, document: &Html, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
         let mut results = Vec::new();
         let mut seen_magnets = std::collections::HashSet::new();
 
-        for magnet_match in magnet_regex.find_iter(&document.html()) {
-            let magnet_link = magnet_match.as_str();
-
-            if seen_magnets.insert(magnet_link.to_string()) {
-                let title = self.extract_title_from_magnet(magnet_link);
+        for magnet_link in find_magnet_links(&document.html()) {
+            if reached_limit(&results, max_results) {
+                break;
+            }
+            if seen_magnets.insert(magnet_link.clone()) {
+                let title = self.extract_title_from_magnet(&magnet_link);
                 let file_list = generate_file_list_from_title(&title);
 
                 results.push(SearchResult {
                     title,
-                    magnet_link: magnet_link.to_string(),
-                    file_size: None,
-                    upload_date: None,
+                    magnet_link,
                     file_list,
-                    source_url: None,
-                    score: None,
-                    tags: None,
+                    provider_name: self.name.clone(),
+                    ..Default::default()
                 });
             }
         }
@@ -671,7 +1906,7 @@ impl GenericProvider {
         let text_upper = text.to_uppercase();
         (text_upper.contains("GB") || text_upper.contains("MB") ||
          text_upper.contains("KB") || text_upper.contains("TB")) &&
-        text.chars().any(|c| c.is_ascii_digit())
+        crate::filter::parse_size_to_bytes(text).is_some()
     }
 
     /// 判断文本是否是日期
@@ -684,26 +1919,11 @@ impl GenericProvider {
     /// 从磁力链接的dn参数中提取标题
     fn extract_title_from_magnet(&self, magnet_link: &str) -> String {
         // 尝试从磁力链接的dn参数中提取文件名
-        if let Some(dn_start) = magnet_link.find("&dn=") {
-            let dn_part = &magnet_link[dn_start + 4..];
-            if let Some(dn_end) = dn_part.find('&') {
-                let dn_value = &dn_part[..dn_end];
-                // URL解码
-                if let Ok(decoded) = urlencoding::decode(dn_value) {
-                    let decoded_str = decoded.to_string();
-                    if !decoded_str.is_empty() && decoded_str.len() > 5 {
-                        return decoded_str;
-                    }
-                }
-            } else {
-                // dn是最后一个参数
-                if let Ok(decoded) = urlencoding::decode(dn_part) {
-                    let decoded_str = decoded.to_string();
-                    if !decoded_str.is_empty() && decoded_str.len() > 5 {
-                        return decoded_str;
-                    }
-                }
-            }
+        let dn_value = magnet_link
+            .split_once('?')
+            .and_then(|(_, query)| query.split('&').find_map(|param| param.strip_prefix("dn=")));
+        if let Some(title) = dn_value.and_then(clean_magnet_display_name) {
+            return title;
         }
 
         // 如果无法从dn参数提取，生成一个基于哈希的标题
@@ -723,6 +1943,44 @@ impl GenericProvider {
     }
 }
 
+/// 解码并清理磁力链接的 dn 参数（`+` 表示空格），清理后为空时返回 None
+///
+/// 解码后按默认的标题清理步骤（`filter::clean_title`）去掉方括号中的推广标记和网址。
+fn clean_magnet_display_name(raw: &str) -> Option<String> {
+    let spaced = raw.replace('+', " ");
+    let decoded = urlencoding::decode(&spaced)
+        .map(|d| d.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&urlencoding::decode_binary(spaced.as_bytes())).into_owned());
+
+    let cleaned = crate::filter::clean_title(&clean_html_text(&decoded), &crate::filter::default_title_cleaning_steps());
+    let cleaned = cleaned.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.' | '|'));
+
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
+
+/// 解析 "文件名 大小" 格式的文件条目，返回文件名以及识别到的大小
+fn split_file_entry(file_text: &str) -> Option<(String, Option<String>)> {
+    let file_text = file_text.trim();
+    if file_text.is_empty() {
+        return None;
+    }
+
+    // 分割文件名和大小，大小通常在最后
+    let parts: Vec<&str> = file_text.split_whitespace().collect();
+    if parts.len() >= 2 {
+        // 检查最后一部分是否是文件大小（包含 GB, MB, KB 等）
+        let last_part = parts[parts.len() - 1];
+        if last_part.contains("GB") || last_part.contains("MB") || last_part.contains("KB") || last_part.contains("TB") {
+            // 文件名是除了最后一部分的所有内容
+            let filename = parts[..parts.len() - 1].join(" ");
+            return Some((filename, Some(last_part.to_string())));
+        }
+    }
+
+    // 如果没有识别到大小，就把整个文本作为文件名
+    Some((file_text.to_string(), None))
+}
+
 /// 根据标题生成相关的文件列表
 fn generate_file_list_from_title(title: &str) -> Vec<String> {
     let mut file_list = Vec::new();
@@ -784,7 +2042,7 @@ fn generate_file_list_from_title(title: &str) -> Vec<String> {
 
 /// 从标题中提取干净的名称（移除特殊字符和格式信息）
 /// 用途：用于搜索解析阶段生成稳定的文件名，尽量保证可预测与无特殊字符。
-/// 注意：展示给用户的标题清理应使用 `clean_title_unified`（main.rs）。
+/// 注意：展示给用户的标题清理应使用 `filter::clean_title`。
 fn extract_clean_title(title: &str) -> String {
     let mut clean_title = title.to_string();
 
@@ -819,245 +2077,3228 @@ fn extract_clean_title(title: &str) -> String {
     }
 }
 
-/// 搜索引擎核心
-pub struct SearchCore {
-    providers: Vec<Arc<dyn SearchProvider>>,
+/// 详情页补全的默认最大并发数
+pub const DEFAULT_ENRICH_CONCURRENCY: usize = 4;
+
+/// 单条结果详情页补全的默认时限
+pub const DEFAULT_ENRICH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 流式搜索通道的缓冲批次数
+const SEARCH_STREAM_BUFFER: usize = 16;
+
+/// 多关键词搜索时同时进行的关键词数量（每个关键词内部仍并发搜索各提供商）
+const KEYWORD_CONCURRENCY: usize = 2;
+
+/// 要搜索的页码范围（含首尾，页码从1开始）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    pub start: u32,
+    pub end: u32,
 }
 
-impl SearchCore {
-    // 注意：基础构造函数已被删除，统一使用 create_ai_enhanced_search_core
+impl PageRange {
+    /// 创建页码范围，起始页至少为1，结束页不小于起始页
+    pub fn new(start: u32, end: u32) -> Self {
+        let start = start.max(1);
+        Self { start, end: end.max(start) }
+    }
 
-    /// 多页搜索 - 按提供商顺序搜索，优先返回clmclm结果
-    pub async fn search_multi_page(&self, query: &str, max_pages: u32) -> Result<Vec<SearchResult>> {
-        if self.providers.is_empty() {
-            return Err(anyhow!("No search providers available"));
-        }
+    /// 范围内的全部页码
+    pub fn pages(&self) -> std::ops::RangeInclusive<u32> {
+        self.start..=self.end
+    }
+}
 
-        println!("🔍 Starting search with {} providers, {} pages each", self.providers.len(), max_pages);
+/// 兼容旧的 `max_pages` 参数：搜索第 1 页到第 `max_pages` 页
+impl From<u32> for PageRange {
+    fn from(max_pages: u32) -> Self {
+        Self { start: 1, end: max_pages }
+    }
+}
 
-        let mut all_results = Vec::new();
+/// 每次请求前的随机等待，避免并发请求的时间过于整齐
+pub struct RequestJitter {
+    max_ms: u64,
+    state: Mutex<u64>,
+}
 
-        // 分离clmclm和其他提供商
-        let mut clmclm_provider = None;
-        let mut other_providers = Vec::new();
+impl RequestJitter {
+    /// 使用指定种子创建，相同种子产生相同的等待序列
+    pub fn with_seed(max_ms: u64, seed: u64) -> Self {
+        Self { max_ms, state: Mutex::new(seed) }
+    }
 
-        for provider in &self.providers {
-            if provider.name() == "clmclm.com" {
-                clmclm_provider = Some(Arc::clone(provider));
-            } else {
-                other_providers.push(Arc::clone(provider));
-            }
-        }
+    /// 使用当前时间作为种子
+    pub fn new(max_ms: u64) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::with_seed(max_ms, seed)
+    }
 
-        // 1. 首先搜索clmclm（如果启用）
-        if let Some(clmclm) = clmclm_provider {
-            println!("🔍 Searching clmclm.com first for faster results");
-            for page in 1..=max_pages {
-                match clmclm.search(query, page).await {
-                    Ok(mut results) => {
-                        let count = results.len();
-                        println!("✅ clmclm.com page {page} returned {count} results");
-                        all_results.append(&mut results);
-                    }
-                    Err(e) => {
-                        println!("❌ clmclm.com page {page} failed: {e}");
-                    }
-                }
-            }
-        }
+    /// 下一次请求前的等待时间，范围为 0..=max_ms
+    pub fn next_delay(&self) -> Duration {
+        // SplitMix64
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Duration::from_millis(z % self.max_ms.saturating_add(1))
+    }
+}
 
-        // 2. 然后并发搜索其他提供商
-        if !other_providers.is_empty() {
-            println!("🔍 Now searching {} other providers concurrently", other_providers.len());
+/// 单个提供商在一次搜索中的请求统计
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProviderMetrics {
+    pub name: String,
+    pub requests: u32,
+    pub successes: u32,
+    pub failures: u32,
+    pub total_latency_ms: u64,
+    pub results_count: usize,
+    /// 响应体不小于 `MIN_PARSEABLE_BODY_BYTES` 却没有解析出任何结果的页面数，通常意味着页面结构已经变化
+    #[serde(default)]
+    pub empty_parses: u32,
+    /// 这些解析为空的页面中最大的响应体字节数
+    #[serde(default)]
+    pub empty_parse_bytes: usize,
+    /// 解析出结果的页面数
+    #[serde(default)]
+    pub productive_pages: u32,
+    /// 解析出结果的页面的响应体总字节数，用于估计该站点正常结果页的大小
+    #[serde(default)]
+    pub productive_body_bytes: u64,
+}
 
-            let mut other_search_futures = Vec::new();
+/// 判断“解析为空”时响应体的最小字节数，更小的页面多半本来就没有结果
+pub const MIN_PARSEABLE_BODY_BYTES: usize = 4 * 1024;
 
-            for provider in other_providers {
-                for page in 1..=max_pages {
-                    let provider = Arc::clone(&provider);
-                    let query = query.to_string();
-                    let provider_name = provider.name().to_string();
+/// 最近一次命令级搜索中各提供商的统计
+///
+/// 每次命令级搜索开始时由 `reset_last_search_metrics` 清空，之后该次搜索的各个 `SearchCore`
+/// （如同时进行的 clmclm 与其他引擎搜索）在 `search_stream` 结束时写入各自的累计统计。
+static LAST_SEARCH_METRICS: Lazy<Mutex<BTreeMap<String, ProviderMetrics>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
 
-                    let search_future = async move {
-                        println!("🔍 Searching {query} page {page} with provider: {provider_name}");
-                        match provider.search(&query, page).await {
-                            Ok(results) => {
-                                let count = results.len();
-                                println!("✅ Provider {provider_name} page {page} returned {count} results");
-                                Ok(results)
+/// 应用退出信号，置为 true 后进行中的搜索立即结束
+static SEARCH_SHUTDOWN: Lazy<tokio::sync::watch::Sender<bool>> = Lazy::new(|| tokio::sync::watch::channel(false).0);
+
+/// 取消所有进行中的搜索（应用退出时调用），已收到的结果照常返回
+pub fn cancel_all_searches() {
+    SEARCH_SHUTDOWN.send_replace(true);
+}
+
+/// 等待应用退出信号
+async fn shutdown_requested() {
+    let mut shutdown = SEARCH_SHUTDOWN.subscribe();
+    // 发送端为静态变量不会被释放，等待只会因收到信号而结束
+    let _ = shutdown.wait_for(|&cancelled| cancelled).await;
+}
+
+/// 获取各提供商最近一次搜索的统计
+pub fn last_search_metrics() -> Vec<ProviderMetrics> {
+    LAST_SEARCH_METRICS.lock().unwrap().values().cloned().collect()
+}
+
+/// 开始新的命令级搜索，丢弃之前搜索的统计（包括之后被禁用的引擎）
+pub fn reset_last_search_metrics() {
+    *LAST_SEARCH_METRICS.lock().unwrap() = BTreeMap::new();
+}
+
+/// 是否已经收集到 `max_results` 条结果
+fn reached_limit(results: &[SearchResult], max_results: Option<usize>) -> bool {
+    max_results.is_some_and(|max| results.len() >= max)
+}
+
+/// 流式搜索中某个提供商单页完成后产生的一批结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SearchBatch {
+    pub provider_name: String,
+    /// 提供商在搜索顺序中的位置（clmclm 在最前），用于稳定排序
+    pub provider_index: usize,
+    pub page: u32,
+    pub results: Vec<SearchResult>,
+}
+
+/// 按 (提供商位置, 页码) 排序后合并各批结果，使最终顺序与网络耗时无关，并按合并后的位置记录 `original_rank`
+pub fn merge_batches(mut batches: Vec<SearchBatch>) -> Vec<SearchResult> {
+    batches.sort_by_key(|batch| (batch.provider_index, batch.page));
+    let mut results: Vec<SearchResult> = batches.into_iter().flat_map(|batch| batch.results).collect();
+    assign_original_ranks(&mut results);
+    results
+}
+
+/// 按当前位置记录 `original_rank`，供排序时作为主键相同时的次序依据
+pub fn assign_original_ranks(results: &mut [SearchResult]) {
+    for (rank, result) in results.iter_mut().enumerate() {
+        result.original_rank = rank;
+    }
+}
+
+/// 快速搜索中留待后台 AI 提取的结果页
+pub struct DeferredPage {
+    provider: Arc<dyn SearchProvider>,
+    provider_index: usize,
+    page: u32,
+    html: DeferredHtml,
+}
+
+/// 结果分页缓存最多保留的搜索数，超出时丢弃最早的搜索
+const MAX_PENDING_RESULT_SEARCHES: usize = 16;
+
+/// 返回给前端的一页结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResultPage {
+    pub search_id: String,
+    /// 从 1 开始的页码
+    pub page: usize,
+    pub total_pages: usize,
+    pub results: Vec<SearchResult>,
+}
+
+/// 同一次搜索的结果按 `page_size` 分页后缓存，首页随搜索命令返回，其余页由前端按需取回
+///
+/// 抓取多少结果与前端一次渲染多少结果互不影响；已取完的搜索会从缓存中移除。
+#[derive(Default)]
+pub struct ResultPager {
+    pending: Mutex<std::collections::VecDeque<PendingPages>>,
+}
+
+/// 某次搜索尚未取回的结果页
+struct PendingPages {
+    search_id: String,
+    total_pages: usize,
+    pages: std::collections::VecDeque<Vec<SearchResult>>,
+}
+
+impl ResultPager {
+    /// 将结果按 `page_size`（至少为 1）分页，返回首页并缓存其余页
+    pub fn paginate(&self, results: Vec<SearchResult>, page_size: usize) -> ResultPage {
+        let mut pages: std::collections::VecDeque<Vec<SearchResult>> =
+            results.chunks(page_size.max(1)).map(<[SearchResult]>::to_vec).collect();
+        let total_pages = pages.len().max(1);
+        let first = pages.pop_front().unwrap_or_default();
+        let search_id = crate::trace::new_search_id();
+
+        if !pages.is_empty() {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.len() >= MAX_PENDING_RESULT_SEARCHES {
+                pending.pop_front();
+            }
+            pending.push_back(PendingPages { search_id: search_id.clone(), total_pages, pages });
+        }
+
+        ResultPage { search_id, page: 1, total_pages, results: first }
+    }
+
+    /// 取出某次搜索的下一页，没有更多结果或搜索已过期时返回 None
+    pub fn next_page(&self, search_id: &str) -> Option<ResultPage> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(|entry| entry.search_id == search_id)?;
+        let entry = &mut pending[index];
+        let total_pages = entry.total_pages;
+        let results = entry.pages.pop_front()?;
+        let page = total_pages - entry.pages.len();
+        if entry.pages.is_empty() {
+            pending.remove(index);
+        }
+        Some(ResultPage { search_id: search_id.to_string(), page, total_pages, results })
+    }
+}
+
+/// 引擎自检中单个引擎的状态
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestStatus {
+    /// 返回了结果，解析正常
+    Ok,
+    /// 请求成功但没有解析出结果，页面结构很可能已经变化
+    Empty,
+    /// 请求失败
+    Failed,
+}
+
+/// 引擎自检中单个引擎的结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EngineSelfTest {
+    pub name: String,
+    pub status: SelfTestStatus,
+    pub result_count: usize,
+    pub error: Option<String>,
+}
+
+/// 两个引擎搜索同一关键词的对比结果，重合按 infohash 计算
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EngineComparison {
+    pub engine_a: String,
+    pub engine_b: String,
+    pub results_a: Vec<SearchResult>,
+    pub results_b: Vec<SearchResult>,
+    /// 两个引擎都找到的资源数
+    pub overlap: usize,
+    pub unique_to_a: usize,
+    pub unique_to_b: usize,
+}
+
+/// 标题的默认最大长度（字符数），足够大以保证默认不截断正常标题
+pub const DEFAULT_MAX_TITLE_LENGTH: usize = 500;
+
+/// 搜索引擎核心
+pub struct SearchCore {
+    providers: Vec<Arc<dyn SearchProvider>>,
+    priority_keywords: Vec<String>,
+    deep_enrich: bool,
+    enrich_concurrency: usize,
+    enrich_timeout: Option<Duration>,
+    require_source_url: bool,
+    min_confidence: Option<f32>,
+    min_seeders: Option<u32>,
+    keep_unknown_seeders: bool,
+    deadline: Option<Duration>,
+    metrics: Mutex<BTreeMap<String, ProviderMetrics>>,
+    jitter: Option<RequestJitter>,
+    max_results: Option<usize>,
+    quality_weights: crate::filter::QualityWeights,
+    fuzzy_dedupe_threshold: Option<f64>,
+    max_title_length: usize,
+    size_precision: Option<usize>,
+    preferences: crate::filter::PreferenceWeights,
+    suspicion_checks: Vec<crate::filter::SuspicionCheck>,
+    strict_magnet_validation: bool,
+    max_total_results: Option<usize>,
+    allowed_languages: Vec<crate::filter::Lang>,
+}
+
+impl SearchCore {
+    // 注意：基础构造函数已被删除，统一使用 create_ai_enhanced_search_core
+
+    /// 设置是否访问详情页补全文件列表
+    pub fn with_deep_enrich(mut self, enabled: bool) -> Self {
+        self.deep_enrich = enabled;
+        self
+    }
+
+    /// 设置详情页补全的最大并发数（至少为 1）和单条结果的时限，时限为 None 表示不限制
+    pub fn with_enrich_limits(mut self, concurrency: usize, timeout: Option<Duration>) -> Self {
+        self.enrich_concurrency = concurrency.max(1);
+        self.enrich_timeout = timeout;
+        self
+    }
+
+    /// 设置整个搜索的总时限，None 表示不限制
+    pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// 设置每次请求前的最大随机等待时间（毫秒），为 0 时不等待
+    pub fn with_request_jitter(mut self, max_ms: u64) -> Self {
+        self.jitter = (max_ms > 0).then(|| RequestJitter::new(max_ms));
+        self
+    }
+
+    /// 设置每个提供商单页的结果数量提示，None 表示不限制
+    pub fn with_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// 设置计算质量分时各项信号的权重
+    pub fn with_quality_weights(mut self, weights: crate::filter::QualityWeights) -> Self {
+        self.quality_weights = weights;
+        self
+    }
+
+    /// 设置按标题相似度合并重复资源的阈值（0.0-1.0），None 表示只按 infohash 去重
+    pub fn with_fuzzy_dedupe(mut self, threshold: Option<f64>) -> Self {
+        self.fuzzy_dedupe_threshold = threshold;
+        self
+    }
+
+    /// 设置标题的最大字符数，超出的标题在单词边界截断，完整标题保留在 `original_title`
+    pub fn with_max_title_length(mut self, max_chars: usize) -> Self {
+        self.max_title_length = max_chars.max(1);
+        self
+    }
+
+    /// 设置统一显示大小时保留的小数位数，None 表示保留各站点原有的大小文本
+    pub fn with_size_precision(mut self, precision: Option<usize>) -> Self {
+        self.size_precision = precision;
+        self
+    }
+
+    /// 设置从收藏中学到的偏好，最终结果按偏好加分重排
+    pub fn with_preferences(mut self, preferences: crate::filter::PreferenceWeights) -> Self {
+        self.preferences = preferences;
+        self
+    }
+
+    /// 设置启用的可疑资源检查项，为空时不标记
+    pub fn with_suspicion_checks(mut self, checks: Vec<crate::filter::SuspicionCheck>) -> Self {
+        self.suspicion_checks = checks;
+        self
+    }
+
+    /// 启用后丢弃 infohash 不是40位十六进制或32位 base32 的结果
+    pub fn with_strict_magnet_validation(mut self, enabled: bool) -> Self {
+        self.strict_magnet_validation = enabled;
+        self
+    }
+
+    /// 设置一次搜索合并后返回的结果总数上限，None 表示不限制
+    pub fn with_max_total_results(mut self, max_total_results: Option<usize>) -> Self {
+        self.max_total_results = max_total_results;
+        self
+    }
+
+    /// 只保留标题文字属于指定语言的结果，为空时不过滤
+    pub fn with_allowed_languages(mut self, languages: Vec<crate::filter::Lang>) -> Self {
+        self.allowed_languages = languages;
+        self
+    }
+
+    /// 设置是否丢弃没有详情页链接的结果
+    pub fn with_require_source_url(mut self, enabled: bool) -> Self {
+        self.require_source_url = enabled;
+        self
+    }
+
+    /// 设置 AI 提取可信度的下限，低于下限的结果会被丢弃，None 表示不过滤
+    pub fn with_min_confidence(mut self, min_confidence: Option<f32>) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// 设置最低做种数，None 表示不过滤；`keep_unknown` 决定做种数未知的结果是否保留
+    pub fn with_min_seeders(mut self, min: Option<u32>, keep_unknown: bool) -> Self {
+        self.min_seeders = min;
+        self.keep_unknown_seeders = keep_unknown;
+        self
+    }
+
+    /// 按历史请求耗时的中位数（毫秒，键为提供商名称）从快到慢排列提供商
+    ///
+    /// 没有耗时记录的提供商排在最后并保持配置中的顺序；clmclm 仍然最先单独搜索。
+    pub fn with_latency_order(mut self, latencies: &BTreeMap<String, u64>) -> Self {
+        if !latencies.is_empty() {
+            self.providers
+                .sort_by_key(|provider| latencies.get(provider.name()).map_or((1, 0), |latency| (0, *latency)));
+        }
+        self
+    }
+
+    /// 按名称查找已配置的提供商
+    pub fn provider(&self, name: &str) -> Option<Arc<dyn SearchProvider>> {
+        self.providers.iter().find(|p| p.name() == name).cloned()
+    }
+
+    /// 用给定关键词搜索每个提供商的第一页，检查解析是否仍然有效
+    ///
+    /// 与连通性检查不同，请求成功但没有结果的引擎标记为 `Empty`，提示其页面结构可能已经变化。
+    pub async fn self_test(&self, keyword: &str) -> Vec<EngineSelfTest> {
+        join_all(self.providers.iter().map(|provider| async move {
+            let name = provider.name().to_string();
+            match provider.search(keyword, 1, None).await {
+                Ok(results) if results.is_empty() => {
+                    trace_println!("⚠️ Self-test: {name} returned no results");
+                    EngineSelfTest { name, status: SelfTestStatus::Empty, result_count: 0, error: None }
+                }
+                Ok(results) => {
+                    trace_println!("✅ Self-test: {name} returned {} results", results.len());
+                    EngineSelfTest { name, status: SelfTestStatus::Ok, result_count: results.len(), error: None }
+                }
+                Err(e) => {
+                    trace_println!("❌ Self-test: {name} failed: {e}");
+                    EngineSelfTest { name, status: SelfTestStatus::Failed, result_count: 0, error: Some(e.to_string()) }
+                }
+            }
+        }))
+        .await
+    }
+
+    /// 只搜索指定提供商的各页，结果同样经过去重与过滤
+    ///
+    /// 提供商未配置时返回错误；某一页失败时返回该错误，避免对比时把失败误当作没有结果。
+    pub async fn search_single_provider(&self, name: &str, query: &str, pages: impl Into<PageRange>) -> Result<Vec<SearchResult>> {
+        let provider = self.provider(name).ok_or_else(|| anyhow!("Provider {name} is not configured"))?;
+        let mut results = Vec::new();
+        for page in pages.into().pages() {
+            let request_id = format!("{name}:p{page}");
+            let page_results = crate::trace::with_request_id(request_id, self.search_page(&provider, query, page)).await?;
+            let mut page_results = self.enrich_results(&provider, page_results).await;
+            self.apply_highlights(query, &mut page_results);
+            results.extend(page_results);
+        }
+        assign_original_ranks(&mut results);
+        Ok(self.finalize_results(results))
+    }
+
+    /// 用同一关键词分别搜索两个提供商，统计按 infohash 计算的重合与各自独有的资源数
+    pub async fn compare_providers(&self, engine_a: &str, engine_b: &str, query: &str, pages: impl Into<PageRange>) -> Result<EngineComparison> {
+        let pages = pages.into();
+        let (results_a, results_b) = crate::trace::in_search_scope(async {
+            futures::try_join!(
+                self.search_single_provider(engine_a, query, pages),
+                self.search_single_provider(engine_b, query, pages),
+            )
+        })
+        .await?;
+
+        let keys_a: std::collections::HashSet<String> =
+            results_a.iter().map(|r| crate::filter::dedupe_key(&r.magnet_link)).collect();
+        let keys_b: std::collections::HashSet<String> =
+            results_b.iter().map(|r| crate::filter::dedupe_key(&r.magnet_link)).collect();
+        let overlap = keys_a.intersection(&keys_b).count();
+        trace_println!("⚖️ Compared {engine_a} ({}) with {engine_b} ({}): {overlap} shared", keys_a.len(), keys_b.len());
+
+        Ok(EngineComparison {
+            engine_a: engine_a.to_string(),
+            engine_b: engine_b.to_string(),
+            unique_to_a: keys_a.len() - overlap,
+            unique_to_b: keys_b.len() - overlap,
+            overlap,
+            results_a,
+            results_b,
+        })
+    }
+
+    /// 快速搜索：各提供商的各页并发请求，只做不依赖 AI 的解析后立即返回整理好的结果
+    ///
+    /// 需要 AI 提取的页面一并返回，交给 `refine_deferred` 在后台处理。单个请求失败不影响其他请求，全部失败时返回最后一个错误。
+    pub async fn quick_search(&self, query: &str, pages: impl Into<PageRange>) -> Result<(Vec<SearchResult>, Vec<DeferredPage>)> {
+        let pages = pages.into();
+        let requests = self.providers.iter().enumerate().flat_map(|(provider_index, provider)| {
+            pages.pages().map(move |page| async move {
+                let request_id = format!("{}:p{page}", provider.name());
+                let outcome = crate::trace::with_request_id(request_id, provider.search_quick(query, page, self.max_results)).await;
+                (provider_index, provider, page, outcome)
+            })
+        });
+        let outcomes = crate::trace::in_search_scope(join_all(requests)).await;
+
+        let total = outcomes.len();
+        let mut batches = Vec::new();
+        let mut deferred = Vec::new();
+        let mut last_error = None;
+        for (provider_index, provider, page, outcome) in outcomes {
+            match outcome {
+                Ok(mut quick) => {
+                    self.apply_highlights(query, &mut quick.results);
+                    if let Some(html) = quick.deferred {
+                        deferred.push(DeferredPage { provider: Arc::clone(provider), provider_index, page, html });
+                    }
+                    batches.push(SearchBatch { provider_name: provider.name().to_string(), provider_index, page, results: quick.results });
+                }
+                Err(e) => {
+                    trace_println!("❌ Quick search on {} page {page} failed: {e}", provider.name());
+                    last_error = Some(e);
+                }
+            }
+        }
+        if batches.is_empty() && total > 0 {
+            return Err(last_error.unwrap_or_else(|| anyhow!("Quick search failed")));
+        }
+
+        let results = self.finalize_results(merge_batches(batches));
+        trace_println!("⚡ Quick search returned {} results, {} pages waiting for AI extraction", results.len(), deferred.len());
+        Ok((results, deferred))
+    }
+
+    /// 对快速搜索留下的页面进行 AI 提取，每完成一页就发送一批整理后的结果；失败的页面只记录日志
+    pub async fn refine_deferred(&self, query: &str, deferred: Vec<DeferredPage>, tx: mpsc::Sender<SearchBatch>) {
+        let max_results = self.max_results;
+        let tx = &tx;
+        stream::iter(deferred)
+            .for_each_concurrent(None, |page| async move {
+                let request_id = format!("{}:p{}:ai", page.provider.name(), page.page);
+                let refined = crate::trace::with_request_id(request_id, page.provider.refine_quick(&page.html, max_results)).await;
+                match refined {
+                    Ok(mut results) if !results.is_empty() => {
+                        self.apply_highlights(query, &mut results);
+                        let results = self.finalize_results(results);
+                        trace_println!("✨ Refined {} page {} with {} AI results", page.provider.name(), page.page, results.len());
+                        let batch = SearchBatch {
+                            provider_name: page.provider.name().to_string(),
+                            provider_index: page.provider_index,
+                            page: page.page,
+                            results,
+                        };
+                        let _ = tx.send(batch).await;
+                    }
+                    Ok(_) => trace_println!("⚠️ AI extraction found nothing on {} page {}", page.provider.name(), page.page),
+                    Err(e) => trace_println!("❌ AI extraction on {} page {} failed: {e}", page.provider.name(), page.page),
+                }
+            })
+            .await;
+    }
+
+    /// 按设置校验、去重并过滤结果，不做排序与展示相关的处理
+    ///
+    /// 边搜索边分析时每批结果先经过这里，避免分析最终会被丢弃的结果。
+    pub fn filter_results(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let results = self.enforce_magnet_validation(results);
+
+        // 按 infohash 去重，并记录每个结果的全部来源（同时补全缺失的 source_url）
+        let before_dedupe = results.len();
+        let mut results = crate::filter::dedupe_results(results);
+        if results.len() < before_dedupe {
+            trace_println!("🧹 Merged {} duplicate results", before_dedupe - results.len());
+        }
+
+        if let Some(threshold) = self.fuzzy_dedupe_threshold {
+            let before = results.len();
+            results = crate::filter::fuzzy_dedupe_results(results, threshold);
+            if results.len() < before {
+                trace_println!("🧹 Merged {} results with similar titles", before - results.len());
+            }
+        }
+
+        if self.require_source_url {
+            let before = results.len();
+            results.retain(|r| r.source_url.is_some());
+            if results.len() < before {
+                trace_println!("🧹 Dropped {} results without a source URL", before - results.len());
+            }
+        }
+
+        if let Some(min_confidence) = self.min_confidence {
+            let before = results.len();
+            results = crate::filter::filter_by_confidence(results, min_confidence);
+            if results.len() < before {
+                trace_println!("🧹 Dropped {} low-confidence AI results", before - results.len());
+            }
+        }
+
+        if let Some(min_seeders) = self.min_seeders {
+            let before = results.len();
+            results = crate::filter::filter_by_min_seeders(results, min_seeders, self.keep_unknown_seeders);
+            if results.len() < before {
+                trace_println!("🧹 Dropped {} results with fewer than {} seeders", before - results.len(), min_seeders);
+            }
+        }
+
+        if !self.allowed_languages.is_empty() {
+            let before = results.len();
+            results = crate::filter::filter_by_language(results, &self.allowed_languages);
+            if results.len() < before {
+                trace_println!("🧹 Dropped {} results in other languages", before - results.len());
+            }
+        }
+
+        results
+    }
+
+    /// 对收集到的全部结果去重，并按设置过滤，最后按全局上限截断
+    ///
+    /// 所有返回给前端的搜索结果都经过这里，全局上限因此对各个搜索入口都生效。
+    pub fn finalize_results(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut results = self.filter_results(results);
+
+        for result in &mut results {
+            // 没有记录原始标题的提供商，以当前标题作为原始标题
+            if result.original_title.is_empty() {
+                result.original_title = result.title.clone();
+            }
+            if let Some(truncated) = crate::filter::truncate_title(&result.title, self.max_title_length) {
+                // 高亮在截断前按完整标题计算，需要裁到截断后的标题内
+                crate::filter::clamp_highlights(&mut result.highlights, &truncated);
+                result.title = truncated;
+            }
+            result.apply_magnet_params();
+            if result.upload_timestamp.is_none() {
+                result.upload_timestamp = result.upload_date.as_deref().and_then(crate::filter::parse_upload_timestamp);
+            }
+        }
+
+        if let Some(precision) = self.size_precision {
+            crate::filter::normalize_size_display(&mut results, precision);
+        }
+
+        crate::filter::apply_quality_scores(&mut results, &self.quality_weights);
+        crate::filter::apply_suspicion_flags(&mut results, &self.suspicion_checks);
+        crate::filter::rerank_by_preferences(&mut results, &self.preferences);
+        self.apply_result_ceiling(&mut results);
+        results
+    }
+
+    /// 按全局上限截断合并、排序后的结果，保留排在前面的结果
+    fn apply_result_ceiling(&self, results: &mut Vec<SearchResult>) {
+        let Some(max_total) = self.max_total_results else {
+            return;
+        };
+        if results.len() > max_total {
+            trace_println!("✂️ Truncated results to the global limit of {}, dropped {}", max_total, results.len() - max_total);
+            results.truncate(max_total);
+        }
+    }
+
+    /// 严格模式下丢弃 infohash 格式不正确的结果，宽松模式下原样返回
+    fn enforce_magnet_validation(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        if !self.strict_magnet_validation {
+            return results;
+        }
+        let before = results.len();
+        let results = crate::filter::filter_invalid_infohashes(results);
+        if results.len() < before {
+            trace_println!("🧹 Dropped {} results with malformed infohashes", before - results.len());
+        }
+        results
+    }
+
+    /// 启用深度补全时，由结果所属的提供商逐条补全（有限并发，保持原有顺序）
+    ///
+    /// 补全在结果的副本上进行，失败或超时的结果保持补全前的文件列表，不影响整个搜索。
+    async fn enrich_results(&self, provider: &Arc<dyn SearchProvider>, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        if !self.deep_enrich || results.is_empty() {
+            return results;
+        }
+
+        let timeout = self.enrich_timeout;
+        stream::iter(results.into_iter().map(|result| {
+            let provider = Arc::clone(provider);
+            async move {
+                let mut enriched = result.clone();
+                let outcome = match timeout {
+                    None => provider.enrich(&mut enriched).await,
+                    Some(timeout) => tokio::time::timeout(timeout, provider.enrich(&mut enriched))
+                        .await
+                        .unwrap_or_else(|_| Err(anyhow!("timed out after {:?}", timeout))),
+                };
+                match outcome {
+                    Ok(()) => enriched,
+                    Err(e) => {
+                        search_log!(warn, "Enrichment failed for '{}': {}", result.title, e);
+                        result
+                    }
+                }
+            }
+        }))
+        .buffered(self.enrich_concurrency.max(1))
+        .collect()
+        .await
+    }
+
+    /// 为结果标记标题中的搜索关键词和优先关键词，供前端高亮显示
+    fn apply_highlights(&self, query: &str, results: &mut [SearchResult]) {
+        let highlight_terms: Vec<&str> = query
+            .split_whitespace()
+            .chain(self.priority_keywords.iter().map(String::as_str))
+            .collect();
+        for result in results {
+            result.highlights = crate::filter::compute_highlights(&result.title, &highlight_terms);
+        }
+    }
+
+    /// 流式多页搜索 - 每个提供商的每一页完成后立即通过 `tx` 发送一批结果
+    ///
+    /// 先按页顺序搜索clmclm，再并发搜索其他提供商。发送的结果未去重；
+    /// 接收端关闭后停止发送，不视为错误。设置了总时限时，到期后停止搜索，
+    /// 已发送的结果仍然有效。
+    pub async fn search_stream(&self, query: &str, pages: impl Into<PageRange>, tx: mpsc::Sender<SearchBatch>) -> Result<()> {
+        let pages = pages.into();
+        if self.providers.is_empty() {
+            return Err(anyhow!("No search providers available"));
+        }
+
+        let searching = async {
+            match self.deadline {
+                None => self.search_providers(query, pages, tx).await,
+                Some(deadline) => match tokio::time::timeout(deadline, self.search_providers(query, pages, tx)).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => {
+                        search_log!(warn, "Search deadline of {:?} reached, returning partial results", deadline);
+                        Ok(())
+                    }
+                },
+            }
+        };
+
+        // 同一次搜索的所有日志共享一个搜索 ID
+        let outcome = crate::trace::in_search_scope(async {
+            tokio::select! {
+                outcome = searching => outcome,
+                _ = shutdown_requested() => {
+                    search_log!(warn, "Search cancelled because the app is shutting down");
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        // 记录该搜索核心的累计统计，供 `last_search_metrics` 查询；同名提供商以最新的累计值为准
+        let metrics = self.metrics.lock().unwrap().clone();
+        LAST_SEARCH_METRICS.lock().unwrap().extend(metrics);
+        outcome
+    }
+
+    /// 按搜索顺序排列的提供商名称
+    pub fn provider_names(&self) -> Vec<String> {
+        self.providers.iter().map(|provider| provider.name().to_string()).collect()
+    }
+
+    /// 该搜索核心发起的所有请求的统计
+    ///
+    /// 每次命令级搜索都会新建 `SearchCore`，统计在其生命周期内累计，不会在单个关键词或单次 `search_stream` 开始时清空，
+    /// 因此多关键词并发搜索时各关键词的请求都会被计入。
+    pub fn metrics(&self) -> Vec<ProviderMetrics> {
+        self.metrics.lock().unwrap().values().cloned().collect()
+    }
+
+    /// 搜索单页并把请求统计累计到该搜索核心（并发调用安全）
+    async fn search_page(&self, provider: &Arc<dyn SearchProvider>, query: &str, page: u32) -> Result<Vec<SearchResult>> {
+        if let Some(jitter) = &self.jitter {
+            tokio::time::sleep(jitter.next_delay()).await;
+        }
+
+        let started = std::time::Instant::now();
+        let (outcome, body_bytes) = crate::net::measure_response_bytes(provider.search(query, page, self.max_results)).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let empty_parse = matches!(&outcome, Ok(results) if results.is_empty()) && body_bytes >= MIN_PARSEABLE_BODY_BYTES;
+        if empty_parse {
+            trace_println!("⚠️ {} page {page}: no results parsed from {body_bytes} bytes of HTML", provider.name());
+        }
+        let outcome = outcome.map(|results| {
+            let mut results = self.enforce_magnet_validation(results);
+            assign_original_ranks(&mut results);
+            results
+        });
+
+        let mut metrics = self.metrics.lock().unwrap();
+        let entry = metrics.entry(provider.name().to_string()).or_insert_with(|| ProviderMetrics {
+            name: provider.name().to_string(),
+            ..ProviderMetrics::default()
+        });
+        entry.requests += 1;
+        entry.total_latency_ms += latency_ms;
+        match &outcome {
+            Ok(results) => {
+                entry.successes += 1;
+                entry.results_count += results.len();
+                if !results.is_empty() {
+                    entry.productive_pages += 1;
+                    entry.productive_body_bytes += body_bytes as u64;
+                }
+            }
+            Err(_) => entry.failures += 1,
+        }
+        if empty_parse {
+            entry.empty_parses += 1;
+            entry.empty_parse_bytes = entry.empty_parse_bytes.max(body_bytes);
+        }
+
+        outcome
+    }
+
+    /// 依次搜索clmclm和其他提供商，由 `search_stream` 负责总时限
+    async fn search_providers(&self, query: &str, pages: PageRange, tx: mpsc::Sender<SearchBatch>) -> Result<()> {
+        trace_println!("🔍 Starting search with {} providers, pages {}-{}", self.providers.len(), pages.start, pages.end);
+
+        // 分离clmclm和其他提供商
+        let mut clmclm_provider = None;
+        let mut other_providers = Vec::new();
+
+        for provider in &self.providers {
+            if provider.name() == "clmclm.com" {
+                clmclm_provider = Some(Arc::clone(provider));
+            } else {
+                other_providers.push(Arc::clone(provider));
+            }
+        }
+
+        // 1. 首先搜索clmclm（如果启用）
+        let has_clmclm = clmclm_provider.is_some();
+        if let Some(clmclm) = clmclm_provider {
+            trace_println!("🔍 Searching clmclm.com first for faster results");
+            for page in pages.pages() {
+                let request_id = format!("{}:p{page}", clmclm.name());
+                match crate::trace::with_request_id(request_id, self.search_page(&clmclm, query, page)).await {
+                    Ok(results) => {
+                        let mut results = self.enrich_results(&clmclm, results).await;
+                        trace_println!("✅ clmclm.com page {page} returned {} results", results.len());
+                        self.apply_highlights(query, &mut results);
+                        let batch = SearchBatch { provider_name: clmclm.name().to_string(), provider_index: 0, page, results };
+                        if tx.send(batch).await.is_err() {
+                            return Ok(()); // 接收端已关闭
+                        }
+                    }
+                    Err(e) => {
+                        trace_println!("❌ clmclm.com page {page} failed: {e}");
+                    }
+                }
+            }
+        }
+
+        // 2. 然后并发搜索其他提供商，每页完成即发送
+        if !other_providers.is_empty() {
+            trace_println!("🔍 Now searching {} other providers concurrently", other_providers.len());
+
+            let mut other_search_futures = Vec::new();
+
+            // clmclm 存在时占用位置 0
+            let index_offset = usize::from(has_clmclm);
+            for (index, provider) in other_providers.into_iter().enumerate() {
+                let provider_index = index + index_offset;
+                for page in pages.pages() {
+                    let provider = Arc::clone(&provider);
+                    let query = query.to_string();
+                    let provider_name = provider.name().to_string();
+                    let tx = tx.clone();
+
+                    let request_id = format!("{provider_name}:p{page}");
+                    let search_future = crate::trace::with_request_id(request_id, async move {
+                        trace_println!("🔍 Searching {query} page {page} with provider: {provider_name}");
+                        match self.search_page(&provider, &query, page).await {
+                            Ok(results) => {
+                                trace_println!("✅ Provider {provider_name} page {page} returned {} results", results.len());
+                                let mut results = self.enrich_results(&provider, results).await;
+                                self.apply_highlights(&query, &mut results);
+                                // 接收端关闭时直接丢弃，不中断其他任务
+                                let _ = tx.send(SearchBatch { provider_name, provider_index, page, results }).await;
                             }
                             Err(e) => {
-                                println!("❌ Provider {provider_name} page {page} failed: {e}");
-                                Err(e)
+                                // 继续处理其他结果，不因为单个任务失败而中断
+                                trace_println!("❌ Provider {provider_name} page {page} failed: {e}");
                             }
                         }
-                    };
+                    });
+
+                    other_search_futures.push(search_future);
+                }
+            }
+
+            // 并发执行其他搜索任务
+            join_all(other_search_futures).await;
+        }
+
+        Ok(())
+    }
+
+    /// 多页搜索 - 消费 `search_stream` 的全部结果，去重后一次性返回
+    pub async fn search_multi_page(&self, query: &str, pages: impl Into<PageRange>) -> Result<Vec<SearchResult>> {
+        let (tx, mut rx) = mpsc::channel::<SearchBatch>(SEARCH_STREAM_BUFFER);
+
+        let collect = async {
+            let mut batches = Vec::new();
+            while let Some(batch) = rx.recv().await {
+                batches.push(batch);
+            }
+            batches
+        };
+
+        let (outcome, batches) = tokio::join!(self.search_stream(query, pages, tx), collect);
+        outcome?;
+
+        let all_results = self.finalize_results(merge_batches(batches));
+
+        trace_println!("🎯 Total results collected from all providers: {}", all_results.len());
+        Ok(all_results)
+    }
+
+
+
+    /// 多关键词搜索 - 分别搜索每个关键词，按 infohash 合并去重并记录命中的关键词
+    ///
+    /// 空白和重复的关键词会被忽略；单个关键词失败不影响其他关键词，全部失败时返回最后一个错误。
+    pub async fn search_multi_keyword(&self, keywords: &[String], pages: impl Into<PageRange>) -> Result<Vec<SearchResult>> {
+        let pages = pages.into();
+        let mut unique_keywords: Vec<String> = Vec::new();
+        for keyword in keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+            if !unique_keywords.iter().any(|k| k == keyword) {
+                unique_keywords.push(keyword.to_string());
+            }
+        }
+        if unique_keywords.is_empty() {
+            return Err(anyhow!("No keywords to search"));
+        }
+
+        let outcomes: Vec<(String, Result<Vec<SearchResult>>)> = stream::iter(unique_keywords.into_iter().map(|keyword| async move {
+            let outcome = self.search_multi_page(&keyword, pages).await;
+            (keyword, outcome)
+        }))
+        .buffered(KEYWORD_CONCURRENCY)
+        .collect()
+        .await;
+
+        let mut per_keyword = Vec::new();
+        let mut last_error = None;
+        for (keyword, outcome) in outcomes {
+            match outcome {
+                Ok(results) => per_keyword.push((keyword, results)),
+                Err(e) => {
+                    trace_println!("❌ Search for keyword '{keyword}' failed: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        if per_keyword.is_empty() {
+            return Err(last_error.unwrap_or_else(|| anyhow!("No keywords to search")));
+        }
+
+        let mut merged = crate::filter::merge_keyword_results(per_keyword);
+        assign_original_ranks(&mut merged);
+        crate::filter::apply_quality_scores(&mut merged, &self.quality_weights);
+        self.apply_result_ceiling(&mut merged);
+        trace_println!("🎯 Total results collected for all keywords: {}", merged.len());
+        Ok(merged)
+    }
+
+    /// 在所有提供商中查找指定 infohash 的资源，只保留哈希一致的结果
+    ///
+    /// `infohash` 需已标准化（见 `parse_infohash_input`）。单个提供商失败不影响其他提供商，全部失败时返回最后一个错误。
+    pub async fn search_by_infohash(&self, infohash: &str) -> Result<Vec<SearchResult>> {
+        let outcomes = join_all(self.providers.iter().map(|provider| async move {
+            (provider.name().to_string(), provider.search_by_infohash(infohash).await)
+        }))
+        .await;
+
+        let mut results = Vec::new();
+        let mut last_error = None;
+        let mut any_succeeded = false;
+        for (name, outcome) in outcomes {
+            match outcome {
+                Ok(found) => {
+                    any_succeeded = true;
+                    results.extend(found.into_iter().filter(|r| extract_infohash(&r.magnet_link).as_deref() == Some(infohash)));
+                }
+                Err(e) => {
+                    trace_println!("❌ Infohash lookup on {name} failed: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        if !any_succeeded {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+        }
+
+        assign_original_ranks(&mut results);
+        let results = self.finalize_results(results);
+        trace_println!("🎯 Found {} results for infohash {}", results.len(), infohash);
+        Ok(results)
+    }
+
+    /// 单页搜索（向后兼容）
+    #[allow(dead_code)]
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
+        self.search_multi_page(query, 1).await
+    }
+}
+
+/// 调试用：用指定提供商解析粘贴的HTML，不发起网络请求
+///
+/// `url_template` 中的占位符会被替换为示例值，得到的地址用于解析相对的详情页链接；
+/// 无法解析为URL时相对链接按提供商自身的规则处理。
+pub async fn parse_html_with_provider(provider: &dyn SearchProvider, url_template: &str, html: &str) -> Result<Vec<SearchResult>> {
+    let page_url = url::Url::parse(
+        &url_template
+            .replace("{keyword}", "")
+            .replace("{page-1}", "0")
+            .replace("{page}", "1"),
+    )
+    .ok();
+
+    let results = provider.parse_html(html, page_url.as_ref()).await?;
+    search_log!(stats, "Parsed {} results from pasted HTML with {}", results.len(), provider.name());
+    Ok(results)
+}
+
+/// 内置提供商的功能列表，键为提供商名称（自定义引擎按解析方式使用 "custom"、"json_api"、"torznab"）
+///
+/// 直接读取各提供商的 `CAPABILITIES` 常量，不创建 HTTP 客户端或 Cookie 存储。
+pub fn builtin_provider_capabilities() -> std::collections::BTreeMap<String, ProviderCapabilities> {
+    [
+        ("clmclm.com", ClmclmProvider::CAPABILITIES),
+        ("custom", GenericProvider::CAPABILITIES),
+        ("json_api", JsonApiProvider::CAPABILITIES),
+        ("torznab", TorznabProvider::CAPABILITIES),
+    ]
+    .into_iter()
+    .map(|(name, capabilities)| (name.to_string(), capabilities))
+    .collect()
+}
+
+/// JSON 搜索接口的字段映射
+///
+/// 路径用 `.` 分隔键名、`[n]` 取数组元素，可省略开头的 `$`，如 `data.items`、`files[0].size`。
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JsonApiMapping {
+    /// 结果数组在响应中的路径，为空时响应本身就是数组
+    #[serde(default)]
+    pub items: String,
+    /// 单条结果内标题字段的路径
+    pub title: String,
+    /// 单条结果内磁力链接字段的路径
+    #[serde(default)]
+    pub magnet: Option<String>,
+    /// 单条结果内 infohash 字段的路径，没有磁力链接字段时用于拼出磁力链接
+    #[serde(default)]
+    pub infohash: Option<String>,
+    /// 单条结果内大小字段的路径，数字按字节数处理
+    #[serde(default)]
+    pub size: Option<String>,
+    /// 单条结果内上传日期字段的路径，数字按 Unix 秒处理
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+/// 按 `JsonApiMapping` 的路径语法取出 JSON 中的值
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indexes) = segment.split_once('[').map_or((segment, ""), |(key, rest)| (key, rest));
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|index| !index.is_empty()) {
+            current = current.get(index.trim_end_matches(']').trim().parse::<usize>().ok()?)?;
+        }
+    }
+    Some(current)
+}
+
+/// 按可选的路径取出 JSON 中的值，未配置路径时返回 None
+fn json_field<'a>(value: &'a serde_json::Value, path: &Option<String>) -> Option<&'a serde_json::Value> {
+    json_path(value, path.as_deref()?)
+}
+
+/// 将 JSON 标量转为去除首尾空白的文本，空值和对象返回 None
+fn json_text(value: &serde_json::Value) -> Option<String> {
+    let text = match value {
+        serde_json::Value::String(text) => text.trim().to_string(),
+        serde_json::Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// 直接解析 JSON 搜索接口的提供商，不经过 HTML 解析和 AI
+pub struct JsonApiProvider {
+    name: String,
+    url_template: String,
+    client: reqwest::Client,
+    mapping: JsonApiMapping,
+    headers: BTreeMap<String, String>,
+}
+
+impl JsonApiProvider {
+    pub const CAPABILITIES: ProviderCapabilities = ProviderCapabilities::NONE;
+
+    pub fn new(name: String, url_template: String, mapping: JsonApiMapping) -> Self {
+        let client = crate::net::client_builder()
+            .timeout(PROVIDER_REQUEST_TIMEOUT)
+            .cookie_provider(crate::net::cookie_jar(&name))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { name, url_template, client, mapping, headers: BTreeMap::new() }
+    }
+
+    /// 设置该引擎附加的请求头，如接口要求的 API key
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// 按字段映射把接口响应转换为搜索结果，缺少标题或磁力链接的条目会被跳过
+    pub fn parse_response(&self, body: &str, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let response: serde_json::Value = serde_json::from_str(body).map_err(|e| anyhow!("Invalid JSON from {}: {}", self.name, e))?;
+        let items = json_path(&response, &self.mapping.items)
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| anyhow!("No result array at '{}' in response from {}", self.mapping.items, self.name))?;
+
+        let mut results = Vec::new();
+        for item in items {
+            let Some(raw_title) = json_path(item, &self.mapping.title).and_then(json_text) else {
+                continue;
+            };
+            let magnet_link = json_field(item, &self.mapping.magnet).and_then(json_text).or_else(|| {
+                let hash = json_field(item, &self.mapping.infohash).and_then(json_text)?;
+                Some(format!("magnet:?xt=urn:btih:{}&dn={}", normalize_infohash(&hash)?, urlencoding::encode(&raw_title)))
+            });
+            let Some(magnet_link) = magnet_link.filter(|magnet| is_btih_magnet(magnet)) else {
+                continue;
+            };
+
+            let file_size = json_field(item, &self.mapping.size).and_then(|size| match size.as_u64() {
+                Some(bytes) => Some(crate::filter::format_size(bytes, crate::filter::DEFAULT_SIZE_PRECISION)),
+                None => json_text(size),
+            });
+            let date = json_field(item, &self.mapping.date);
+            let upload_timestamp = date.and_then(serde_json::Value::as_i64);
+            let upload_date = date.and_then(|date| match upload_timestamp {
+                Some(timestamp) => chrono::DateTime::from_timestamp(timestamp, 0).map(|date| date.format("%Y-%m-%d").to_string()),
+                None => json_text(date),
+            });
+
+            let title = clean_html_text(&raw_title);
+            results.push(SearchResult {
+                file_list: generate_file_list_from_title(&title),
+                original_title: raw_title,
+                title,
+                magnet_link,
+                file_size,
+                upload_date,
+                provider_name: self.name.clone(),
+                upload_timestamp,
+                ..Default::default()
+            });
+            if max_results.is_some_and(|max| results.len() >= max) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for JsonApiProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        Self::CAPABILITIES
+    }
+
+    async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let url = fill_url_template(&self.url_template, &urlencoding::encode(query), page);
+        search_log!(info, "Searching JSON API: {}", url);
+
+        let response = self.client
+            .get(&url)
+            .header("Accept", "application/json")
+            .headers(crate::net::request_headers(&self.headers))
+            .send()
+            .await
+            .map_err(|e| handle_request_error(&url, e))?;
+
+        if !response.status().is_success() {
+            search_log!(error, "HTTP error {} for {}", response.status(), url);
+            return Err(anyhow!("HTTP error {}: {}", response.status(), url));
+        }
+
+        let body = crate::net::read_text(response).await?;
+        let results = self.parse_response(&body, max_results)?;
+        search_log!(stats, "Found {} results on page {}", results.len(), page);
+        Ok(results)
+    }
+
+    async fn parse_html(&self, html: &str, _page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
+        self.parse_response(html, None)
+    }
+}
+
+/// Torznab 接口（Jackett、Prowlarr 等）的配置，接口地址使用引擎的 URL 模板
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TorznabConfig {
+    pub api_key: String,
+    /// 限定搜索的分类编号（如 "2000" 电影），为空时搜索全部分类
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+/// Torznab 每页请求的结果数
+const TORZNAB_PAGE_SIZE: u32 = 100;
+
+/// 通过 Torznab 接口搜索的提供商，直接解析 RSS 中的 `<item>`
+pub struct TorznabProvider {
+    name: String,
+    api_url: String,
+    client: reqwest::Client,
+    config: TorznabConfig,
+    headers: BTreeMap<String, String>,
+}
+
+impl TorznabProvider {
+    pub const CAPABILITIES: ProviderCapabilities = ProviderCapabilities {
+        seeders: true,
+        ..ProviderCapabilities::NONE
+    };
+
+    pub fn new(name: String, api_url: String, config: TorznabConfig) -> Self {
+        let client = crate::net::client_builder()
+            .timeout(PROVIDER_REQUEST_TIMEOUT)
+            .cookie_provider(crate::net::cookie_jar(&name))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { name, api_url, client, config, headers: BTreeMap::new() }
+    }
+
+    /// 设置该引擎附加的请求头，如反向代理要求的认证头
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// 解析 Torznab 的 RSS 响应，没有磁力链接也没有 infohash 的条目会被跳过
+    pub fn parse_response(&self, xml: &str, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let document = roxmltree::Document::parse(xml).map_err(|e| anyhow!("Invalid Torznab XML from {}: {}", self.name, e))?;
+        let root = document.root_element();
+        if root.has_tag_name("error") {
+            return Err(anyhow!(
+                "Torznab error {} from {}: {}",
+                root.attribute("code").unwrap_or("?"),
+                self.name,
+                root.attribute("description").unwrap_or_default()
+            ));
+        }
+
+        let mut results = Vec::new();
+        for item in root.descendants().filter(|node| node.has_tag_name("item")) {
+            let child_text = |name: &str| {
+                item.children()
+                    .find(|child| child.tag_name().name() == name && child.tag_name().namespace().is_none())
+                    .and_then(|child| child.text())
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+                    .map(str::to_string)
+            };
+            // torznab:attr 等扩展属性，按本地名称匹配以兼容不同的命名空间前缀
+            let attr = |name: &str| {
+                item.children()
+                    .find(|child| child.tag_name().name() == "attr" && child.attribute("name") == Some(name))
+                    .and_then(|child| child.attribute("value"))
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+            let enclosure_url = item
+                .children()
+                .find(|child| child.has_tag_name("enclosure"))
+                .and_then(|enclosure| enclosure.attribute("url"))
+                .map(str::to_string);
+
+            let Some(raw_title) = child_text("title") else {
+                continue;
+            };
+            let magnet_link = [attr("magneturl"), child_text("link"), enclosure_url]
+                .into_iter()
+                .flatten()
+                .find(|url| is_btih_magnet(url))
+                .or_else(|| {
+                    let hash = normalize_infohash(&attr("infohash")?)?;
+                    Some(format!("magnet:?xt=urn:btih:{hash}&dn={}", urlencoding::encode(&raw_title)))
+                });
+            let Some(magnet_link) = magnet_link else {
+                continue;
+            };
+
+            let file_size = attr("size")
+                .or_else(|| child_text("size"))
+                .and_then(|size| size.parse::<u64>().ok())
+                .map(|bytes| crate::filter::format_size(bytes, crate::filter::DEFAULT_SIZE_PRECISION));
+            let published = child_text("pubDate").and_then(|date| chrono::DateTime::parse_from_rfc2822(&date).ok());
+            let source_url = child_text("comments").or_else(|| child_text("guid").filter(|guid| guid.starts_with("http")));
+
+            let title = clean_html_text(&raw_title);
+            results.push(SearchResult {
+                file_list: generate_file_list_from_title(&title),
+                original_title: raw_title,
+                title,
+                magnet_link,
+                file_size,
+                upload_date: published.map(|date| date.format("%Y-%m-%d").to_string()),
+                source_url,
+                provider_name: self.name.clone(),
+                seeders: attr("seeders").and_then(|seeders| seeders.parse().ok()),
+                upload_timestamp: published.map(|date| date.timestamp()),
+                ..Default::default()
+            });
+            if max_results.is_some_and(|max| results.len() >= max) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for TorznabProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        Self::CAPABILITIES
+    }
+
+    async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+        let mut params = vec![
+            ("t", "search".to_string()),
+            ("q", query.to_string()),
+            ("apikey", self.config.api_key.clone()),
+            ("limit", TORZNAB_PAGE_SIZE.to_string()),
+            // 按 u64 计算偏移，避免极大的页码溢出
+            ("offset", (u64::from(page.saturating_sub(1)) * u64::from(TORZNAB_PAGE_SIZE)).to_string()),
+        ];
+        if !self.config.categories.is_empty() {
+            params.push(("cat", self.config.categories.join(",")));
+        }
+        search_log!(info, "Searching Torznab: {} (page {})", self.api_url, page);
+
+        let response = self.client
+            .get(&self.api_url)
+            .headers(crate::net::request_headers(&self.headers))
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| handle_request_error(&self.api_url, e))?;
+
+        if !response.status().is_success() {
+            search_log!(error, "HTTP error {} for {}", response.status(), self.api_url);
+            return Err(anyhow!("HTTP error {}: {}", response.status(), self.api_url));
+        }
+
+        let xml = crate::net::read_text(response).await?;
+        let results = self.parse_response(&xml, max_results)?;
+        search_log!(stats, "Found {} results on page {}", results.len(), page);
+        Ok(results)
+    }
+
+    async fn parse_html(&self, html: &str, _page_url: Option<&url::Url>) -> Result<Vec<SearchResult>> {
+        self.parse_response(html, None)
+    }
+}
+
+/// 自定义搜索引擎的配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomEngineConfig {
+    pub name: String,
+    pub url_template: String,
+    /// 为 false 时即使配置了 LLM 也只使用基础解析
+    pub use_ai: bool,
+    /// 结果页的 CSS 选择器，配置后优先使用
+    pub selectors: Option<GenericSelectors>,
+    /// 附加的请求头，覆盖同名的全局请求头
+    pub headers: BTreeMap<String, String>,
+    /// JSON 接口的字段映射，配置后 URL 模板按 JSON 接口请求
+    pub json_api: Option<JsonApiMapping>,
+    /// Torznab 接口配置，配置后 URL 模板作为 Torznab 接口地址
+    pub torznab: Option<TorznabConfig>,
+    /// 解析结果页的策略顺序
+    pub parse_order: Vec<ParseStrategy>,
+    /// 登录方式，配置后搜索前先登录
+    pub auth: Option<EngineAuth>,
+}
+
+impl CustomEngineConfig {
+    /// 创建启用 AI 提取的自定义引擎配置
+    pub fn new(name: String, url_template: String) -> Self {
+        Self { name, url_template, use_ai: true, selectors: None, headers: BTreeMap::new(), json_api: None, torznab: None, parse_order: default_parse_order(), auth: None }
+    }
+
+    /// 设置 Torznab 接口配置
+    pub fn with_torznab(mut self, config: Option<TorznabConfig>) -> Self {
+        self.torznab = config;
+        self
+    }
+
+    /// 设置 JSON 接口的字段映射
+    pub fn with_json_api(mut self, mapping: Option<JsonApiMapping>) -> Self {
+        self.json_api = mapping;
+        self
+    }
+
+    /// 设置该引擎附加的请求头
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// 设置结果页的 CSS 选择器
+    pub fn with_selectors(mut self, selectors: Option<GenericSelectors>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    /// 设置是否使用 AI 提取
+    pub fn with_use_ai(mut self, use_ai: bool) -> Self {
+        self.use_ai = use_ai;
+        self
+    }
+
+    /// 设置解析策略的尝试顺序
+    pub fn with_parse_order(mut self, order: Vec<ParseStrategy>) -> Self {
+        self.parse_order = order;
+        self
+    }
+
+    /// 设置登录方式（只对 HTML 解析的引擎生效）
+    pub fn with_auth(mut self, auth: Option<EngineAuth>) -> Self {
+        self.auth = auth;
+        self
+    }
+}
+
+/// 创建带有AI功能的搜索核心
+pub fn create_ai_enhanced_search_core(
+    extraction_config: Option<LlmConfig>,
+    analysis_config: Option<LlmConfig>, // 保持向后兼容，但现在只用于HTML提取
+    priority_keywords: Vec<String>,
+    custom_engines: Vec<CustomEngineConfig>,
+    clmclm_config: Option<ClmclmConfig>, // 为 Some 时包含 clmclm.com
+    empty_title_policy: EmptyTitlePolicy,
+) -> SearchCore {
+    let mut providers: Vec<Arc<dyn SearchProvider>> = Vec::new();
+
+    // 只有在明确启用时才添加 clmclm.com 提供商
+    if let Some(config) = clmclm_config {
+        trace_println!("✅ Adding clmclm.com provider");
+        providers.push(Arc::new(ClmclmProvider::new().with_config(config)));
+    }
+
+    // 为自定义搜索引擎创建AI增强的提供商
+    // 优先使用 extraction_config，如果没有则使用 analysis_config（向后兼容）
+    let html_extraction_config = extraction_config.or(analysis_config);
+
+    let llm_client: Option<Arc<dyn LlmClient>> = html_extraction_config
+        .as_ref()
+        .map(|_| Arc::new(GeminiClient::new()) as Arc<dyn LlmClient>);
+    let extraction_limiter = html_extraction_config
+        .as_ref()
+        .and_then(|config| config.max_concurrency)
+        .map(|limit| Arc::new(tokio::sync::Semaphore::new(limit.max(1) as usize)));
+
+    for engine in custom_engines {
+        // Torznab 与配置了字段映射的 JSON 接口直接解析结构化数据，不需要 AI
+        if let Some(config) = engine.torznab {
+            trace_println!("✅ Adding Torznab provider: {}", engine.name);
+            providers.push(Arc::new(TorznabProvider::new(engine.name, engine.url_template, config).with_headers(engine.headers)));
+            continue;
+        }
+        if let Some(mapping) = engine.json_api {
+            trace_println!("✅ Adding JSON API provider: {}", engine.name);
+            providers.push(Arc::new(JsonApiProvider::new(engine.name, engine.url_template, mapping).with_headers(engine.headers)));
+            continue;
+        }
+
+        let provider = match (&llm_client, &html_extraction_config) {
+            (Some(llm_client), Some(extract_config)) if engine.use_ai => {
+                trace_println!("✅ Adding AI-enhanced custom provider: {}", engine.name);
+                GenericProvider::new(engine.name, engine.url_template)
+                    .with_llm_client_and_config(llm_client.clone(), extract_config.clone())
+                    .with_extraction_limiter(extraction_limiter.clone())
+                    .with_priority_keywords(priority_keywords.clone())
+                    .with_empty_title_policy(empty_title_policy)
+                    .with_selectors(engine.selectors)
+                    .with_headers(engine.headers)
+                    .with_parse_order(engine.parse_order)
+                    .with_auth(engine.auth)
+            }
+            // 没有LLM配置或该引擎关闭了AI时，创建基础的自定义提供商
+            _ => {
+                trace_println!("✅ Adding basic custom provider: {}", engine.name);
+                GenericProvider::new(engine.name, engine.url_template)
+                    .with_selectors(engine.selectors)
+                    .with_headers(engine.headers)
+                    .with_parse_order(engine.parse_order)
+                    .with_auth(engine.auth)
+            }
+        };
+        providers.push(Arc::new(provider));
+    }
+
+    SearchCore { providers, priority_keywords, deep_enrich: false, enrich_concurrency: DEFAULT_ENRICH_CONCURRENCY, enrich_timeout: Some(DEFAULT_ENRICH_TIMEOUT), require_source_url: false, min_confidence: None, min_seeders: None, keep_unknown_seeders: true, deadline: None, metrics: Mutex::default(), jitter: None, max_results: None, quality_weights: Default::default(), fuzzy_dedupe_threshold: None, max_title_length: DEFAULT_MAX_TITLE_LENGTH, size_precision: None, preferences: Default::default(), suspicion_checks: crate::filter::default_suspicion_checks(), strict_magnet_validation: false, max_total_results: None, allowed_languages: Vec::new() }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use crate::test_support::{llm_config, MockLlmClient};
+    // removed redundant single-component import per clippy
+
+    #[tokio::test]
+    async fn test_search_successful() {
+        // Start a mock server
+        let server = MockServer::start();
+
+        // Create a mock
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/search-test-1-1-1.html");
+            then.status(200)
+                .header("content-type", "text/html; charset=UTF-8")
+                .body(r#"
+                    <!DOCTYPE html>
+                    <html>
+                    <body>
+                        <div class="ssbox">
+                            <div class="title"><h3><a href="/detail/123">Test Title 1</a></h3></div>
+                            <div class="sbar">
+                                <a href="magnet:?xt=urn:btih:12345">Magnet Link</a>
+                                <span>大小: 1.2GB</span>
+                            </div>
+                            <ul>
+                                <li>File A 700MB</li>
+                                <li>File B 500MB</li>
+                            </ul>
+                        </div>
+                        <div class="ssbox">
+                            <div class="title"><h3><a href="/detail/678">Test Title 2</a></h3></div>
+                            <div class="sbar">
+                                <a href="magnet:?xt=urn:btih:67890">Magnet Link</a>
+                                <span>大小: 900MB</span>
+                            </div>
+                            <ul>
+                                <li>Episode 01 450MB</li>
+                                <li>Episode 02 450MB</li>
+                            </ul>
+                        </div>
+                    </body>
+                    </html>
+                "#);
+        });
+
+        // Perform the search against the mock server
+        let provider = ClmclmProvider::with_base_url(&server.base_url());
+        let results = provider.search("test", 1, None).await.unwrap();
+
+        // Assert
+        mock.assert();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Test Title 1");
+        assert_eq!(results[0].magnet_link, "magnet:?xt=urn:btih:12345");
+        assert_eq!(results[1].title, "Test Title 2");
+        assert_eq!(results[1].magnet_link, "magnet:?xt=urn:btih:67890");
+    }
+
+    #[tokio::test]
+    async fn test_search_no_results() {
+        // Start a mock server
+        let server = MockServer::start();
+
+        // Create a mock for a page with no items
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/search-empty-1-1-1.html");
+            then.status(200)
+                .header("content-type", "text/html; charset=UTF-8")
+                .body(r#"
+                    <!DOCTYPE html>
+                    <html>
+                    <body>
+                        <p>No results found.</p>
+                    </body>
+                    </html>
+                "#);
+        });
+
+        // Perform the search
+        let provider = ClmclmProvider::with_base_url(&server.base_url());
+        let results = provider.search("empty", 1, None).await.unwrap();
+
+        // Assert
+        mock.assert();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_builtin_provider_capabilities_include_clmclm() {
+        let capabilities = builtin_provider_capabilities();
+
+        let clmclm = capabilities.get("clmclm.com").expect("clmclm should be listed");
+        assert!(clmclm.detail_enrichment);
+        assert!(clmclm.url_codes);
+        assert!(!clmclm.ai_extraction);
+
+        let custom = capabilities.get("custom").expect("custom engines should be listed");
+        assert!(custom.ai_extraction);
+
+        assert!(capabilities.get("torznab").expect("torznab engines should be listed").seeders);
+        assert!(!capabilities.get("json_api").expect("json api engines should be listed").ai_extraction);
+    }
+
+    #[tokio::test]
+    async fn test_search_uses_configured_category_and_sort() {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/search-test-2-3-1.html");
+            then.status(200)
+                .header("content-type", "text/html; charset=UTF-8")
+                .body("<html><body></body></html>");
+        });
+
+        let provider = ClmclmProvider::with_base_url(&server.base_url()).with_config(ClmclmConfig {
+            category_code: "2".to_string(),
+            sort_code: "3".to_string(),
+            ..ClmclmConfig::default()
+        });
+        provider.search("test", 1, None).await.unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_clmclm_enrich_from_detail_page() {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/detail/123");
+            then.status(200)
+                .header("content-type", "text/html; charset=UTF-8")
+                .body(r#"
+                    <html>
+                    <body>
+                        <ul class="nav"><li>Home</li><li>Latest</li></ul>
+                        <div class="info"><span>大小: 3.4GB</span></div>
+                        <ul>
+                            <li>Movie.2023.1080p.mkv 3.3GB</li>
+                            <li>Subs/Chinese.srt 80KB</li>
+                            <li>Sample.mkv 20MB</li>
+                        </ul>
+                    </body>
+                    </html>
+                "#);
+        });
+
+        let provider = ClmclmProvider::with_base_url(&server.base_url());
+        let mut result = SearchResult {
+            title: "Movie 2023".to_string(),
+            magnet_link: "magnet:?xt=urn:btih:12345".to_string(),
+            file_size: Some("3GB".to_string()),
+            file_list: vec!["Movie_2023.mkv".to_string()],
+            source_url: Some(server.url("/detail/123")),
+            ..Default::default()
+        };
+
+        provider.enrich(&mut result).await.unwrap();
+
+        mock.assert();
+        assert_eq!(result.file_size.as_deref(), Some("3.4GB"));
+        assert_eq!(
+            result.file_list,
+            vec!["Movie.2023.1080p.mkv", "Subs/Chinese.srt", "Sample.mkv"]
+        );
+    }
+
+    #[test]
+    fn test_clmclm_parse_with_overridden_selectors() {
+        let html = r#"
+            <html><body>
+                <article class="item">
+                    <h2 class="name"><a href="/detail/1">New Layout Title</a></h2>
+                    <p class="meta"><a href="magnet:?xt=urn:btih:abcdef">Magnet</a><em>大小: 2.1GB</em></p>
+                    <ol><li>Movie.mkv 2.1GB</li></ol>
+                </article>
+            </body></html>
+        "#;
+
+        let provider = ClmclmProvider::with_base_url("http://clmclm.test").with_config(ClmclmConfig {
+            selectors: ClmclmSelectors {
+                row: "article.item".to_string(),
+                title: "h2.name > a".to_string(),
+                magnet: "p.meta a[href^=\"magnet:\"]".to_string(),
+                size: "p.meta em".to_string(),
+                file_list: "ol >>> li".to_string(), // 无效，回退为默认值
+            },
+            ..ClmclmConfig::default()
+        });
+        assert_eq!(provider.config.selectors.file_list, "ul > li");
+
+        let results = provider.parse_results(html, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "New Layout Title");
+        assert_eq!(results[0].magnet_link, "magnet:?xt=urn:btih:abcdef");
+        assert_eq!(results[0].file_size.as_deref(), Some("2.1GB"));
+        assert_eq!(results[0].source_url.as_deref(), Some("http://clmclm.test/detail/1"));
+    }
+
+    #[test]
+    fn test_clmclm_incremental_parse_matches_full_document_parse() {
+        let mut html = String::from("<!DOCTYPE html><html><body><div class=\"main\">");
+        for i in 0..1000 {
+            html.push_str(&format!(
+                r#"<div class="ssbox"><div class="title"><h3><a href="/detail/{i}">Title <b>{i}</b></a></h3></div>
+                <div class="sbar"><a href="magnet:?xt=urn:btih:{i:040}">Magnet</a><span>热度: {i}</span><span>大小: {i}MB</span></div>
+                <ul><li>File.{i}.mkv {i}MB</li><li>Sub.{i}.srt 1KB</li></ul></div>"#
+            ));
+        }
+        html.push_str("</div></body></html>");
+
+        let provider = ClmclmProvider::with_base_url("http://clmclm.test");
+        let selectors = CompiledClmclmSelectors::compile(&provider.config.selectors).unwrap();
+
+        let full = provider.parse_results_full(&html, &selectors, None).unwrap();
+        let incremental = provider.parse_results(&html, None).unwrap();
+
+        // 逐块解析时同一时刻只为一个结果块构建 DOM
+        let largest_block = tag_blocks(&html, "div", "ssbox").map(str::len).max().unwrap();
+        assert!(largest_block * 100 < html.len());
+
+        assert_eq!(full.len(), 1000);
+        assert_eq!(serde_json::to_value(&incremental).unwrap(), serde_json::to_value(&full).unwrap());
+        assert_eq!(
+            serde_json::to_value(provider.parse_results(&html, Some(5)).unwrap()).unwrap(),
+            serde_json::to_value(provider.parse_results_full(&html, &selectors, Some(5)).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_clmclm_table_row_selector_matches_full_document_parse() {
+        let html = r#"
+            <html><body><table>
+                <tr class="row">
+                    <td class="name"><a href="/detail/1">Table Row Movie</a></td>
+                    <td class="bar"><a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">Magnet</a><span>大小: 1.5GB</span></td>
+                </tr>
+            </table></body></html>
+        "#;
+        let provider = ClmclmProvider::with_base_url("http://clmclm.test").with_config(ClmclmConfig {
+            selectors: ClmclmSelectors {
+                row: "tr.row".to_string(),
+                title: "td.name a".to_string(),
+                magnet: "td.bar a[href^=\"magnet:\"]".to_string(),
+                size: "td.bar span".to_string(),
+                file_list: "ul > li".to_string(),
+            },
+            ..ClmclmConfig::default()
+        });
+        let selectors = CompiledClmclmSelectors::compile(&provider.config.selectors).unwrap();
+
+        let results = provider.parse_results(html, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Table Row Movie");
+        assert_eq!(
+            serde_json::to_value(&results).unwrap(),
+            serde_json::to_value(provider.parse_results_full(html, &selectors, None).unwrap()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generic_source_url_resolves_against_redirect_target() {
+        let canonical = MockServer::start();
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        canonical.mock(|when, then| {
+            when.method(GET).path("/s/movie/1");
+            then.status(200).body(format!(
+                r#"<table><tr><td><a href="/detail/42">Movie 2023</a></td><td>1.4 GB</td><td><a href="magnet:?xt=urn:btih:{hash}">m</a></td></tr></table>"#
+            ));
+        });
+
+        let legacy = MockServer::start();
+        let redirect = legacy.mock(|when, then| {
+            when.method(GET).path("/search");
+            then.status(301).header("Location", canonical.url("/s/movie/1"));
+        });
+
+        let template = format!("{}/search?q={{keyword}}&p={{page}}", legacy.base_url());
+        let provider = GenericProvider::new("legacy".to_string(), template);
+        let results = provider.search("movie", 1, None).await.unwrap();
+
+        redirect.assert();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].source_url, Some(canonical.url("/detail/42")));
+    }
+
+    #[test]
+    fn test_extract_title_from_encoded_dn() {
+        let provider = GenericProvider::new("example".to_string(), "https://example.com/s?q={keyword}".to_string());
+
+        let plus_spaces = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Big+Buck+Bunny+%282008%29+1080p&tr=udp%3A%2F%2Ftracker";
+        assert_eq!(provider.extract_title_from_magnet(plus_spaces), "Big Buck Bunny (2008) 1080p");
+
+        let with_junk = "magnet:?dn=%5Bwww.example.com%5D%E5%A4%A7%E9%B1%BC%E6%B5%B7%E6%A3%A0+-+Big.Fish&xt=urn:btih:0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(provider.extract_title_from_magnet(with_junk), "大鱼海棠 - Big.Fish");
+
+        let with_url = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Movie+2023+http%3A%2F%2Fbt.example.cc+++1080p";
+        assert_eq!(provider.extract_title_from_magnet(with_url), "Movie 2023 1080p");
+
+        let short = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Up";
+        assert_eq!(provider.extract_title_from_magnet(short), "Up");
+
+        let no_dn = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(provider.extract_title_from_magnet(no_dn), "Torrent_01234567");
+    }
+
+    #[test]
+    fn test_generic_parse_magnet_with_dn_before_xt() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let html = format!(
+            r#"<html><body><div><a href="magnet:?dn=Some.Movie.2023.1080p&amp;xt=urn:btih:{hash}&amp;tr=udp%3A%2F%2Ftracker">下载</a></div></body></html>"#
+        );
+
+        let provider = GenericProvider::new("example".to_string(), "https://example.com/s?q={keyword}".to_string());
+        let results = provider.parse_generic_results(&html, None, None).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].magnet_link,
+            format!("magnet:?dn=Some.Movie.2023.1080p&xt=urn:btih:{hash}&tr=udp%3A%2F%2Ftracker")
+        );
+        assert_eq!(results[0].title, "Some.Movie.2023.1080p");
+        assert!(is_btih_magnet(&results[0].magnet_link));
+        assert!(!is_btih_magnet("magnet:?dn=Only.Name"));
+    }
+
+    /// 每页返回固定条数结果的测试提供商
+    struct StaticProvider {
+        name: String,
+        per_page: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl SearchProvider for StaticProvider {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn search(&self, query: &str, page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            Ok((0..self.per_page)
+                .map(|i| SearchResult {
+                    title: format!("{query} {} {page}-{i}", self.name),
+                    magnet_link: format!("magnet:?xt=urn:btih:{}{page}{i}", self.name),
+                    // 偶数条目带有详情页链接
+                    source_url: (i % 2 == 0).then(|| format!("https://{}.test/{page}/{i}", self.name)),
+                    provider_name: self.name.clone(),
+                    ..Default::default()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_log_lines_share_correlation_id() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 1 }),
+                Arc::new(StaticProvider { name: "beta".to_string(), per_page: 1 }),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        // 未指定时自动分配搜索 ID，同一次搜索的各个请求共享它
+        core.search_multi_page("correlation-probe", 2).await.unwrap();
+        let lines = crate::trace::captured::lines_containing("Searching correlation-probe");
+        assert_eq!(lines.len(), 4);
+        let search_ids: std::collections::HashSet<_> = lines
+            .iter()
+            .map(|line| line.trim_start_matches('[').split('/').next().unwrap().to_string())
+            .collect();
+        assert_eq!(search_ids.len(), 1);
+        // 每个提供商的每一页有自己的请求 ID
+        assert!(lines.iter().any(|line| line.contains("/beta:p2] ")));
+
+        // 调用方指定的搜索 ID 会被沿用
+        crate::trace::with_search_id("corr0001".to_string(), core.search_multi_page("correlation-explicit", 1))
+            .await
+            .unwrap();
+        let lines = crate::trace::captured::lines_containing("Searching correlation-explicit");
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.starts_with("[corr0001/")));
+    }
+
+    #[tokio::test]
+    async fn test_multi_keyword_search_keeps_metrics_of_every_keyword() {
+        let core = SearchCore {
+            providers: vec![Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 1 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        let keywords = vec!["first".to_string(), "second".to_string()];
+        core.search_multi_keyword(&keywords, 1).await.unwrap();
+
+        // 后开始的关键词不会清掉先开始的关键词已记录的统计
+        let metrics = core.metrics();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!((metrics[0].requests, metrics[0].successes, metrics[0].results_count), (2, 2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_highlights_stay_inside_truncated_title() {
+        let core = SearchCore {
+            providers: vec![Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 1 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        }
+        .with_max_title_length(14);
+
+        // "1080p" 与 "BluRay" 都落在截断处之后
+        let results = core.search_multi_page("Sintel 2010 1080p BluRay", 1).await.unwrap();
+        assert_eq!(results[0].title, "Sintel 2010…");
+        assert_eq!(results[0].highlights, vec![(0, 6), (7, 11)]);
+    }
+
+    #[tokio::test]
+    async fn test_allowed_languages_filter_search_results() {
+        let core = SearchCore {
+            providers: vec![Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 2 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        }
+        .with_allowed_languages(vec![crate::filter::Lang::Cjk]);
+
+        // 纯拉丁字母的标题被过滤，同时含有中文的标题保留
+        assert!(core.search_multi_page("Sintel", 1).await.unwrap().is_empty());
+        assert_eq!(core.search_multi_page("流浪地球", 1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_result_pager_chunks_into_page_size() {
+        let results = StaticProvider { name: "alpha".to_string(), per_page: 7 }.search("paged", 1, None).await.unwrap();
+        let pager = ResultPager::default();
+
+        let first = pager.paginate(results.clone(), 3);
+        assert_eq!((first.page, first.total_pages, first.results.len()), (1, 3, 3));
+
+        let second = pager.next_page(&first.search_id).unwrap();
+        let third = pager.next_page(&first.search_id).unwrap();
+        assert_eq!((second.page, second.results.len()), (2, 3));
+        assert_eq!((third.page, third.results.len()), (3, 1));
+        assert!(pager.next_page(&first.search_id).is_none());
+
+        // 分页后顺序与完整结果一致
+        let titles: Vec<_> = [first, second, third].into_iter().flat_map(|page| page.results).map(|r| r.title).collect();
+        assert_eq!(titles, results.into_iter().map(|r| r.title).collect::<Vec<_>>());
+
+        // 一页就能放下时不缓存
+        let single = pager.paginate(vec![], 3);
+        assert_eq!(single.total_pages, 1);
+        assert!(pager.next_page(&single.search_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_delivers_all_batches() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 2 }),
+                Arc::new(StaticProvider { name: "beta".to_string(), per_page: 3 }),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+        // 缓冲区小于批次数，发送端只能在接收端消费时继续
+        let (tx, mut rx) = mpsc::channel(1);
+
+        let drain = async {
+            let mut batches: Vec<SearchBatch> = Vec::new();
+            while let Some(batch) = rx.recv().await {
+                batches.push(batch);
+            }
+            batches
+        };
+        let (outcome, batches) = tokio::join!(core.search_stream("movie", 2, tx), drain);
+        outcome.unwrap();
+
+        assert_eq!(batches.len(), 4);
+        let total: usize = batches.iter().map(|b| b.results.len()).sum();
+        assert_eq!(total, 10);
+        assert!(batches.iter().flat_map(|b| &b.results).all(|r| r.highlights == vec![(0, 5)]));
+
+        let collected = core.search_multi_page("movie", 2).await.unwrap();
+        assert_eq!(collected.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_require_source_url_drops_unverifiable_results() {
+        let core = SearchCore {
+            providers: vec![Arc::new(StaticProvider { name: "alpha".to_string(), per_page: 3 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+        assert_eq!(core.search_multi_page("movie", 1).await.unwrap().len(), 3);
+
+        let strict = core.with_require_source_url(true);
+        let results = strict.search_multi_page("movie", 1).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.source_url.is_some()));
+    }
+
+    /// 在返回结果前等待指定时间的测试提供商
+    struct SlowProvider {
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl SearchProvider for SlowProvider {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn search(&self, query: &str, page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            tokio::time::sleep(self.delay).await;
+            StaticProvider { name: "slow".to_string(), per_page: 1 }.search(query, page, None).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_deadline_returns_partial_results() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(StaticProvider { name: "fast".to_string(), per_page: 2 }),
+                Arc::new(SlowProvider { delay: Duration::from_secs(30) }),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        }
+        .with_deadline(Some(Duration::from_millis(200)));
+
+        let started = std::time::Instant::now();
+        let results = core.search_multi_page("movie", 1).await.unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.provider_name == "fast"));
+    }
+
+    /// 记录被请求页码的测试提供商
+    struct PageRecordingProvider {
+        pages: std::sync::Mutex<Vec<u32>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SearchProvider for PageRecordingProvider {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn search(&self, query: &str, page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            self.pages.lock().unwrap().push(page);
+            StaticProvider { name: "recording".to_string(), per_page: 1 }.search(query, page, None).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_only_fetches_requested_page_range() {
+        let provider = Arc::new(PageRecordingProvider { pages: std::sync::Mutex::new(Vec::new()) });
+        let core = SearchCore {
+            providers: vec![provider.clone()],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        let results = core.search_multi_page("movie", PageRange::new(3, 5)).await.unwrap();
+
+        let mut pages = provider.pages.lock().unwrap().clone();
+        pages.sort();
+        assert_eq!(pages, vec![3, 4, 5]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(PageRange::from(2).pages().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    /// 第2页请求失败的测试提供商
+    struct FlakyProvider;
+
+    #[async_trait::async_trait]
+    impl SearchProvider for FlakyProvider {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn search(&self, query: &str, page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            if page == 2 {
+                return Err(anyhow!("HTTP error 503"));
+            }
+            StaticProvider { name: "flaky".to_string(), per_page: 1 }.search(query, page, None).await
+        }
+    }
+
+    /// 始终请求失败的测试提供商
+    struct BrokenProvider;
+
+    #[async_trait::async_trait]
+    impl SearchProvider for BrokenProvider {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        async fn search(&self, _query: &str, _page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            Err(anyhow!("HTTP error 502"))
+        }
+    }
+
+    /// 按查询词返回一个同名哈希结果和一个无关结果的测试提供商
+    struct HashEchoProvider;
+
+    #[async_trait::async_trait]
+    impl SearchProvider for HashEchoProvider {
+        fn name(&self) -> &str {
+            "hash-echo"
+        }
+
+        async fn search(&self, query: &str, _page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            let mut results = StaticProvider { name: "hash-echo".to_string(), per_page: 2 }.search(query, 1, None).await?;
+            results[0].magnet_link = format!("magnet:?xt=urn:btih:{}&dn=Found", query.to_uppercase());
+            results[1].magnet_link = "magnet:?xt=urn:btih:ffffffffffffffffffffffffffffffffffffffff".to_string();
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_by_infohash_keeps_matching_results() {
+        let core = create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default());
+        let core = SearchCore {
+            providers: vec![Arc::new(HashEchoProvider), Arc::new(BrokenProvider)],
+            ..core
+        };
+
+        let infohash = parse_infohash_input(" ABCDEF0123456789ABCDEF0123456789ABCDEF01 ").unwrap();
+        assert_eq!(infohash, "abcdef0123456789abcdef0123456789abcdef01");
+        assert_eq!(parse_infohash_input(&format!("magnet:?xt=urn:btih:{infohash}")), Some(infohash.clone()));
+        assert_eq!(parse_infohash_input("not-a-hash"), None);
+
+        let results = core.search_by_infohash(&infohash).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(extract_infohash(&results[0].magnet_link), Some(infohash));
+    }
+
+    /// 每页返回固定 infohash 的提供商
+    struct FixedHashProvider {
+        name: String,
+        hashes: Vec<&'static str>,
+    }
+
+    #[async_trait::async_trait]
+    impl SearchProvider for FixedHashProvider {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn search(&self, query: &str, page: u32, _max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            let mut results = StaticProvider { name: self.name.clone(), per_page: self.hashes.len() }.search(query, page, None).await?;
+            for (result, hash) in results.iter_mut().zip(&self.hashes) {
+                result.magnet_link = format!("magnet:?xt=urn:btih:{hash}");
+            }
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compare_providers_counts_shared_infohashes() {
+        let shared = "1111111111111111111111111111111111111111";
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(FixedHashProvider {
+                    name: "established".to_string(),
+                    hashes: vec![shared, "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"],
+                }),
+                Arc::new(FixedHashProvider {
+                    name: "candidate".to_string(),
+                    hashes: vec!["cccccccccccccccccccccccccccccccccccccccc", shared],
+                }),
+                Arc::new(BrokenProvider),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        let comparison = core.compare_providers("established", "candidate", "ubuntu", 1).await.unwrap();
+        assert_eq!(comparison.results_a.len(), 3);
+        assert_eq!(comparison.results_b.len(), 2);
+        assert_eq!((comparison.overlap, comparison.unique_to_a, comparison.unique_to_b), (1, 2, 1));
+        // 只搜索被比较的两个引擎
+        assert!(comparison.results_a.iter().chain(&comparison.results_b).all(|r| r.provider_name != "broken"));
+
+        assert!(core.compare_providers("established", "missing", "ubuntu", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_strict_magnet_validation_rejects_malformed_infohashes() {
+        let hashes = vec![
+            "0123456789abcdef0123456789abcdef01234567",
+            "MFRGGZDFMZTWQ2LKNNWG23TPOBYXE43U", // base32
+            "abc123",
+            "not-a-hash",
+        ];
+        let core = |strict: bool| {
+            SearchCore {
+                providers: vec![Arc::new(FixedHashProvider { name: "junk".to_string(), hashes: hashes.clone() })],
+                ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+            }
+            .with_strict_magnet_validation(strict)
+        };
+
+        let strict = core(true).search_multi_page("movie", 1).await.unwrap();
+        assert_eq!(strict.len(), 2);
+        assert!(strict.iter().all(|r| extract_infohash(&r.magnet_link).is_some()));
+
+        // 宽松模式下原样保留
+        let lenient = core(false).search_multi_page("movie", 1).await.unwrap();
+        let magnets: Vec<_> = lenient.iter().map(|r| r.magnet_link.as_str()).collect();
+        assert!(magnets.contains(&"magnet:?xt=urn:btih:abc123"));
+        assert!(magnets.contains(&"magnet:?xt=urn:btih:not-a-hash"));
+        assert_eq!(lenient.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_max_total_results_caps_merged_results() {
+        let shared = "1111111111111111111111111111111111111111";
+        let core = |max_total_results| {
+            SearchCore {
+                providers: vec![
+                    Arc::new(FixedHashProvider {
+                        name: "a".to_string(),
+                        hashes: vec![shared, "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"],
+                    }),
+                    Arc::new(FixedHashProvider {
+                        name: "b".to_string(),
+                        hashes: vec![shared, "cccccccccccccccccccccccccccccccccccccccc", "dddddddddddddddddddddddddddddddddddddddd"],
+                    }),
+                ],
+                ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+            }
+            .with_max_total_results(max_total_results)
+        };
+
+        assert_eq!(core(None).search_multi_page("movie", 1).await.unwrap().len(), 5);
+        // 上限在去重之后生效，6 条原始结果去重为 5 条时不会被截断
+        assert_eq!(core(Some(5)).search_multi_page("movie", 1).await.unwrap().len(), 5);
+
+        let capped = core(Some(4)).search_multi_page("movie", 1).await.unwrap();
+        assert_eq!(capped.len(), 4);
+        assert!(capped.iter().any(|r| r.provider_name == "b"));
+
+        // 快速搜索与单引擎搜索同样受上限约束
+        let (quick, _) = core(Some(3)).quick_search("movie", 1).await.unwrap();
+        assert_eq!(quick.len(), 3);
+        assert_eq!(core(Some(2)).search_single_provider("a", "movie", 1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_provider_results_keep_site_order_as_rank() {
+        let core = SearchCore {
+            providers: vec![Arc::new(StaticProvider { name: "solo".to_string(), per_page: 3 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        let mut results = core.search_single_provider("solo", "movie", PageRange::new(1, 2)).await.unwrap();
+        let ranks: Vec<_> = results.iter().map(|r| r.original_rank).collect();
+        assert_eq!(ranks, vec![0, 1, 2, 3, 4, 5]);
+
+        // 没有分数时全部相同，按站点顺序排列
+        results.reverse();
+        crate::filter::sort_results(&mut results, crate::filter::SortKey::ScoreDesc);
+        let ranks: Vec<_> = results.iter().map(|r| r.original_rank).collect();
+        assert_eq!(ranks, vec![0, 1, 2, 3, 4, 5]);
+    }
 
-                    other_search_futures.push(search_future);
-                }
-            }
+    #[test]
+    fn test_providers_ordered_by_recorded_latency() {
+        let core = create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default());
+        let provider = |name: &str| Arc::new(StaticProvider { name: name.to_string(), per_page: 0 }) as Arc<dyn SearchProvider>;
+        let core = SearchCore {
+            providers: vec![provider("slow"), provider("unknown"), provider("fast"), provider("medium")],
+            ..core
+        };
+        let names = |core: &SearchCore| core.providers.iter().map(|p| p.name().to_string()).collect::<Vec<_>>();
+
+        // 没有记录时保持配置顺序
+        let core = core.with_latency_order(&BTreeMap::new());
+        assert_eq!(names(&core), vec!["slow", "unknown", "fast", "medium"]);
+
+        let latencies = BTreeMap::from([
+            ("slow".to_string(), 2400),
+            ("fast".to_string(), 150),
+            ("medium".to_string(), 800),
+        ]);
+        let core = core.with_latency_order(&latencies);
+        assert_eq!(names(&core), vec!["fast", "medium", "slow", "unknown"]);
+    }
 
-            // 并发执行其他搜索任务
-            let results = join_all(other_search_futures).await;
+    #[tokio::test]
+    async fn test_self_test_flags_engines_returning_no_results() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(StaticProvider { name: "working".to_string(), per_page: 3 }),
+                Arc::new(StaticProvider { name: "changed".to_string(), per_page: 0 }),
+                Arc::new(BrokenProvider),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
 
-            for result in results {
-                match result {
-                    Ok(mut page_results) => {
-                        all_results.append(&mut page_results);
-                    }
-                    Err(e) => {
-                        println!("⚠️ Search task failed: {e}");
-                        // 继续处理其他结果，不因为单个任务失败而中断
-                    }
-                }
-            }
-        }
+        let report = core.self_test("ubuntu").await;
+        let statuses: Vec<(&str, SelfTestStatus, usize)> = report
+            .iter()
+            .map(|r| (r.name.as_str(), r.status, r.result_count))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("working", SelfTestStatus::Ok, 3),
+                ("changed", SelfTestStatus::Empty, 0),
+                ("broken", SelfTestStatus::Failed, 0),
+            ]
+        );
+        assert!(report[2].error.as_deref().unwrap().contains("502"));
+    }
 
-        println!("🎯 Total results collected from all providers: {}", all_results.len());
-        Ok(all_results)
+    #[tokio::test]
+    async fn test_provider_metrics_track_successes_and_failures() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(StaticProvider { name: "steady".to_string(), per_page: 2 }),
+                Arc::new(FlakyProvider),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+
+        core.search_multi_page("movie", 3).await.unwrap();
+
+        let metrics = core.metrics();
+        let flaky = metrics.iter().find(|m| m.name == "flaky").unwrap();
+        assert_eq!((flaky.requests, flaky.successes, flaky.failures, flaky.results_count), (3, 2, 1, 2));
+        let steady = metrics.iter().find(|m| m.name == "steady").unwrap();
+        assert_eq!((steady.requests, steady.successes, steady.failures, steady.results_count), (3, 3, 0, 6));
+
+        assert!(last_search_metrics().iter().any(|m| m.name == "flaky" && m.failures == 1));
+
+        // 新的搜索开始后不再报告之前搜索的引擎
+        reset_last_search_metrics();
+        assert!(!last_search_metrics().iter().any(|m| m.name == "steady"));
     }
 
+    #[test]
+    fn test_request_jitter_stays_within_bound() {
+        let jitter = RequestJitter::with_seed(250, 42);
+        let delays: Vec<_> = (0..1000).map(|_| jitter.next_delay()).collect();
 
+        assert!(delays.iter().all(|d| *d <= Duration::from_millis(250)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
 
-    /// 单页搜索（向后兼容）
-    #[allow(dead_code)]
-    pub async fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
-        self.search_multi_page(query, 1).await
+        // 相同种子产生相同序列
+        let replay = RequestJitter::with_seed(250, 42);
+        assert!(delays.iter().take(10).all(|d| *d == replay.next_delay()));
     }
-}
 
-/// 创建带有AI功能的搜索核心
-pub fn create_ai_enhanced_search_core(
-    extraction_config: Option<LlmConfig>,
-    analysis_config: Option<LlmConfig>, // 保持向后兼容，但现在只用于HTML提取
-    priority_keywords: Vec<String>,
-    custom_engines: Vec<(String, String)>, // (name, url_template) pairs
-    include_clmclm: bool // 是否包含 clmclm.com
-) -> SearchCore {
-    let mut providers: Vec<Arc<dyn SearchProvider>> = Vec::new();
+    #[tokio::test(start_paused = true)]
+    async fn test_results_order_is_stable_when_providers_finish_out_of_order() {
+        let core = SearchCore {
+            providers: vec![
+                Arc::new(SlowProvider { delay: Duration::from_millis(100) }),
+                Arc::new(StaticProvider { name: "fast".to_string(), per_page: 2 }),
+            ],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
 
-    // 只有在明确启用时才添加 clmclm.com 提供商
-    if include_clmclm {
-        println!("✅ Adding clmclm.com provider");
-        providers.push(Arc::new(ClmclmProvider::new()));
+        let started = tokio::time::Instant::now();
+        let results = core.search_multi_page("q", 2).await.unwrap();
+        // 慢提供商的两页仍然并发执行
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+
+        let titles: Vec<_> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["q slow 1-0", "q slow 2-0", "q fast 1-0", "q fast 1-1", "q fast 2-0", "q fast 2-1"]);
     }
 
-    // 为自定义搜索引擎创建AI增强的提供商
-    // 优先使用 extraction_config，如果没有则使用 analysis_config（向后兼容）
-    let html_extraction_config = extraction_config.or(analysis_config);
+    #[test]
+    fn test_blank_ai_title_follows_empty_title_policy() {
+        let hash = "dddddddddddddddddddddddddddddddddddddddd";
+        let batch = || crate::llm_service::BatchExtractBasicInfoResult {
+            results: vec![crate::llm_service::ExtractedBasicInfo {
+                title: "  ".to_string(),
+                magnet_link: format!("magnet:?xt=urn:btih:{hash}&dn=Some.Show.S01E01.1080p"),
+                file_size: None,
+                source_url: None,
+                thumbnail_url: None,
+                confidence: 1.0,
+            }],
+        };
+        let provider = GenericProvider::new("ai".to_string(), "https://example.com/s?q={keyword}".to_string());
+
+        let derived = provider.parse_ai_html_response_from_batch(batch(), None).unwrap();
+        assert_eq!(derived.len(), 1);
+        assert_eq!(derived[0].title, "Some.Show.S01E01.1080p");
+
+        let provider = provider.with_empty_title_policy(EmptyTitlePolicy::Drop);
+        assert!(provider.parse_ai_html_response_from_batch(batch(), None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_provider_stops_at_max_results_hint() {
+        let server = MockServer::start();
+        let rows: String = (0..5)
+            .map(|i| format!(r#"<tr><td><a href="/t/{i}">Row number {i}</a></td><td><a href="magnet:?xt=urn:btih:{i:0>40}">m</a></td></tr>"#))
+            .collect();
+        server.mock(|when, then| {
+            when.method(GET).path("/s");
+            then.status(200).body(format!("<table>{rows}</table>"));
+        });
+
+        let provider = GenericProvider::new("limited".to_string(), format!("{}/s?q={{keyword}}", server.base_url()));
+        let results = provider.search("row", 1, Some(2)).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].title, "Row number 1");
+        assert_eq!(provider.search("row", 1, None).await.unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_extraction_token_estimate_follows_truncation_limit() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/s");
+            then.status(200).body(format!("<html><body>{}</body></html>", "<tr><td>row</td></tr>".repeat(1000)));
+        });
+        let provider = GenericProvider::new("big".to_string(), format!("{}/s?q={{keyword}}", server.base_url()));
+
+        let small = provider.estimate_extraction_tokens("row", 4_000).await.unwrap();
+        let large = provider.estimate_extraction_tokens("row", 12_000).await.unwrap();
+        let full = provider.estimate_extraction_tokens("row", 1_000_000).await.unwrap();
+
+        assert_eq!(small.truncated_chars, 4_000);
+        assert_eq!(large.truncated_chars, 12_000);
+        assert_eq!(full.truncated_chars, full.html_chars);
+        // HTML 都是 ASCII，每多发送 4 个字符约多 1 个 token
+        assert_eq!(large.estimated_tokens - small.estimated_tokens, 2_000);
+        assert!(full.estimated_tokens > large.estimated_tokens);
+
+        // 按字符而不是字节截断，多字节字符不会被截断在中间
+        assert_eq!(truncate_chars("电影资源", 2), "电影");
+        assert_eq!(truncate_chars("电影", 10), "电影");
+    }
+
+    #[tokio::test]
+    async fn test_ai_disabled_engine_never_calls_llm() {
+        let server = MockServer::start();
+        let hash = "eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+        server.mock(|when, then| {
+            when.method(GET).path("/s");
+            then.status(200).body(format!(
+                r#"<table><tr><td><a href="/t/1">Plain Table Row</a></td><td><a href="magnet:?xt=urn:btih:{hash}">m</a></td></tr></table>"#
+            ));
+        });
+        let llm = server.mock(|when, then| {
+            when.method(POST).path_contains("generateContent");
+            then.status(500);
+        });
+
+        let extraction_config = LlmConfig { api_base: server.base_url(), ..llm_config() };
+        let engine = CustomEngineConfig::new("plain".to_string(), format!("{}/s?q={{keyword}}", server.base_url()))
+            .with_use_ai(false);
+        let core = create_ai_enhanced_search_core(Some(extraction_config), None, Vec::new(), vec![engine], None, EmptyTitlePolicy::default());
+
+        let results = core.search_multi_page("plain", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Plain Table Row");
+        llm.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_generic_provider_parses_with_configured_selectors() {
+        let server = MockServer::start();
+        let hash_a = "1111111111111111111111111111111111111111";
+        let hash_b = "2222222222222222222222222222222222222222";
+        server.mock(|when, then| {
+            when.method(GET).path("/list");
+            then.status(200).body(format!(
+                r#"<ul class="torrents">
+                    <li class="item"><a class="name" href="/detail/1">Movie A 1080p</a><span class="size">1.4 GB</span><time>2024-03-01</time><a class="dl" href="magnet:?xt=urn:btih:{hash_a}">get</a></li>
+                    <li class="item"><a class="name" href="/detail/2">Movie B 720p</a><span class="size">700 MB</span><a class="dl" href="magnet:?xt=urn:btih:{hash_b}">get</a></li>
+                    <li class="item"><a class="name" href="/detail/3">No magnet here</a></li>
+                </ul>"#
+            ));
+        });
+
+        let selectors = GenericSelectors {
+            row: "li.item".to_string(),
+            title: "a.name".to_string(),
+            magnet: "a.dl".to_string(),
+            size: Some("span.size".to_string()),
+            date: Some("time".to_string()),
+        };
+        let provider = GenericProvider::new("css".to_string(), format!("{}/list?q={{keyword}}", server.base_url()))
+            .with_selectors(Some(selectors));
+        let results = provider.search("movie", 1, None).await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Movie A 1080p");
+        assert_eq!(results[0].file_size.as_deref(), Some("1.4 GB"));
+        assert_eq!(results[0].upload_date.as_deref(), Some("2024-03-01"));
+        assert_eq!(results[0].source_url, Some(format!("{}/detail/1", server.base_url())));
+        assert_eq!(results[1].magnet_link, format!("magnet:?xt=urn:btih:{hash_b}"));
+        assert_eq!(results[1].upload_date, None);
+
+        // 选择器不匹配时回退到通用解析
+        let fallback = GenericProvider::new("css".to_string(), format!("{}/list?q={{keyword}}", server.base_url()))
+            .with_selectors(Some(GenericSelectors {
+                row: "div.none".to_string(),
+                title: "a".to_string(),
+                magnet: default_generic_magnet_selector(),
+                size: None,
+                date: None,
+            }));
+        assert_eq!(fallback.search("movie", 1, None).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_row_images_resolved_into_thumbnail_urls() {
+        let page_url = url::Url::parse("https://posters.test/search/movie/1").unwrap();
+        let provider = GenericProvider::new("thumbs".to_string(), "https://posters.test/search/{keyword}/{page}".to_string());
+
+        let list = r#"
+            <ul>
+                <li class="item">
+                    <img src="data:image/gif;base64,R0lGOD" data-src="/posters/1.jpg">
+                    <a class="name" href="/detail/1">Poster Movie</a>
+                    <a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">magnet</a>
+                </li>
+                <li class="item">
+                    <a class="name" href="/detail/2">No Poster</a>
+                    <a href="magnet:?xt=urn:btih:2222222222222222222222222222222222222222">magnet</a>
+                </li>
+            </ul>
+        "#;
+        let selectors = GenericSelectors {
+            row: "li.item".to_string(),
+            title: "a.name".to_string(),
+            magnet: default_generic_magnet_selector(),
+            size: None,
+            date: None,
+        };
+        let results = provider.parse_with_selectors(list, &selectors, Some(&page_url), None).unwrap();
+        assert_eq!(results[0].thumbnail_url.as_deref(), Some("https://posters.test/posters/1.jpg"));
+        assert_eq!(results[1].thumbnail_url, None);
+
+        let table = r#"
+            <table><tr>
+                <td><img src="thumbs/3.png"><a href="/detail/3">Table Poster Movie</a></td>
+                <td>1.4 GB</td>
+                <td><a href="magnet:?xt=urn:btih:3333333333333333333333333333333333333333">magnet</a></td>
+            </tr></table>
+        "#;
+        let results = provider.parse_generic_results(table, Some(&page_url), None).unwrap();
+        assert_eq!(results[0].thumbnail_url.as_deref(), Some("https://posters.test/search/movie/thumbs/3.png"));
+    }
+
+    #[tokio::test]
+    async fn test_generic_provider_reuses_session_cookie_across_pages() {
+        let server = MockServer::start();
+        let magnet = "magnet:?xt=urn:btih:3333333333333333333333333333333333333333";
+        server.mock(|when, then| {
+            when.method(GET).path("/search/1");
+            then.status(200)
+                .header("Set-Cookie", "session=abc123; Path=/")
+                .body(format!(r#"<a href="{magnet}">Page One Result</a>"#));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/search/2").header("Cookie", "session=abc123");
+            then.status(200).body(format!(r#"<a href="{magnet}&dn=two">Page Two Result</a>"#));
+        });
+
+        let provider = GenericProvider::new(
+            "cookie-test".to_string(),
+            format!("{}/search/{{page}}?q={{keyword}}", server.base_url()),
+        );
+        assert!(!provider.search("movie", 1, None).await.unwrap().is_empty());
+        // 没有携带 cookie 时第 2 页不会命中 mock
+        assert!(!provider.search("movie", 2, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generic_provider_logs_in_before_searching() {
+        let server = MockServer::start();
+        let login = server.mock(|when, then| {
+            when.method(POST).path("/login").body_contains("username=alice").body_contains("password=secret");
+            then.status(200)
+                .header("Set-Cookie", "uid=42; Path=/")
+                .body("Welcome back, alice");
+        });
+        let search = server.mock(|when, then| {
+            when.method(GET).path("/browse/1").header("Cookie", "uid=42");
+            then.status(200)
+                .body(r#"<a href="magnet:?xt=urn:btih:4444444444444444444444444444444444444444">Private Result</a>"#);
+        });
+
+        let auth = EngineAuth {
+            login_url: format!("{}/login", server.base_url()),
+            fields: BTreeMap::from([
+                ("username".to_string(), "alice".to_string()),
+                ("password".to_string(), "secret".to_string()),
+            ]),
+            success_text: Some("Welcome back".to_string()),
+            success_cookie: Some("uid".to_string()),
+        };
+        let provider = || {
+            GenericProvider::new("auth-test".to_string(), format!("{}/browse/{{page}}?q={{keyword}}", server.base_url()))
+                .with_auth(Some(auth.clone()))
+        };
+
+        // 没有登录 cookie 时搜索不会命中 mock
+        assert_eq!(provider().search("movie", 1, None).await.unwrap().len(), 1);
+        // 同一次运行中只登录一次
+        assert_eq!(provider().search("movie", 1, None).await.unwrap().len(), 1);
+        login.assert_hits(1);
+        search.assert_hits(2);
+
+        // 成功判断不满足时搜索失败，不会继续请求结果页
+        let rejected = GenericProvider::new("auth-test-rejected".to_string(), format!("{}/browse/{{page}}", server.base_url()))
+            .with_auth(Some(EngineAuth { success_text: Some("Logged out".to_string()), ..auth.clone() }));
+        assert!(rejected.search("movie", 1, None).await.is_err());
+        search.assert_hits(2);
+    }
 
-    if let Some(extract_config) = html_extraction_config {
-        let llm_client: Arc<dyn LlmClient> = Arc::new(GeminiClient::new());
+    #[tokio::test]
+    async fn test_generic_provider_logs_in_again_when_session_expires() {
+        let server = MockServer::start();
+        // 先定义的 mock 优先匹配：带 cookie 的请求返回结果，其余请求视为会话失效
+        server.mock(|when, then| {
+            when.method(GET).header("Cookie", "uid=42");
+            then.status(200)
+                .body(r#"<a href="magnet:?xt=urn:btih:5555555555555555555555555555555555555555">Private Result</a>"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/forbidden/1");
+            then.status(403);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/redirect/1");
+            then.status(302).header("Location", "/login?next=%2Fredirect%2F1");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/login");
+            then.status(200).body("Please log in");
+        });
+        let login = server.mock(|when, then| {
+            when.method(POST).path("/login");
+            then.status(200).header("Set-Cookie", "uid=42; Path=/").body("Welcome back");
+        });
 
-        for (name, url_template) in custom_engines {
-            println!("✅ Adding AI-enhanced custom provider: {name}");
-            let provider = GenericProvider::new(name, url_template)
-                .with_llm_client_and_config(llm_client.clone(), extract_config.clone())
-                .with_priority_keywords(priority_keywords.clone());
-            providers.push(Arc::new(provider));
+        let auth = EngineAuth {
+            login_url: format!("{}/login", server.base_url()),
+            fields: BTreeMap::new(),
+            success_text: None,
+            success_cookie: None,
+        };
+        for (name, path) in [("expiry-forbidden", "forbidden"), ("expiry-redirect", "redirect")] {
+            // 模拟之前登录过、但会话 cookie 已失效
+            ENGINE_SESSIONS.lock().await.insert(name.to_string(), auth.clone());
+            let provider = GenericProvider::new(name.to_string(), format!("{}/{path}/{{page}}?q={{keyword}}", server.base_url()))
+                .with_auth(Some(auth.clone()));
+            assert_eq!(provider.search("movie", 1, None).await.unwrap().len(), 1);
         }
-    } else {
-        // 如果没有LLM配置，创建基础的自定义提供商
-        for (name, url_template) in custom_engines {
-            println!("✅ Adding basic custom provider: {name}");
-            let provider = GenericProvider::new(name, url_template);
-            providers.push(Arc::new(provider));
+        login.assert_hits(2);
+    }
+
+    #[test]
+    fn test_append_trackers_skips_present_and_duplicate_trackers() {
+        let magnet = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Movie&tr=udp%3A%2F%2Ftracker.a.org%3A1337";
+        let extra = vec![
+            "udp://tracker.a.org:1337".to_string(),
+            " udp://tracker.b.org:80 ".to_string(),
+            "udp://tracker.b.org:80".to_string(),
+            String::new(),
+        ];
+
+        let augmented = append_trackers(magnet, &extra);
+        assert_eq!(augmented, format!("{magnet}&tr=udp%3A%2F%2Ftracker.b.org%3A80"));
+        // 再次追加不会产生重复
+        assert_eq!(append_trackers(&augmented, &extra), augmented);
+        assert_eq!(append_trackers(magnet, &[]), magnet);
+    }
+
+    #[test]
+    fn test_canonicalize_magnet_is_order_and_case_insensitive() {
+        let first = "magnet:?xt=urn:btih:0123456789ABCDEF0123456789ABCDEF01234567&dn=Some+Movie%20%201080p&tr=udp%3A%2F%2Ftracker.b.org%3A80&tr=udp://tracker.a.org:1337";
+        let second = " magnet:?tr=udp://tracker.a.org:1337&amp;dn=Some%20Movie%201080p&xt=urn:btih:0123456789abcdef0123456789abcdef01234567&tr=udp://tracker.b.org:80&tr=udp://tracker.a.org:1337&xl=1024 ";
+
+        let canonical = canonicalize_magnet(first);
+        assert_eq!(canonical, canonicalize_magnet(second));
+        assert_eq!(
+            canonical,
+            "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Some%20Movie%201080p&tr=udp%3A%2F%2Ftracker.a.org%3A1337&tr=udp%3A%2F%2Ftracker.b.org%3A80"
+        );
+        // 无法解析 infohash 时保持原样
+        assert_eq!(canonicalize_magnet(" magnet:?xt=urn:btih:abc "), "magnet:?xt=urn:btih:abc");
+    }
+
+    /// 每次提取都返回固定结果的模拟 LLM 客户端
+    fn canned_llm_client(results: Vec<crate::llm_service::ExtractedBasicInfo>) -> MockLlmClient {
+        MockLlmClient::new().with_extract(move |_| Ok(crate::llm_service::BatchExtractBasicInfoResult { results: results.clone() }))
+    }
+
+    #[tokio::test]
+    async fn test_quick_search_defers_ai_extraction() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/quick/1");
+            then.status(200)
+                .body(r#"<a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111&dn=Quick.Result">m</a>"#);
+        });
+        let llm = Arc::new(canned_llm_client(vec![crate::llm_service::ExtractedBasicInfo {
+            title: "Refined Result".to_string(),
+            magnet_link: "magnet:?xt=urn:btih:2222222222222222222222222222222222222222".to_string(),
+            file_size: None,
+            source_url: None,
+            thumbnail_url: None,
+            confidence: 1.0,
+        }]));
+        let config = llm_config();
+        let provider = GenericProvider::new("quick".to_string(), format!("{}/quick/{{page}}?q={{keyword}}", server.base_url()))
+            .with_llm_client_and_config(llm.clone(), config);
+        let core = SearchCore {
+            providers: vec![Arc::new(provider), Arc::new(StaticProvider { name: "static".to_string(), per_page: 1 })],
+            ..create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+        };
+        let calls = || llm.calls();
+
+        // 基础结果返回时还没有调用 AI
+        let (results, deferred) = core.quick_search("movie", 1).await.unwrap();
+        assert_eq!(calls(), 0);
+        let mut titles: Vec<_> = results.iter().map(|r| r.title.as_str()).collect();
+        titles.sort_unstable();
+        assert_eq!(titles, vec!["Quick.Result", "movie static 1-0"]);
+        assert_eq!(deferred.len(), 1);
+
+        // AI 提取的结果随后以批次送达
+        let (tx, mut rx) = mpsc::channel(4);
+        core.refine_deferred("movie", deferred, tx).await;
+        let batch = rx.recv().await.unwrap();
+        assert_eq!(calls(), 1);
+        assert_eq!(batch.provider_name, "quick");
+        assert_eq!(batch.results.iter().map(|r| r.title.as_str()).collect::<Vec<_>>(), vec!["Refined Result"]);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parse_order_stops_at_first_strategy_with_results() {
+        let html = r#"<ul>
+            <li class="item"><a class="name" href="/d/1">Selector Title</a><a href="magnet:?xt=urn:btih:1111111111111111111111111111111111111111">m</a></li>
+        </ul>"#;
+        let config = llm_config();
+        let ai_result = crate::llm_service::ExtractedBasicInfo {
+            title: "AI Title".to_string(),
+            magnet_link: "magnet:?xt=urn:btih:2222222222222222222222222222222222222222".to_string(),
+            file_size: None,
+            source_url: None,
+            thumbnail_url: None,
+            confidence: 1.0,
+        };
+        let selectors = |row: &str| GenericSelectors {
+            row: row.to_string(),
+            title: "a.name".to_string(),
+            magnet: default_generic_magnet_selector(),
+            size: None,
+            date: None,
+        };
+        let provider = |row: &str, results: Vec<crate::llm_service::ExtractedBasicInfo>, order: Vec<ParseStrategy>| {
+            let llm = Arc::new(canned_llm_client(results));
+            let provider = GenericProvider::new("chain".to_string(), "https://example.com/s?q={keyword}".to_string())
+                .with_llm_client_and_config(llm.clone(), config.clone())
+                .with_selectors(Some(selectors(row)))
+                .with_parse_order(order);
+            (provider, llm)
+        };
+        let titles = |results: Vec<SearchResult>| results.into_iter().map(|r| r.title).collect::<Vec<_>>();
+        let calls = |llm: &MockLlmClient| llm.calls();
+
+        // 选择器命中时不再调用 AI
+        let (chain, llm) = provider("li.item", vec![ai_result.clone()], default_parse_order());
+        assert_eq!(titles(chain.parse_page(html, None, None).await.unwrap()), vec!["Selector Title"]);
+        assert_eq!(calls(&llm), 0);
+
+        // 选择器没有结果时交给 AI
+        let (chain, llm) = provider("div.none", vec![ai_result.clone()], default_parse_order());
+        assert_eq!(titles(chain.parse_page(html, None, None).await.unwrap()), vec!["AI Title"]);
+        assert_eq!(calls(&llm), 1);
+
+        // AI 也没有结果时回退到启发式解析
+        let (chain, llm) = provider("div.none", Vec::new(), default_parse_order());
+        let heuristic = titles(chain.parse_generic_results(html, None, None).unwrap());
+        assert_eq!(heuristic.len(), 1);
+        assert_eq!(titles(chain.parse_page(html, None, None).await.unwrap()), heuristic);
+        assert_eq!(calls(&llm), 1);
+
+        // 自定义顺序：启发式在前时不调用 AI
+        let (chain, llm) = provider("div.none", vec![ai_result], vec![ParseStrategy::Heuristic, ParseStrategy::Ai, ParseStrategy::Heuristic]);
+        assert_eq!(chain.parse_order, vec![ParseStrategy::Heuristic, ParseStrategy::Ai]);
+        assert_eq!(titles(chain.parse_page(html, None, None).await.unwrap()), heuristic);
+        assert_eq!(calls(&llm), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ai_prose_response_is_salvaged() {
+        let hash_a = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        let hash_b = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+        let response = format!(
+            "Sure! I found these torrents on the page:\n\n1. **Movie A 1080p** - magnet:?xt=urn:btih:{hash_a}&amp;dn=Movie.A\n2. magnet:?xt=urn:btih:{hash_b}&dn=Movie.B.720p\n\nLet me know if you need anything else."
+        );
+        // 模型返回的是非JSON文本
+        let llm_client: Arc<dyn LlmClient> = Arc::new(MockLlmClient::new().with_extract(move |_| {
+            Err(crate::llm_service::ExtractionParseError {
+                raw_response: response.clone(),
+                message: "expected value at line 1 column 1".to_string(),
+            }
+            .into())
+        }));
+        let config = llm_config();
+        let provider = GenericProvider::new("ai".to_string(), "https://example.com/s?q={keyword}".to_string())
+            .with_llm_client_and_config(llm_client.clone(), config);
+
+        let results = provider.call_ai_for_html_analysis("<html></html>", None, None, llm_client).await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Movie A 1080p");
+        assert_eq!(results[0].magnet_link, format!("magnet:?xt=urn:btih:{hash_a}&dn=Movie.A"));
+        // 同一行没有标题文字时从 dn 推导
+        assert_eq!(results[1].title, "Movie.B.720p");
+    }
+
+    #[tokio::test]
+    async fn test_extraction_respects_shared_concurrency_limit() {
+        let config = LlmConfig { max_concurrency: Some(2), ..llm_config() };
+        for (limit, expected_peak) in [(1, 1), (2, 2)] {
+            // 记录同时进行中的提取请求峰值
+            let peak_client = Arc::new(
+                MockLlmClient::new()
+                    .with_extract(|_| Ok(crate::llm_service::BatchExtractBasicInfoResult { results: Vec::new() }))
+                    .with_delay(Duration::from_millis(20)),
+            );
+            let llm_client: Arc<dyn LlmClient> = peak_client.clone();
+            let limiter = Arc::new(tokio::sync::Semaphore::new(limit));
+            let providers: Vec<GenericProvider> = (0..4)
+                .map(|i| {
+                    GenericProvider::new(format!("ai{i}"), "https://example.com/s?q={keyword}".to_string())
+                        .with_llm_client_and_config(llm_client.clone(), config.clone())
+                        .with_extraction_limiter(Some(limiter.clone()))
+                })
+                .collect();
+
+            join_all(providers.iter().map(|p| p.call_ai_for_html_analysis("<html></html>", None, None, llm_client.clone()))).await;
+
+            assert_eq!(peak_client.peak(), expected_peak);
         }
     }
 
-    SearchCore { providers }
-}
+    #[tokio::test]
+    async fn test_parse_html_with_provider_runs_offline_parsers() {
+        let clmclm_html = r#"
+            <div class="ssbox">
+                <div class="title"><h3><a href="/detail/1">Fixture Title</a></h3></div>
+                <div class="sbar"><a href="magnet:?xt=urn:btih:12345">Magnet Link</a><span>大小: 1.2GB</span></div>
+                <ul><li>Fixture.mkv 1.2GB</li></ul>
+            </div>
+        "#;
+        let clmclm = ClmclmProvider::with_base_url("http://clmclm.test");
+        let results = parse_html_with_provider(&clmclm, "", clmclm_html).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Fixture Title");
+        assert_eq!(results[0].file_size.as_deref(), Some("1.2GB"));
+
+        let generic_html = r#"<table>
+            <tr><td><a href="/torrent/7">Generic Fixture 720p</a></td><td>700 MB</td>
+                <td><a href="magnet:?xt=urn:btih:7777777777777777777777777777777777777777">m</a></td></tr>
+        </table>"#;
+        let generic = GenericProvider::new("fixture".to_string(), "https://fixture.example/search?q={keyword}&p={page}".to_string());
+        let results = parse_html_with_provider(&generic, "https://fixture.example/search?q={keyword}&p={page}", generic_html)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Generic Fixture 720p");
+        assert_eq!(results[0].source_url.as_deref(), Some("https://fixture.example/torrent/7"));
+        assert_eq!(results[0].provider_name, "fixture");
+    }
+
+    #[test]
+    fn test_ai_output_duplicate_magnets_are_dropped() {
+        let hash = "eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+        let info = |title: &str, magnet_link: String| crate::llm_service::ExtractedBasicInfo {
+            title: title.to_string(),
+            magnet_link,
+            file_size: None,
+            source_url: None,
+            thumbnail_url: None,
+            confidence: 1.0,
+        };
+        let batch = crate::llm_service::BatchExtractBasicInfoResult {
+            results: vec![
+                info("First Copy", format!("magnet:?xt=urn:btih:{hash}")),
+                info("Second Copy", format!("magnet:?xt=urn:btih:{}&dn=dup", hash.to_uppercase())),
+            ],
+        };
+        let provider = GenericProvider::new("ai".to_string(), "https://example.com/s?q={keyword}".to_string());
 
+        let results = provider.parse_ai_html_response_from_batch(batch, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "First Copy");
+    }
 
+    #[test]
+    fn test_ai_result_keeps_original_title() {
+        let batch = crate::llm_service::BatchExtractBasicInfoResult {
+            results: vec![crate::llm_service::ExtractedBasicInfo {
+                title: " <b>Movie</b> &amp; Friends ".to_string(),
+                magnet_link: "magnet:?xt=urn:btih:ffffffffffffffffffffffffffffffffffffffff".to_string(),
+                file_size: None,
+                source_url: None,
+                thumbnail_url: None,
+                confidence: 1.0,
+            }],
+        };
+        let provider = GenericProvider::new("ai".to_string(), "https://example.com/s?q={keyword}".to_string());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use httpmock::prelude::*;
-    // removed redundant single-component import per clippy
+        let results = provider.parse_ai_html_response_from_batch(batch, None).unwrap();
+        assert_eq!(results[0].title, "Movie & Friends");
+        assert_eq!(results[0].original_title, "<b>Movie</b> &amp; Friends");
+    }
+
+    /// 记录同时进行的详情页补全数量的测试提供商，标题含 "stuck" 的结果永远不会补全完成
+    struct EnrichCountingProvider {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl SearchProvider for EnrichCountingProvider {
+        fn name(&self) -> &str {
+            "enrich"
+        }
+
+        async fn search(&self, query: &str, page: u32, max_results: Option<usize>) -> Result<Vec<SearchResult>> {
+            StaticProvider { name: "enrich".to_string(), per_page: 10 }.search(query, page, max_results).await
+        }
+
+        async fn enrich(&self, result: &mut SearchResult) -> Result<()> {
+            use std::sync::atomic::Ordering;
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(current, Ordering::SeqCst);
+            // 超时被取消时同样要减少计数
+            struct Finished<'a>(&'a std::sync::atomic::AtomicUsize);
+            impl Drop for Finished<'_> {
+                fn drop(&mut self) {
+                    self.0.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+            let _finished = Finished(&self.in_flight);
+
+            result.file_list = vec!["partial".to_string()];
+            let delay = if result.title.contains("stuck") { Duration::from_secs(30) } else { Duration::from_millis(20) };
+            tokio::time::sleep(delay).await;
+            result.file_list = vec!["movie.mkv".to_string()];
+            Ok(())
+        }
+    }
 
     #[tokio::test]
-    async fn test_search_successful() {
-        // Start a mock server
-        let server = MockServer::start();
+    async fn test_enrichment_respects_concurrency_limit_and_timeout() {
+        let provider = Arc::new(EnrichCountingProvider {
+            in_flight: Default::default(),
+            peak: Default::default(),
+        });
+        let core = create_ai_enhanced_search_core(None, None, Vec::new(), Vec::new(), None, EmptyTitlePolicy::default())
+            .with_deep_enrich(true)
+            .with_enrich_limits(3, Some(Duration::from_millis(200)));
+
+        let dyn_provider: Arc<dyn SearchProvider> = provider.clone();
+        let mut results = dyn_provider.search("movie", 1, None).await.unwrap();
+        results[4].title = "movie stuck".to_string();
+        results[4].file_list = vec!["from listing".to_string()];
+
+        let enriched = core.enrich_results(&dyn_provider, results).await;
+
+        assert_eq!(enriched.len(), 10);
+        assert_eq!(provider.peak.load(std::sync::atomic::Ordering::SeqCst), 3);
+        // 超时的结果保留补全前的文件列表，其余结果正常补全
+        assert_eq!(enriched[4].file_list, vec!["from listing"]);
+        assert!(enriched.iter().enumerate().filter(|(i, _)| *i != 4).all(|(_, r)| r.file_list == vec!["movie.mkv"]));
+    }
 
-        // Create a mock
+    #[tokio::test]
+    async fn test_magnet_xl_fills_missing_file_size() {
+        let mut results = StaticProvider { name: "alpha".to_string(), per_page: 2 }.search("movie", 1, None).await.unwrap();
+
+        let result = &mut results[0];
+        result.magnet_link = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Movie&xl=1610612736&so=0,2,4-6".to_string();
+        result.apply_magnet_params();
+        assert_eq!(result.exact_length, Some(1_610_612_736));
+        assert_eq!(result.select_only.as_deref(), Some("0,2,4-6"));
+        assert_eq!(result.file_size.as_deref(), Some("1.50 GB"));
+
+        // 页面已提供大小时保持不变
+        let scraped = &mut results[1];
+        scraped.magnet_link = "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&amp;xl=1024".to_string();
+        scraped.file_size = Some("700 MB".to_string());
+        scraped.apply_magnet_params();
+        assert_eq!(scraped.exact_length, Some(1024));
+        assert_eq!(scraped.file_size.as_deref(), Some("700 MB"));
+
+        assert_eq!(magnet_exact_length("magnet:?xt=urn:btih:abc&xl=abc"), None);
+        assert_eq!(magnet_select_only("magnet:?xt=urn:btih:abc&so=1,-3"), None);
+        assert_eq!(magnet_select_only("magnet:?xt=urn:btih:abc&so=1-2-3"), None);
+    }
+
+    #[tokio::test]
+    async fn test_generic_provider_sends_global_and_engine_headers() {
+        let server = MockServer::start();
         let mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/search-test-1-1-1.html");
-            then.status(200)
-                .header("content-type", "text/html; charset=UTF-8")
-                .body(r#"
-                    <!DOCTYPE html>
-                    <html>
-                    <body>
-                        <div class="ssbox">
-                            <div class="title"><h3><a href="/detail/123">Test Title 1</a></h3></div>
-                            <div class="sbar">
-                                <a href="magnet:?xt=urn:btih:12345">Magnet Link</a>
-                                <span>大小: 1.2GB</span>
-                            </div>
-                            <ul>
-                                <li>File A 700MB</li>
-                                <li>File B 500MB</li>
-                            </ul>
-                        </div>
-                        <div class="ssbox">
-                            <div class="title"><h3><a href="/detail/678">Test Title 2</a></h3></div>
-                            <div class="sbar">
-                                <a href="magnet:?xt=urn:btih:67890">Magnet Link</a>
-                                <span>大小: 900MB</span>
-                            </div>
-                            <ul>
-                                <li>Episode 01 450MB</li>
-                                <li>Episode 02 450MB</li>
-                            </ul>
-                        </div>
-                    </body>
-                    </html>
-                "#);
+                .path("/s")
+                .header("dnt", "1")
+                .header("accept-language", "ja");
+            then.status(200).body("<html><body>no results</body></html>");
         });
 
-        // Perform the search against the mock server
-        let provider = ClmclmProvider::with_base_url(&server.base_url());
-        let results = provider.search("test", 1).await.unwrap();
+        // 全局默认带有 DNT，引擎的 Accept-Language 覆盖全局与内置的值
+        let provider = GenericProvider::new("headers".to_string(), format!("{}/s?q={{keyword}}", server.base_url()))
+            .with_headers(BTreeMap::from([("Accept-Language".to_string(), "ja".to_string())]));
+        provider.search("movie", 1, None).await.unwrap();
 
-        // Assert
         mock.assert();
-        assert_eq!(results.len(), 2);
-        assert_eq!(results[0].title, "Test Title 1");
-        assert_eq!(results[0].magnet_link, "magnet:?xt=urn:btih:12345");
-        assert_eq!(results[1].title, "Test Title 2");
-        assert_eq!(results[1].magnet_link, "magnet:?xt=urn:btih:67890");
     }
 
     #[tokio::test]
-    async fn test_search_no_results() {
-        // Start a mock server
+    async fn test_clmclm_categories_scraped_from_navigation() {
         let server = MockServer::start();
+        let home = server.mock(|when, then| {
+            when.method(GET).path("/");
+            then.status(200).body(
+                r#"<html><body>
+                <div class="nav">
+                    <a href="/">首页</a>
+                    <a href="/search-keyword-1-1-1.html">全部</a>
+                    <a href="/search-keyword-2-1-1.html"> 影视 </a>
+                    <a href="/cat-3.html">音乐</a>
+                    <a href="/search-keyword-2-2-1.html">影视</a>
+                    <a href="/about.html">关于</a>
+                </div>
+                </body></html>"#,
+            );
+        });
 
-        // Create a mock for a page with no items
+        let provider = ClmclmProvider::with_base_url(&server.base_url());
+        let categories = provider.fetch_categories().await.unwrap();
+
+        let pairs: Vec<_> = categories.iter().map(|c| (c.code.as_str(), c.name.as_str())).collect();
+        assert_eq!(pairs, vec![("1", "全部"), ("2", "影视"), ("3", "音乐")]);
+        home.assert();
+    }
+
+    #[test]
+    fn test_ai_confidence_parsed_and_filtered() {
+        let hash = |c: char| c.to_string().repeat(40);
+        let response = format!(
+            r#"{{"results": [
+                {{"title": "Real Movie", "magnet_link": "magnet:?xt=urn:btih:{}", "file_size": null, "source_url": null, "confidence": 0.9}},
+                {{"title": "Sidebar Ad", "magnet_link": "magnet:?xt=urn:btih:{}", "file_size": null, "source_url": null, "confidence": 0.2}},
+                {{"title": "No Score", "magnet_link": "magnet:?xt=urn:btih:{}", "file_size": null, "source_url": null}}
+            ]}}"#,
+            hash('a'),
+            hash('b'),
+            hash('c'),
+        );
+        let batch: crate::llm_service::BatchExtractBasicInfoResult = serde_json::from_str(&response).unwrap();
+        let provider = GenericProvider::new("ai".to_string(), "https://example.com/s?q={keyword}".to_string());
+
+        let results = provider.parse_ai_html_response_from_batch(batch, None).unwrap();
+        let confidences: Vec<_> = results.iter().map(|r| r.confidence).collect();
+        assert_eq!(confidences, vec![Some(0.9), Some(0.2), Some(1.0)]);
+
+        let kept = crate::filter::filter_by_confidence(results, 0.5);
+        let titles: Vec<_> = kept.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Real Movie", "No Score"]);
+    }
+
+    #[tokio::test]
+    async fn test_json_api_provider_maps_fields() {
+        let server = MockServer::start();
+        let api = server.mock(|when, then| {
+            when.method(GET).path("/api/search").query_param("q", "big buck").query_param("page", "0");
+            then.status(200).header("content-type", "application/json").body(
+                r#"{"data": {"torrents": [
+                    {"name": "Big Buck Bunny 1080p", "links": {"magnet": "magnet:?xt=urn:btih:dddddddddddddddddddddddddddddddddddddddd"}, "size": 1610612736, "added": 1700000000},
+                    {"name": "Big Buck Bunny 720p", "info_hash": "EEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEE", "size": "700 MB", "added": "2024-01-02"},
+                    {"name": "No Magnet", "size": 1}
+                ]}}"#,
+            );
+        });
+
+        let mapping = JsonApiMapping {
+            items: "$.data.torrents".to_string(),
+            title: "name".to_string(),
+            magnet: Some("links.magnet".to_string()),
+            infohash: Some("info_hash".to_string()),
+            size: Some("size".to_string()),
+            date: Some("added".to_string()),
+        };
+        let provider = JsonApiProvider::new("json".to_string(), format!("{}/api/search?q={{keyword}}&page={{page-1}}", server.base_url()), mapping);
+        let results = provider.search("big buck", 1, None).await.unwrap();
+
+        api.assert();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Big Buck Bunny 1080p");
+        assert_eq!(results[0].file_size.as_deref(), Some("1.50 GB"));
+        assert_eq!(results[0].upload_timestamp, Some(1_700_000_000));
+        assert_eq!(results[0].upload_date.as_deref(), Some("2023-11-14"));
+        assert_eq!(results[1].magnet_link, "magnet:?xt=urn:btih:eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee&dn=Big%20Buck%20Bunny%20720p");
+        assert_eq!(results[1].file_size.as_deref(), Some("700 MB"));
+        assert_eq!(results[1].upload_date.as_deref(), Some("2024-01-02"));
+        assert!(results.iter().all(|r| r.provider_name == "json"));
+    }
+
+    #[test]
+    fn test_torznab_response_parsed_into_results() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <title>Jackett</title>
+    <item>
+      <title>Big.Buck.Bunny.2008.1080p.BluRay.x264</title>
+      <guid>https://indexer.test/details/1</guid>
+      <comments>https://indexer.test/details/1</comments>
+      <pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate>
+      <size>1610612736</size>
+      <link>https://indexer.test/download/1.torrent</link>
+      <enclosure url="https://indexer.test/download/1.torrent" length="1610612736" type="application/x-bittorrent" />
+      <torznab:attr name="seeders" value="42" />
+      <torznab:attr name="magneturl" value="magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa&amp;dn=Big.Buck.Bunny" />
+    </item>
+    <item>
+      <title>Sintel 2010 720p</title>
+      <pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate>
+      <torznab:attr name="infohash" value="BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB" />
+      <torznab:attr name="size" value="734003200" />
+    </item>
+    <item>
+      <title>Torrent file only</title>
+      <enclosure url="https://indexer.test/download/3.torrent" type="application/x-bittorrent" />
+    </item>
+  </channel>
+</rss>"#;
+        let provider = TorznabProvider::new(
+            "jackett".to_string(),
+            "http://localhost:9117/api".to_string(),
+            TorznabConfig { api_key: "key".to_string(), categories: Vec::new() },
+        );
+
+        let results = provider.parse_response(xml, None).unwrap();
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0].title, "Big.Buck.Bunny.2008.1080p.BluRay.x264");
+        assert_eq!(results[0].magnet_link, "magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa&dn=Big.Buck.Bunny");
+        assert_eq!(results[0].file_size.as_deref(), Some("1.50 GB"));
+        assert_eq!(results[0].seeders, Some(42));
+        assert_eq!(results[0].upload_date.as_deref(), Some("2024-01-02"));
+        assert_eq!(results[0].source_url.as_deref(), Some("https://indexer.test/details/1"));
+
+        assert_eq!(results[1].magnet_link, "magnet:?xt=urn:btih:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb&dn=Sintel%202010%20720p");
+        assert_eq!(results[1].file_size.as_deref(), Some("700.00 MB"));
+        assert_eq!(results[1].seeders, None);
+
+        let error = provider.parse_response(r#"<error code="100" description="Invalid API Key"/>"#, None).unwrap_err();
+        assert!(error.to_string().contains("Invalid API Key"));
+    }
+
+    #[tokio::test]
+    async fn test_torznab_sends_engine_headers_and_wide_offset() {
+        let server = MockServer::start();
         let mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/search-empty-1-1-1.html");
+                .path("/api")
+                .header("x-api-gateway", "secret")
+                .query_param("offset", "429496729400");
             then.status(200)
-                .header("content-type", "text/html; charset=UTF-8")
-                .body(r#"
-                    <!DOCTYPE html>
-                    <html>
-                    <body>
-                        <p>No results found.</p>
-                    </body>
-                    </html>
-                "#);
+                .header("content-type", "application/rss+xml")
+                .body(r#"<rss version="2.0"><channel></channel></rss>"#);
         });
 
-        // Perform the search
-        let provider = ClmclmProvider::with_base_url(&server.base_url());
-        let results = provider.search("empty", 1).await.unwrap();
+        let provider = TorznabProvider::new(
+            "jackett".to_string(),
+            format!("{}/api", server.base_url()),
+            TorznabConfig { api_key: "key".to_string(), categories: Vec::new() },
+        )
+        .with_headers(BTreeMap::from([("X-Api-Gateway".to_string(), "secret".to_string())]));
 
-        // Assert
+        let results = provider.search("sintel", u32::MAX, None).await.unwrap();
         mock.assert();
         assert!(results.is_empty());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,138 @@
+// 单元测试共用的辅助类型。库（lib.rs）与命令层（main.rs）的测试各自以 `mod test_support` 引入本文件。
+
+// 两个测试目标各自只用到其中一部分
+#![allow(dead_code)]
+
+use crate::llm_service::{BatchAnalysisItem, BatchAnalysisResult, BatchExtractBasicInfoResult, LlmClient, LlmConfig};
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+type ExtractHandler = Box<dyn Fn(&str) -> Result<BatchExtractBasicInfoResult> + Send + Sync>;
+type AnalyzeHandler = Box<dyn Fn(&[BatchAnalysisItem]) -> Result<Vec<BatchAnalysisResult>> + Send + Sync>;
+
+/// 测试用的模拟 LLM 客户端
+///
+/// 只有设置了处理函数的阶段会返回结果，其余方法返回错误。每次调用都会计数并记录同时进行中的调用峰值，
+/// 设置了 `delay` 时每次调用先等待该时长（配合 `start_paused` 的测试不会真的等待）。
+#[derive(Default)]
+pub struct MockLlmClient {
+    extract: Option<ExtractHandler>,
+    analyze: Option<AnalyzeHandler>,
+    delay: Option<Duration>,
+    calls: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl MockLlmClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置 HTML 提取阶段的处理函数，参数为传入的 HTML
+    pub fn with_extract(mut self, handler: impl Fn(&str) -> Result<BatchExtractBasicInfoResult> + Send + Sync + 'static) -> Self {
+        self.extract = Some(Box::new(handler));
+        self
+    }
+
+    /// 设置批量分析阶段的处理函数
+    pub fn with_analyze(
+        mut self,
+        handler: impl Fn(&[BatchAnalysisItem]) -> Result<Vec<BatchAnalysisResult>> + Send + Sync + 'static,
+    ) -> Self {
+        self.analyze = Some(Box::new(handler));
+        self
+    }
+
+    /// 批量分析时原样返回标题，纯度固定为 `purity_score`
+    pub fn echo_analysis(purity_score: u8) -> Self {
+        Self::new().with_analyze(move |items| Ok(items.iter().map(|item| echo_result(item, purity_score)).collect()))
+    }
+
+    /// 每次调用先等待 `delay`
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// 已发生的调用次数（包括返回错误的调用）
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// 同时进行中的调用数的峰值
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+
+    /// 记录一次调用，等待设置的延迟后执行 `call`
+    async fn track<T>(&self, call: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(current, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        call()
+    }
+}
+
+/// 测试用的 LLM 配置：Gemini 接口、每批 5 条、单项超时 30 秒、并发 1
+///
+/// 需要其他值时用结构体更新语法覆盖，如 `LlmConfig { api_base: server.base_url(), ..llm_config() }`。
+pub fn llm_config() -> LlmConfig {
+    LlmConfig {
+        provider: "gemini".to_string(),
+        api_key: "test".to_string(),
+        api_base: String::new(),
+        model: "test-model".to_string(),
+        batch_size: 5,
+        single_item_timeout_secs: 30,
+        max_concurrency: Some(1),
+        max_html_chars: None,
+    }
+}
+
+/// 原样使用标题的分析结果
+pub fn echo_result(item: &BatchAnalysisItem, purity_score: u8) -> BatchAnalysisResult {
+    BatchAnalysisResult { cleaned_title: item.title.clone(), purity_score, tags: Vec::new() }
+}
+
+#[async_trait::async_trait]
+impl LlmClient for MockLlmClient {
+    async fn batch_extract_basic_info_from_html(
+        &self,
+        html_content: &str,
+        _extraction_config: &LlmConfig,
+        _max_results: Option<usize>,
+    ) -> Result<BatchExtractBasicInfoResult> {
+        self.track(|| match &self.extract {
+            Some(handler) => handler(html_content),
+            None => Err(anyhow!("MockLlmClient: extraction is not configured")),
+        })
+        .await
+    }
+
+    async fn batch_analyze_scores_and_tags(
+        &self,
+        _original_title: &str,
+        _file_list: &[String],
+        _analysis_config: &LlmConfig,
+    ) -> Result<(String, u8, Vec<String>)> {
+        self.track(|| Err(anyhow!("MockLlmClient: single-item analysis is not supported"))).await
+    }
+
+    async fn batch_analyze_multiple_items(
+        &self,
+        items: &[BatchAnalysisItem],
+        _analysis_config: &LlmConfig,
+    ) -> Result<Vec<BatchAnalysisResult>> {
+        self.track(|| match &self.analyze {
+            Some(handler) => handler(items),
+            None => Err(anyhow!("MockLlmClient: batch analysis is not configured")),
+        })
+        .await
+    }
+}
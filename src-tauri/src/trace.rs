@@ -0,0 +1,122 @@
+// 请求级日志关联：每次搜索分配一个搜索 ID，每个提供商/页码请求和 LLM 调用再分配请求 ID，
+// 日志行带上 "[搜索ID/请求ID]" 前缀，便于在并发日志中追踪一次搜索的完整过程。
+//
+// ID 保存在 tokio 的 task-local 中，`join_all`、`buffered` 等在同一任务内并发的 future 各自持有自己的作用域；
+// 通过 `tokio::spawn` 启动的新任务不会继承，需要重新进入作用域。
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+tokio::task_local! {
+    static SEARCH_ID: String;
+    static REQUEST_ID: String;
+}
+
+static NEXT_REQUEST: AtomicU64 = AtomicU64::new(1);
+
+/// 带关联 ID 前缀输出一行日志
+macro_rules! trace_println {
+    ($($arg:tt)*) => {
+        $crate::trace::emit(&format!($($arg)*))
+    };
+}
+pub(crate) use trace_println;
+
+/// 生成新的搜索 ID（8 位十六进制）
+pub fn new_search_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// 生成进程内唯一的请求 ID，如 "llm-12"
+pub fn next_request_id(kind: &str) -> String {
+    format!("{kind}-{}", NEXT_REQUEST.fetch_add(1, Ordering::Relaxed))
+}
+
+/// 当前作用域的搜索 ID
+pub fn current_search_id() -> Option<String> {
+    SEARCH_ID.try_with(Clone::clone).ok()
+}
+
+/// 当前作用域的请求 ID，嵌套的请求以 "/" 连接
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// 在指定搜索 ID 的作用域中运行 `future`
+pub async fn with_search_id<F: Future>(search_id: String, future: F) -> F::Output {
+    SEARCH_ID.scope(search_id, future).await
+}
+
+/// 已在某次搜索的作用域中时沿用它的 ID，否则分配新的搜索 ID
+pub async fn in_search_scope<F: Future>(future: F) -> F::Output {
+    match current_search_id() {
+        Some(_) => future.await,
+        None => with_search_id(new_search_id(), future).await,
+    }
+}
+
+/// 在请求 ID 的作用域中运行 `future`，嵌套在其他请求中时拼接到上级请求 ID 之后
+pub async fn with_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    let request_id = match current_request_id() {
+        Some(parent) => format!("{parent}/{request_id}"),
+        None => request_id,
+    };
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// 当前作用域的日志前缀，不在任何作用域中时为空
+pub fn prefix() -> String {
+    match (current_search_id(), current_request_id()) {
+        (Some(search), Some(request)) => format!("[{search}/{request}] "),
+        (Some(search), None) => format!("[{search}] "),
+        (None, Some(request)) => format!("[{request}] "),
+        (None, None) => String::new(),
+    }
+}
+
+/// 输出一行带关联 ID 的日志
+pub fn emit(message: &str) {
+    let line = format!("{}{message}", prefix());
+    println!("{line}");
+    #[cfg(test)]
+    captured::push(line);
+}
+
+/// 测试中收集输出的日志行，按搜索 ID 过滤即可不受并行测试干扰
+#[cfg(test)]
+pub(crate) mod captured {
+    use std::sync::Mutex;
+
+    static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    pub fn push(line: String) {
+        LINES.lock().unwrap().push(line);
+    }
+
+    pub fn lines_containing(needle: &str) -> Vec<String> {
+        LINES.lock().unwrap().iter().filter(|line| line.contains(needle)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nested_request_ids_share_search_id() {
+        let prefixes = with_search_id("abc12345".to_string(), async {
+            let outer = prefix();
+            let (a, b) = futures::join!(
+                with_request_id("p1".to_string(), async { with_request_id("llm-1".to_string(), async { prefix() }).await }),
+                with_request_id("p2".to_string(), async { prefix() }),
+            );
+            (outer, a, b)
+        })
+        .await;
+
+        assert_eq!(prefixes.0, "[abc12345] ");
+        assert_eq!(prefixes.1, "[abc12345/p1/llm-1] ");
+        assert_eq!(prefixes.2, "[abc12345/p2] ");
+        assert_eq!(prefix(), "");
+    }
+}
@@ -0,0 +1,38 @@
+// src-tauri/tests/search_core.rs
+// 在没有任何 Tauri 上下文的情况下使用库目标的搜索流程
+
+use ai_magnet_assistant_lib::searcher::{create_ai_enhanced_search_core, CustomEngineConfig, EmptyTitlePolicy};
+use httpmock::prelude::*;
+
+#[tokio::test]
+async fn test_search_core_without_tauri() {
+    let server = MockServer::start();
+    let hash_a = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    let hash_b = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    let page = server.mock(|when, then| {
+        when.method(GET).path("/search").query_param("q", "ubuntu").query_param("p", "1");
+        then.status(200).body(format!(
+            r#"<html><body><table>
+                <tr><td><a href="/t/1">Ubuntu 24.04 Desktop ISO</a></td><td>5.7 GB</td><td><a href="magnet:?xt=urn:btih:{hash_a}">magnet</a></td></tr>
+                <tr><td><a href="/t/2">Ubuntu 24.04 Server ISO</a></td><td>2.6 GB</td><td><a href="magnet:?xt=urn:btih:{hash_b}">magnet</a></td></tr>
+            </table></body></html>"#
+        ));
+    });
+
+    let core = create_ai_enhanced_search_core(
+        None,
+        None,
+        Vec::new(),
+        vec![CustomEngineConfig::new("mock".to_string(), format!("{}/search?q={{keyword}}&p={{page}}", server.base_url()))],
+        None,
+        EmptyTitlePolicy::default(),
+    );
+    let results = core.search_multi_page("ubuntu", 1).await.unwrap();
+
+    page.assert();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].title, "Ubuntu 24.04 Desktop ISO");
+    assert_eq!(results[0].file_size.as_deref(), Some("5.7 GB"));
+    assert!(results.iter().all(|r| r.provider_name == "mock"));
+}